@@ -0,0 +1,149 @@
+//! running a command for many documents at once
+use super::*;
+
+/// `*` matches any run of characters, `?` a single one
+fn wildcard_matches(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.split_first(), name.split_first()) {
+        (None, _) => name.is_empty(),
+        (Some(('*', rest)), _) => {
+            (0..=name.len()).any(|skipped| wildcard_matches(rest, &name[skipped..]))
+        }
+        (Some(_), None) => false,
+        (Some(('?', rest)), Some((_, name_rest))) => wildcard_matches(rest, name_rest),
+        (Some((expected, rest)), Some((found, name_rest))) => {
+            expected == found && wildcard_matches(rest, name_rest)
+        }
+    }
+}
+
+/// files tlumok writes next to the documents (workspaces and their backups, exports, translated documents
+/// with the default template) all have it in their names
+fn is_tlumok_file(name: &str) -> bool {
+    name.contains(".tlumok.") || name.contains(".tlumok-")
+}
+
+/// translated documents already written for the documents (with a workspace) among `files`,
+/// they match the same wildcards as the originals when the output template keeps their names
+async fn previous_outputs(
+    files: &[PathBuf],
+    config: &TlumokConfig,
+) -> std::collections::HashSet<PathBuf> {
+    let mut outputs = std::collections::HashSet::new();
+    for file in files {
+        let original_document = match OriginalDocument::from_file(file) {
+            Ok(original_document) => original_document,
+            Err(_) => continue,
+        };
+        let workspace_path = TranslationWorkspace::default_path_for_document(&original_document);
+        if !workspace_path.exists() {
+            continue;
+        }
+        let found = async {
+            let workspace = TranslationWorkspace::load(&workspace_path).await?;
+            config
+                .clone()
+                .for_document(file)?
+                .output_template()
+                .existing_outputs(&workspace)
+        }
+        .await;
+        match found {
+            Ok(found) => outputs.extend(found),
+            Err(e) => tracing::warn!("looking for translated documents of [{file:?}] :: {e:?}"),
+        }
+    }
+    outputs
+}
+
+/// expands wildcards in file names (not in directories), for shells that don't do it themselves -
+/// tlumok's own files and documents it translated are left out
+pub async fn expand_patterns(files: Vec<PathBuf>, config: &TlumokConfig) -> Result<Vec<PathBuf>> {
+    let mut expanded = vec![];
+    for file in files {
+        let pattern = file
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if !pattern.contains(['*', '?']) {
+            expanded.push(file);
+            continue;
+        }
+        let directory = match file.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_owned(),
+            _ => PathBuf::from("."),
+        };
+        let pattern = pattern.chars().collect::<Vec<_>>();
+        let mut matching = vec![];
+        for entry in
+            std::fs::read_dir(&directory).wrap_err_with(|| format!("reading [{directory:?}]"))?
+        {
+            let path = entry.wrap_err("reading dir entry")?.path();
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if path.is_file()
+                && !is_tlumok_file(&name)
+                && wildcard_matches(&pattern, &name.chars().collect::<Vec<_>>())
+            {
+                matching.push(path);
+            }
+        }
+        let outputs = previous_outputs(&matching, config).await;
+        matching.retain(|path| {
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            !outputs.contains(&canonical) && !outputs.contains(path)
+        });
+        if matching.is_empty() {
+            eyre::bail!("no files match [{file:?}]");
+        }
+        matching.sort();
+        expanded.extend(matching);
+    }
+    Ok(expanded)
+}
+
+/// runs `run` for every document, one failing doesn't stop the rest,
+/// all failures are reported at the end
+pub async fn for_each_document<F, Fut>(
+    files: Vec<PathBuf>,
+    config: &TlumokConfig,
+    mut run: F,
+) -> Result<()>
+where
+    F: FnMut(PathBuf) -> Fut,
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    let files = expand_patterns(files, config).await?;
+    if let [file] = &files[..] {
+        return run(file.clone()).await;
+    }
+    let mut failed = vec![];
+    for (done, file) in files.iter().enumerate() {
+        if shutdown::is_requested() {
+            eyre::bail!(
+                "interrupted, {} of {} documents done",
+                done - failed.len(),
+                files.len()
+            );
+        }
+        if let Err(e) = run(file.clone()).await {
+            tracing::error!("[{file:?}] :: {e:?}");
+            failed.push(format!("{}: {e:#}", file.display()));
+        }
+    }
+    println!(
+        "{} of {} documents done",
+        files.len() - failed.len(),
+        files.len()
+    );
+    if !failed.is_empty() {
+        eyre::bail!(
+            "{} of {} documents failed:\n{}",
+            failed.len(),
+            files.len(),
+            failed.join("\n")
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,147 @@
+//! removing a workspace and everything generated from it
+use super::*;
+
+impl OutputTemplate {
+    /// translated documents of the workspace already written with this template,
+    /// the `{date}` token matches any date since it differs on every save - the original document
+    /// and the workspace are never among them, even if the template matches their names
+    pub fn existing_outputs(&self, workspace: &TranslationWorkspace) -> Result<Vec<PathBuf>> {
+        let own_files = WorkspaceStorage::all()
+            .map(|storage| {
+                TranslationWorkspace::path_for_document(&workspace.original_document, storage)
+            })
+            .chain([workspace.original_document.path.clone()])
+            .filter_map(|path| path.canonicalize().ok())
+            .collect::<Vec<_>>();
+        let example = self.path_for(workspace);
+        let directory = match example.parent() {
+            Some(directory) if directory.is_dir() => directory,
+            _ => return Ok(vec![]),
+        };
+        let template = Self {
+            filename_template: self.filename_template.replace("{date}", "\u{0}"),
+            ..self.clone()
+        }
+        .path_for(workspace);
+        let template = template
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let parts = template.split('\u{0}').collect::<Vec<_>>();
+        let mut outputs = vec![];
+        for entry in
+            std::fs::read_dir(directory).wrap_err_with(|| format!("reading [{directory:?}]"))?
+        {
+            let path = entry.wrap_err("reading dir entry")?.path();
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let own_file = path
+                .canonicalize()
+                .map(|path| own_files.contains(&path))
+                .unwrap_or_default();
+            if path.is_file() && !own_file && matches_parts(&name, &parts) {
+                outputs.push(path);
+            }
+        }
+        outputs.sort();
+        Ok(outputs)
+    }
+}
+
+/// `name` consists of `parts` in order, with a date (see [FILE_SAFE_DATETIME]) in between each of them
+fn matches_parts(name: &str, parts: &[&str]) -> bool {
+    match parts {
+        [] => name.is_empty(),
+        [last] => name == *last,
+        [first, rest @ ..] => match name.strip_prefix(first) {
+            // the part after the date could start with a digit too, so every length is tried
+            Some(remaining) => remaining
+                .char_indices()
+                .map(|(at, _)| at)
+                .chain([remaining.len()])
+                .any(|at| is_date(&remaining[..at]) && matches_parts(&remaining[at..], rest)),
+            None => false,
+        },
+    }
+}
+
+fn is_date(text: &str) -> bool {
+    chrono::NaiveDateTime::parse_from_str(text, FILE_SAFE_DATETIME).is_ok()
+}
+
+/// what [TranslationWorkspace::artifacts] should include besides the workspace files
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanOptions {
+    /// translated documents and the xliff export
+    pub outputs: bool,
+    pub project_dictionary: bool,
+}
+
+impl TranslationWorkspace {
+    /// files and directories belonging to the workspace of the document, all of them exist
+    pub fn artifacts(
+        &self,
+        output_template: &OutputTemplate,
+        CleanOptions {
+            outputs,
+            project_dictionary,
+        }: CleanOptions,
+    ) -> Result<Vec<PathBuf>> {
+        // a workspace could have been saved in more than one format over time
+        let mut artifacts = WorkspaceStorage::all()
+            .map(|storage| Self::path_for_document(&self.original_document, storage))
+            .filter(|path| path.exists())
+            .collect::<Vec<_>>();
+        if outputs {
+            artifacts.extend(output_template.existing_outputs(self)?);
+            artifacts.push(Self::default_xliff_path_for_document(
+                &self.original_document,
+            ));
+        }
+        if project_dictionary {
+            let TlumokTranslationOptions {
+                source_language,
+                target_language,
+                ..
+            } = self.translation_options;
+            artifacts.push(
+                crate::key_value_cache::cache_service::project_dictionary_path(
+                    &self.original_document.path,
+                    (source_language, target_language),
+                )?,
+            );
+        }
+        artifacts.retain(|path| path.exists());
+        artifacts.dedup();
+        Ok(artifacts)
+    }
+}
+
+pub fn remove_artifacts(artifacts: &[PathBuf]) -> Result<()> {
+    for path in artifacts {
+        match path.is_dir() {
+            true => std::fs::remove_dir_all(path),
+            false => std::fs::remove_file(path),
+        }
+        .wrap_err_with(|| format!("removing [{path:?}]"))?;
+        tracing::info!("removed [{path:?}]");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_have_to_match_the_date_format() {
+        let parts = ["doc-", ".pl.txt"];
+        assert!(matches_parts("doc-2026-10-17--22-09-05.pl.txt", &parts));
+        assert!(!matches_parts("doc-notes.pl.txt", &parts));
+        assert!(!matches_parts("doc-.pl.txt", &parts));
+        assert!(matches_parts("2026-10-17--22-09-05-1.txt", &["", "-1.txt"]));
+        assert!(matches_parts("doc.txt", &["doc.txt"]));
+    }
+}
@@ -0,0 +1,244 @@
+//! what changed in a translated document since the previous time it was written,
+//! so a review pass can be checked for changes nobody intended
+use super::*;
+use indexmap::IndexSet;
+
+/// lines of unchanged text shown around every change
+const CONTEXT_LINES: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// shortest list of changes turning `old` into `new` (myers' algorithm)
+pub fn diff<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Change<'a>> {
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let (old_middle, new_middle) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+    old[..prefix]
+        .iter()
+        .map(|line| Change::Same(line))
+        .chain(diff_middle(old_middle, new_middle))
+        .chain(
+            old[old.len() - suffix..]
+                .iter()
+                .map(|line| Change::Same(line)),
+        )
+        .collect()
+}
+
+fn diff_middle<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Change<'a>> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = n + m;
+    if max == 0 {
+        return vec![];
+    }
+    let index = |k: isize| (k + max) as usize;
+    let goes_down =
+        |v: &[isize], k: isize, d: isize| k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]);
+    let mut v = vec![0isize; 2 * max as usize + 2];
+    // furthest reaching paths of every step, walked back to recover the changes
+    let mut trace = vec![];
+    'search: for d in 0..=max {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = match goes_down(&v, k, d) {
+                true => v[index(k + 1)],
+                false => v[index(k - 1)] + 1,
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+    let mut changes = vec![];
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let previous_k = match goes_down(v, k, d) {
+            true => k + 1,
+            false => k - 1,
+        };
+        let previous_x = v[index(previous_k)];
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            x -= 1;
+            y -= 1;
+            changes.push(Change::Same(old[x as usize]));
+        }
+        if d > 0 {
+            match x == previous_x {
+                true => changes.push(Change::Added(new[previous_y as usize])),
+                false => changes.push(Change::Removed(old[previous_x as usize])),
+            }
+        }
+        x = previous_x;
+        y = previous_y;
+    }
+    changes.reverse();
+    changes
+}
+
+/// changes with a few lines of context around them, `@@ line N @@` starts every group,
+/// empty when nothing changed
+pub fn unified(changes: &[Change]) -> String {
+    let near_change = |position: usize| {
+        changes[position.saturating_sub(CONTEXT_LINES)
+            ..changes.len().min(position + CONTEXT_LINES + 1)]
+            .iter()
+            .any(|change| !matches!(change, Change::Same(_)))
+    };
+    let mut output = String::new();
+    let mut new_line = 0;
+    let mut shown_previous = false;
+    for (position, change) in changes.iter().enumerate() {
+        if !matches!(change, Change::Removed(_)) {
+            new_line += 1;
+        }
+        let shown = near_change(position);
+        if shown && !shown_previous {
+            output.push_str(&format!("@@ line {} @@\n", new_line.max(1)));
+        }
+        shown_previous = shown;
+        if !shown {
+            continue;
+        }
+        let (sign, line) = match change {
+            Change::Same(line) => (' ', line),
+            Change::Removed(line) => ('-', line),
+            Change::Added(line) => ('+', line),
+        };
+        output.push_str(&format!("{sign} {line}\n"));
+    }
+    output
+}
+
+/// lines of a document that are compared, the translatable texts for packages since
+/// their xml barely has any lines at all - keyed by the package entry they come from
+fn compared_lines(file_format: FileFormat, content: &[u8]) -> Result<Vec<(String, Vec<String>)>> {
+    if !file_format.is_package() {
+        return Ok(vec![(
+            String::new(),
+            String::from_utf8_lossy(content)
+                .lines()
+                .map(ToString::to_string)
+                .collect(),
+        )]);
+    }
+    Ok(formats::content_documents(file_format, content)?
+        .into_iter()
+        .map(|(entry, document)| {
+            let texts = formats::translatable_ranges(file_format, &document)
+                .into_iter()
+                .map(|range| document[range].to_string())
+                .collect();
+            (entry, texts)
+        })
+        .collect())
+}
+
+/// lines of the entry, none when the document doesn't have it
+fn lines_of<'a>(documents: &'a [(String, Vec<String>)], entry: &str) -> Vec<&'a str> {
+    documents
+        .iter()
+        .find(|(name, _)| name == entry)
+        .map(|(_, lines)| lines.iter().map(String::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// report of what changed between two translated documents of the same format,
+/// [None] when their texts are the same
+pub async fn compare_documents(
+    file_format: FileFormat,
+    previous: &Path,
+    current: &Path,
+) -> Result<Option<String>> {
+    let read = |path: &Path| {
+        let path = path.to_owned();
+        async move {
+            tokio::fs::read(&path)
+                .await
+                .wrap_err_with(|| format!("reading [{path:?}]"))
+                .and_then(|content| {
+                    compared_lines(file_format, &content)
+                        .wrap_err_with(|| format!("reading text of [{path:?}]"))
+                })
+        }
+    };
+    let (previous_documents, current_documents) = (read(previous).await?, read(current).await?);
+    let mut report = String::new();
+    let (mut added, mut removed) = (0, 0);
+    let entries = previous_documents
+        .iter()
+        .chain(current_documents.iter())
+        .map(|(entry, _)| entry.as_str())
+        .collect::<IndexSet<_>>();
+    for entry in entries {
+        let changes = diff(
+            &lines_of(&previous_documents, entry),
+            &lines_of(&current_documents, entry),
+        );
+        added += changes
+            .iter()
+            .filter(|change| matches!(change, Change::Added(_)))
+            .count();
+        removed += changes
+            .iter()
+            .filter(|change| matches!(change, Change::Removed(_)))
+            .count();
+        let changed = unified(&changes);
+        if changed.is_empty() {
+            continue;
+        }
+        if !entry.is_empty() {
+            report.push_str(&format!("=== {entry}\n"));
+        }
+        report.push_str(&changed);
+    }
+    if added == 0 && removed == 0 {
+        return Ok(None);
+    }
+    Ok(Some(format!(
+        "--- {previous:?}\n+++ {current:?}\n{report}{removed} lines removed, {added} added"
+    )))
+}
+
+impl OutputTemplate {
+    /// most recently written translated document of the workspace other than `current`
+    pub fn previous_output(
+        &self,
+        workspace: &TranslationWorkspace,
+        current: &Path,
+    ) -> Result<Option<PathBuf>> {
+        let mut outputs = self
+            .existing_outputs(workspace)?
+            .into_iter()
+            .filter(|output| output != current)
+            .map(|output| modified_at(&output).map(|modified| (modified, output)))
+            .collect::<Result<Vec<_>>>()?;
+        outputs.sort();
+        Ok(outputs.pop().map(|(_, output)| output))
+    }
+}
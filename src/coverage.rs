@@ -0,0 +1,153 @@
+use super::*;
+
+/// how close the best dictionary match of a segment is, like the analysis of most CAT tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CoverageBucket {
+    Exact,
+    /// 95% - 99% similar
+    Fuzzy95,
+    /// 75% - 94% similar
+    Fuzzy75,
+    NoMatch,
+}
+
+impl CoverageBucket {
+    pub const ALL: [Self; 4] = [Self::Exact, Self::Fuzzy95, Self::Fuzzy75, Self::NoMatch];
+
+    pub fn for_percent(percent: u32) -> Self {
+        match percent {
+            100.. => Self::Exact,
+            95..=99 => Self::Fuzzy95,
+            75..=94 => Self::Fuzzy75,
+            _ => Self::NoMatch,
+        }
+    }
+}
+
+impl std::fmt::Display for CoverageBucket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Exact => "exact",
+                Self::Fuzzy95 => "95-99%",
+                Self::Fuzzy75 => "75-94%",
+                Self::NoMatch => "no match",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct BucketStats {
+    pub segments: usize,
+    pub characters: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub total: BucketStats,
+    pub buckets: IndexMap<CoverageBucket, BucketStats>,
+}
+
+/// similarity of the closest known text, 100 being identical
+pub fn best_match_percent(text: &str, known: &std::collections::HashSet<String>) -> u32 {
+    if known.contains(text) {
+        return 100;
+    }
+    best_match(text, known.iter().map(String::as_str))
+        .map(|(_, percent)| percent)
+        .unwrap_or_default()
+}
+
+/// closest candidate and its similarity, only identical texts get 100
+pub fn best_match<'a>(
+    text: &str,
+    candidates: impl Iterator<Item = &'a str>,
+) -> Option<(&'a str, u32)> {
+    let len = text.chars().count();
+    candidates
+        .filter(|candidate| {
+            // texts of very different lengths can't reach any of the fuzzy buckets
+            let candidate_len = candidate.chars().count();
+            candidate_len.min(len) * 4 >= candidate_len.max(len) * 3
+        })
+        .map(|candidate| {
+            let percent = match candidate == text {
+                true => 100,
+                false => (strsim::normalized_levenshtein(text, candidate) * 100.0)
+                    .floor()
+                    .min(99.0) as u32,
+            };
+            (candidate, percent)
+        })
+        .max_by_key(|(_, percent)| *percent)
+}
+
+impl TranslationWorkspace {
+    /// matches every segment against all dictionaries of the workspace's language pair
+    pub async fn coverage(
+        &self,
+        dictionary_service: translation_service::DictionaryService,
+    ) -> Result<CoverageReport> {
+        let TlumokTranslationOptions {
+            source_language,
+            target_language,
+            ..
+        } = self.translation_options;
+        let known = dictionary_service
+            .all_original_texts((source_language, target_language))
+            .await
+            .wrap_err("reading dictionaries")?;
+        let mut report = CoverageReport {
+            buckets: CoverageBucket::ALL
+                .into_iter()
+                .map(|bucket| (bucket, BucketStats::default()))
+                .collect(),
+            ..Default::default()
+        };
+        tokio::task::block_in_place(|| {
+            for segment in self.segments.segments.values() {
+                if segment.original_text.trim().is_empty() {
+                    continue;
+                }
+                let characters = segment.original_text.chars().count();
+                let bucket =
+                    CoverageBucket::for_percent(best_match_percent(&segment.original_text, &known));
+                for stats in [&mut report.total, &mut report.buckets[&bucket]] {
+                    stats.segments += 1;
+                    stats.characters += characters;
+                }
+            }
+        });
+        Ok(report)
+    }
+}
+
+fn percent_of(part: usize, total: usize) -> f64 {
+    match total {
+        0 => 0.0,
+        total => part as f64 * 100.0 / total as f64,
+    }
+}
+
+pub fn print_report(CoverageReport { total, buckets }: &CoverageReport) {
+    let row = |name: &str, stats: &BucketStats| {
+        println!(
+            "{name:<10} {:>10} {:>7.1}% {:>12} {:>7.1}%",
+            stats.segments,
+            percent_of(stats.segments, total.segments),
+            stats.characters,
+            percent_of(stats.characters, total.characters),
+        )
+    };
+    println!(
+        "{:<10} {:>10} {:>8} {:>12} {:>8}",
+        "match", "segments", "", "characters", ""
+    );
+    for (bucket, stats) in buckets.iter() {
+        row(&bucket.to_string(), stats);
+    }
+    row("total", total);
+}
@@ -0,0 +1,151 @@
+//! languages deepl can translate from and into, fetched once and cached in the data directory,
+//! so an unsupported pair fails before anything is sent instead of in the middle of a run
+use super::*;
+use deepl_api::{
+    DeepL,
+    LanguageList,
+    LanguageType,
+};
+use translation_service::{
+    Language,
+    LanguagePair,
+    TranslationService,
+};
+
+/// the list rarely changes, so it's only fetched again after this many days
+const MAX_AGE_DAYS: i64 = 30;
+pub const CACHE_FILENAME: &str = "deepl-languages.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportedLanguages {
+    /// deepl language codes, eg. `PL`
+    pub source: Vec<String>,
+    /// can be regional, eg. `EN-GB`
+    pub target: Vec<String>,
+    pub fetched: AppTime,
+}
+
+/// what's known about the languages deepl supports, see [TranslationService::check_language_pair]
+#[derive(Debug, Clone, Default)]
+pub enum LanguageSupport {
+    #[default]
+    NotLoaded,
+    Loaded(SupportedLanguages),
+    /// the list couldn't be fetched, it's not asked for again until the next run
+    Unavailable,
+}
+
+fn supports(codes: &[String], language: Language) -> bool {
+    let code = language.to_deepl_language_static();
+    codes.iter().any(|supported| {
+        let supported = supported.to_uppercase();
+        supported == code || supported.starts_with(&format!("{code}-"))
+    })
+}
+
+impl SupportedLanguages {
+    fn cache_path() -> Result<PathBuf> {
+        Ok(filesystem::data_directory()?.join(CACHE_FILENAME))
+    }
+
+    /// [None] when there is no cached list or it's too old
+    fn load_cached() -> Option<Self> {
+        let path = Self::cache_path().ok()?;
+        let cached = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())?;
+        (now() - cached.fetched < chrono::Duration::days(MAX_AGE_DAYS)).then_some(cached)
+    }
+
+    async fn save(&self) -> Result<()> {
+        let path = Self::cache_path()?;
+        filesystem::write_atomically(
+            &path,
+            serde_json::to_string_pretty(self)
+                .wrap_err("serializing deepl languages")?
+                .as_bytes(),
+        )
+        .await
+    }
+
+    async fn fetch(deepl: &DeepL) -> Result<Self> {
+        let codes = |languages: LanguageList| {
+            languages
+                .into_iter()
+                .map(|language| language.language)
+                .collect()
+        };
+        let source = deepl
+            .languages(LanguageType::Source)
+            .await
+            .map_err(|e| eyre::eyre!("{e:?}"))
+            .wrap_err("getting source languages from deepl")?;
+        let target = deepl
+            .languages(LanguageType::Target)
+            .await
+            .map_err(|e| eyre::eyre!("{e:?}"))
+            .wrap_err("getting target languages from deepl")?;
+        Ok(Self {
+            source: codes(source),
+            target: codes(target),
+            fetched: now(),
+        })
+    }
+
+    pub fn check(&self, (source_language, target_language): LanguagePair) -> Result<()> {
+        if source_language == target_language {
+            eyre::bail!("source and target language are both [{source_language}]");
+        }
+        if !supports(&self.source, source_language) {
+            eyre::bail!(
+                "deepl can't translate from [{source_language}], supported source languages: {}",
+                self.source.join(", ")
+            );
+        }
+        if !supports(&self.target, target_language) {
+            eyre::bail!(
+                "deepl can't translate into [{target_language}], supported target languages: {}",
+                self.target.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
+
+impl TranslationService {
+    /// fails when deepl can't translate between the languages, when the list of supported
+    /// languages can't be fetched the pair is let through (deepl will complain on its own),
+    /// and it's not fetched again for the rest of the run
+    pub async fn check_language_pair(
+        &self,
+        deepl: &DeepL,
+        language_pair: LanguagePair,
+    ) -> Result<()> {
+        let mut supported_languages = self.supported_languages.lock().await;
+        if let LanguageSupport::NotLoaded = *supported_languages {
+            *supported_languages = match SupportedLanguages::load_cached() {
+                Some(cached) => LanguageSupport::Loaded(cached),
+                None => match SupportedLanguages::fetch(deepl).await {
+                    Ok(fetched) => {
+                        if let Err(e) = fetched.save().await {
+                            tracing::warn!(
+                                "{e:?}, deepl languages will be fetched again next time"
+                            );
+                        }
+                        LanguageSupport::Loaded(fetched)
+                    }
+                    Err(e) => {
+                        tracing::warn!("{e:?}, language pairs will not be checked");
+                        LanguageSupport::Unavailable
+                    }
+                },
+            };
+        }
+        match &*supported_languages {
+            LanguageSupport::Loaded(supported_languages) => {
+                supported_languages.check(language_pair)
+            }
+            LanguageSupport::NotLoaded | LanguageSupport::Unavailable => Ok(()),
+        }
+    }
+}
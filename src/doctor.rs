@@ -0,0 +1,156 @@
+//! `tlumok doctor` - goes through everything tlumok needs to work and says what to fix
+use super::*;
+
+/// scratch entries created by the checks, removed right after
+const PROBE_NAME: &str = ".tlumok-doctor";
+
+/// prints a single line of the checklist, returns whether the check passed
+fn report(name: &str, result: Result<String>, hint: &str) -> bool {
+    match result {
+        Ok(details) => {
+            println!("[ok]   {name}: {details}");
+            true
+        }
+        Err(e) => {
+            println!("[fail] {name}: {e:#}");
+            println!("       hint: {hint}");
+            false
+        }
+    }
+}
+
+fn skip(name: &str, reason: &str) {
+    println!("[skip] {name}: {reason}");
+}
+
+fn check_config_file() -> Result<String> {
+    let path = TlumokConfig::default_config_path()?;
+    match path.exists() {
+        true => Ok(path.display().to_string()),
+        false => eyre::bail!("there is no config file at [{path:?}]"),
+    }
+}
+
+async fn check_deepl(api_key: &str) -> Result<String> {
+    let usage_information = deepl_api::DeepL::new(api_key.to_string())
+        .usage_information()
+        .await
+        .map_err(|e| eyre::eyre!("{e:?}"))
+        .wrap_err("getting usage information from deepl")?;
+    Ok(format!(
+        "{} of {} characters used this period",
+        usage_information.character_count, usage_information.character_limit
+    ))
+}
+
+/// writes and removes a file, which is all tlumok does with these directories
+fn check_writable(directory: Result<PathBuf>) -> Result<String> {
+    let directory = directory?;
+    let probe = directory.join(PROBE_NAME);
+    std::fs::write(&probe, "").wrap_err_with(|| format!("writing to [{directory:?}]"))?;
+    std::fs::remove_file(&probe).wrap_err_with(|| format!("removing [{probe:?}]"))?;
+    Ok(directory.display().to_string())
+}
+
+/// goes around [key_value_cache::cache_service::dictionary_at_path] so the test dictionary
+/// isn't kept open (and flushed after it's removed) until the process exits
+fn check_dictionary_storage() -> Result<String> {
+    let path = filesystem::dictionaries_directory()?.join(PROBE_NAME);
+    let written = (|| -> Result<bool> {
+        let db =
+            sled::open(&path).wrap_err_with(|| format!("opening test dictionary [{path:?}]"))?;
+        db.insert(PROBE_NAME, PROBE_NAME)
+            .wrap_err("writing to the test dictionary")?;
+        let read = db
+            .get(PROBE_NAME)
+            .wrap_err("reading from the test dictionary")?;
+        Ok(read.as_deref() == Some(PROBE_NAME.as_bytes()))
+    })();
+    if path.exists() {
+        std::fs::remove_dir_all(&path).wrap_err_with(|| format!("removing [{path:?}]"))?;
+    }
+    match written? {
+        true => Ok("test dictionary written and read back".to_string()),
+        false => eyre::bail!("test dictionary did not return what was written to it"),
+    }
+}
+
+/// runs every check (later ones are skipped when what they need is broken), fails if any of them did
+pub async fn run(api_key: Option<String>, profile: Option<String>) -> Result<()> {
+    let mut failed = 0;
+    let mut check = |passed: bool| {
+        if !passed {
+            failed += 1;
+        }
+    };
+    check(report(
+        "config file",
+        check_config_file(),
+        "run `tlumok generate-default-tlumok-config` and fill in `deepl_api_key`",
+    ));
+    let config = TlumokConfig::resolve(api_key, profile);
+    let config = match config {
+        Ok(config) => {
+            check(report("config", Ok("valid".to_string()), ""));
+            Some(config)
+        }
+        Err(e) => {
+            check(report(
+                "config",
+                Err(e),
+                "fix the setting mentioned above, or remove the file and generate a new one",
+            ));
+            None
+        }
+    };
+    let api_key = match config.as_ref() {
+        Some(config) => {
+            let api_key =
+                TlumokConfig::checked_api_key(&config.deepl_api_key).map(ToString::to_string);
+            check(report(
+                "deepl api key",
+                api_key
+                    .as_ref()
+                    .map(|_| "set".to_string())
+                    .map_err(|e| eyre::eyre!("{e:#}")),
+                "an api key can be found in the account settings on deepl.com",
+            ));
+            api_key.ok()
+        }
+        None => {
+            skip("deepl api key", "config could not be loaded");
+            None
+        }
+    };
+    match api_key {
+        Some(api_key) => check(report(
+            "deepl connection",
+            check_deepl(&api_key).await,
+            "make sure the key is right (free keys end with `:fx`) and that deepl.com is reachable",
+        )),
+        None => skip("deepl connection", "there is no api key"),
+    }
+    check(report(
+        "data directory",
+        check_writable(filesystem::data_directory()),
+        &format!(
+            "set {} to a directory you can write to, tlumok keeps its dictionaries and logs there",
+            filesystem::DATA_DIR_ENV_VAR
+        ),
+    ));
+    check(report(
+        "dictionaries directory",
+        check_writable(filesystem::dictionaries_directory()),
+        "make sure the `dictionaries` directory in the data directory is writable",
+    ));
+    check(report(
+        "dictionary storage",
+        check_dictionary_storage(),
+        "close other tlumok windows, a dictionary can only be open in one process at a time",
+    ));
+    if failed > 0 {
+        eyre::bail!("failed checks: {failed}");
+    }
+    println!("everything looks fine");
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! translated file names for the output, see [OutputTemplate::TRANSLATED_STEM]
+use super::*;
+
+/// can't be a part of a file name on at least one of the systems tlumok runs on
+const FORBIDDEN_CHARACTERS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// words of a file stem and the character they were joined with, eg. `getting-started`
+fn stem_words(stem: &str) -> (String, Option<char>) {
+    match ['-', '_']
+        .into_iter()
+        .find(|separator| stem.contains(*separator) && !stem.contains(' '))
+    {
+        Some(separator) => (stem.replace(separator, " "), Some(separator)),
+        None => (stem.to_string(), None),
+    }
+}
+
+/// joins the translated words back the way the original stem was, leaving out what a file name can't have
+fn file_name_safe(translated: &str, separator: Option<char>) -> Result<String> {
+    let words = translated
+        .split_whitespace()
+        .map(|word| word.replace(FORBIDDEN_CHARACTERS, ""))
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    let stem = match separator {
+        Some(separator) => words.join(&separator.to_string()),
+        None => words.join(" "),
+    };
+    let stem = stem.trim_end_matches('.').to_string();
+    if stem.is_empty() {
+        eyre::bail!("translation [{translated}] leaves nothing for a file name");
+    }
+    Ok(stem)
+}
+
+impl OutputTemplate {
+    /// original file name without the extension, translated and cached in
+    /// [TranslationWorkspace::translated_stem], falls back to `{stem}` until then
+    pub const TRANSLATED_STEM: &'static str = "{translated_stem}";
+    pub fn translates_stem(&self) -> bool {
+        self.filename_template.contains(Self::TRANSLATED_STEM)
+    }
+}
+
+/// translation of the workspace's file stem, the project dictionary is checked first,
+/// then the global one and only then deepl
+pub async fn translate_stem(
+    workspace: &TranslationWorkspace,
+    translation_service: TranslationService,
+) -> Result<String> {
+    let OriginalDocument { path, .. } = &workspace.original_document;
+    let TlumokTranslationOptions {
+        source_language,
+        target_language,
+        ..
+    } = workspace.translation_options;
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .ok_or_else(|| eyre::eyre!("{path:?} has no file name"))?;
+    let (words, separator) = stem_words(&stem);
+    let dictionary_service = translation_service.dictionary_service.clone();
+    let language_pair = (source_language, target_language);
+    let from_project = dictionary_service
+        .clone()
+        .get_project_suggestions(path.clone(), language_pair, words.clone())
+        .await?
+        .pop();
+    let from_dictionary = match from_project {
+        Some(suggestion) => Some(suggestion),
+        None => dictionary_service
+            .get_global_suggestions(language_pair, words.clone())
+            .await?
+            .into_iter()
+            .find(|suggestion| suggestion.match_type == translation_service::MatchType::Exact),
+    };
+    let translated = match from_dictionary {
+        Some(suggestion) => suggestion.translated_text,
+        None => {
+            translation_service
+                .translate_text(words, workspace.translation_options)
+                .await
+                .wrap_err_with(|| format!("translating file name [{stem}]"))?
+                .text
+        }
+    };
+    file_name_safe(&translated, separator)
+}
+
+impl TranslationWorkspace {
+    /// fills [TranslationWorkspace::translated_stem] unless it's already there,
+    /// returns whether the workspace changed
+    pub async fn ensure_translated_stem(
+        &mut self,
+        translation_service: &TranslationService,
+    ) -> Result<bool> {
+        if self.translated_stem.is_some() {
+            return Ok(false);
+        }
+        let translated = translate_stem(self, translation_service.clone()).await?;
+        tracing::info!("output file name translated to [{translated}]");
+        self.translated_stem = Some(translated);
+        Ok(true)
+    }
+}
@@ -0,0 +1,124 @@
+//! format specific knowledge about which parts of a document should be translated
+//!
+//! every format reports byte ranges of translatable text, everything outside of them
+//! (markup, code, attributes...) is copied to the translated document as it is
+pub mod asciidoc;
+pub mod docx;
+pub mod epub;
+pub mod html;
+pub mod package;
+pub mod pdf;
+pub mod rst;
+
+use super::*;
+use std::ops::Range;
+
+/// byte ranges of `content` that contain prose, in document order and non-overlapping
+pub fn translatable_ranges(file_format: FileFormat, content: &str) -> Vec<Range<usize>> {
+    match file_format {
+        FileFormat::Txt | FileFormat::Pdf => std::iter::once(0..content.len()).collect(),
+        FileFormat::Adoc => asciidoc::translatable_ranges(content),
+        FileFormat::Rst => rst::translatable_ranges(content),
+        FileFormat::Epub => html::translatable_ranges(content),
+        FileFormat::Docx => docx::paragraphs(content).into_iter().flatten().collect(),
+    }
+}
+
+/// names and contents of the translated files of a package, see [FileFormat::is_package]
+pub fn content_documents(file_format: FileFormat, package: &[u8]) -> Result<Vec<(String, String)>> {
+    match file_format {
+        FileFormat::Epub => epub::content_documents(package),
+        FileFormat::Docx => docx::content_documents(package),
+        other => eyre::bail!("{other} documents are not packages"),
+    }
+}
+
+/// fixes up the document after translated segments were put in place of the original ones
+pub fn reassembled(file_format: FileFormat, content: String) -> String {
+    match file_format {
+        FileFormat::Txt | FileFormat::Adoc | FileFormat::Epub | FileFormat::Pdf => content,
+        FileFormat::Docx => docx::reassembled(content),
+        FileFormat::Rst => rst::reassembled(content),
+    }
+}
+
+/// entities of xml based formats that aren't predefined by xml itself, but show up in xhtml anyway
+const HTML_ENTITIES: &[(&str, &str)] = &[("nbsp", "\u{a0}")];
+
+/// text of the document the way it is translated, entities of xml based formats are decoded
+/// (left as they are when one of them is unknown)
+pub fn decoded(file_format: FileFormat, raw: &str) -> String {
+    match file_format {
+        FileFormat::Epub | FileFormat::Docx => {
+            let entities = HTML_ENTITIES
+                .iter()
+                .map(|(name, text)| (name.as_bytes().to_vec(), text.as_bytes().to_vec()))
+                .collect();
+            match quick_xml::escape::unescape_with(raw.as_bytes(), &entities) {
+                Ok(text) => String::from_utf8_lossy(&text).into_owned(),
+                Err(e) => {
+                    tracing::debug!("keeping entities of [{raw}] as they are: {e:?}");
+                    raw.to_owned()
+                }
+            }
+        }
+        _ => raw.to_owned(),
+    }
+}
+
+/// translated text the way it is put into the document, see [decoded]
+pub fn encoded(file_format: FileFormat, text: &str) -> String {
+    match file_format {
+        FileFormat::Epub | FileFormat::Docx => {
+            String::from_utf8_lossy(&quick_xml::escape::partial_escape(text.as_bytes()))
+                .into_owned()
+        }
+        _ => text.to_owned(),
+    }
+}
+
+/// splits text longer than `max_len` characters at word boundaries (or anywhere in a word,
+/// if a single word is too long), returns the pieces together with their byte offsets
+pub fn split_overlong(text: &str, max_len: usize) -> Vec<(usize, &str)> {
+    use unicode_segmentation::UnicodeSegmentation;
+    let max_len = max_len.max(1);
+    if text.chars().count() <= max_len {
+        return vec![(0, text)];
+    }
+    // places where the text can be split, no further than `max_len` characters apart
+    let boundaries = text
+        .split_word_bound_indices()
+        .flat_map(|(offset, word)| {
+            word.char_indices()
+                .step_by(max_len)
+                .map(move |(inner_offset, _)| offset + inner_offset)
+        })
+        .skip(1)
+        .chain(std::iter::once(text.len()));
+    let mut pieces = vec![];
+    let mut start = 0;
+    let mut last_boundary = 0;
+    // characters between `start` and `last_boundary`
+    let mut len = 0;
+    for boundary in boundaries {
+        let step = text[last_boundary..boundary].chars().count();
+        if len + step > max_len {
+            pieces.push((start, &text[start..last_boundary]));
+            start = last_boundary;
+            len = 0;
+        }
+        len += step;
+        last_boundary = boundary;
+    }
+    pieces.push((start, &text[start..]));
+    pieces
+}
+
+/// lines of `content` together with the byte offset they start at, without the line ending
+pub fn lines_with_offsets(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    content.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line.trim_end_matches(['\n', '\r'])))
+    })
+}
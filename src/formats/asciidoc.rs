@@ -0,0 +1,156 @@
+//! AsciiDoc support
+//!
+//! translated: paragraphs, section titles, block titles, list items and admonition paragraphs
+//! (without the `NOTE:` label), including prose inside example, sidebar and quote blocks
+//!
+//! left as they are: block delimiters, listing / literal / passthrough / comment blocks,
+//! tables, attribute entries (`:name: value`), block attributes and anchors (`[source]`, `[[id]]`),
+//! block macros (`image::`, `include::`) and comments
+//!
+//! inline formatting (`*bold*`, `link:url[text]`) stays inside of the translated sentence
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Line {
+    Blank,
+    /// markup which is never translated
+    Structural,
+    /// opens a block whose content is not translated, it is closed by the same line
+    VerbatimDelimiter,
+    /// a line that stands on its own (section / block title), prose starts after the prefix
+    Title {
+        prefix: usize,
+    },
+    /// starts a new paragraph (list item, admonition), prose starts after the prefix
+    Marker {
+        prefix: usize,
+    },
+    /// continues the current paragraph
+    Prose,
+}
+
+const ADMONITION_LABELS: &[&str] = &["NOTE: ", "TIP: ", "IMPORTANT: ", "WARNING: ", "CAUTION: "];
+
+fn delimiter_char(line: &str) -> Option<char> {
+    let first = line.chars().next()?;
+    (line.len() >= 4 && line.chars().all(|c| c == first)).then_some(first)
+}
+
+fn is_attribute_entry(line: &str) -> bool {
+    line.strip_prefix(':')
+        .and_then(|rest| rest.split_once(':'))
+        .map(|(name, value)| {
+            !name.is_empty()
+                && !name.contains(char::is_whitespace)
+                && (value.is_empty() || value.starts_with(' '))
+        })
+        .unwrap_or_default()
+}
+
+fn is_macro(line: &str) -> bool {
+    line.ends_with(']')
+        && line
+            .split_once(':')
+            .map(|(name, _)| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric()))
+            .unwrap_or_default()
+        && !line.contains(' ')
+}
+
+fn list_marker_len(line: &str) -> Option<usize> {
+    let bullets = line
+        .chars()
+        .take_while(|c| matches!(c, '*' | '-' | '.'))
+        .count();
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let marker = match (bullets, digits) {
+        (0, 0) => return None,
+        (0, digits) => match line[digits..].starts_with('.') {
+            true => digits + 1,
+            false => return None,
+        },
+        (bullets, _) => bullets,
+    };
+    line[marker..].starts_with(' ').then_some(marker + 1)
+}
+
+fn classify(line: &str) -> Line {
+    if line.trim().is_empty() {
+        return Line::Blank;
+    }
+    match delimiter_char(line) {
+        Some('-' | '.' | '+' | '/') => return Line::VerbatimDelimiter,
+        Some('=' | '*' | '_') => return Line::Structural,
+        _ => {}
+    }
+    if line.starts_with("|===") {
+        return Line::VerbatimDelimiter;
+    }
+    if line == "--"
+        || line.starts_with("//")
+        || is_attribute_entry(line)
+        || (line.starts_with('[') && line.ends_with(']'))
+        || is_macro(line)
+    {
+        return Line::Structural;
+    }
+    let heading = line.chars().take_while(|c| *c == '=').count();
+    if heading > 0 && line[heading..].starts_with(' ') {
+        return Line::Title {
+            prefix: heading + 1,
+        };
+    }
+    if line.starts_with('.') && !line.starts_with("..") && !line[1..].starts_with(' ') {
+        return Line::Title { prefix: 1 };
+    }
+    if let Some(label) = ADMONITION_LABELS
+        .iter()
+        .find(|label| line.starts_with(*label))
+    {
+        return Line::Marker {
+            prefix: label.len(),
+        };
+    }
+    if let Some(prefix) = list_marker_len(line) {
+        return Line::Marker { prefix };
+    }
+    Line::Prose
+}
+
+pub fn translatable_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut paragraph: Option<Range<usize>> = None;
+    let mut verbatim_until: Option<&str> = None;
+    for (start, line) in lines_with_offsets(content) {
+        if let Some(delimiter) = verbatim_until {
+            if line == delimiter {
+                verbatim_until = None;
+            }
+            continue;
+        }
+        let end = start + line.len();
+        match classify(line) {
+            Line::Blank | Line::Structural => ranges.extend(paragraph.take()),
+            Line::VerbatimDelimiter => {
+                ranges.extend(paragraph.take());
+                verbatim_until = Some(line);
+            }
+            Line::Title { prefix } => {
+                ranges.extend(paragraph.take());
+                ranges.push(start + prefix..end);
+            }
+            Line::Marker { prefix } => {
+                ranges.extend(paragraph.take());
+                paragraph = Some(start + prefix..end);
+            }
+            Line::Prose => {
+                paragraph = Some(match paragraph.take() {
+                    Some(current) => current.start..end,
+                    None => start..end,
+                })
+            }
+        }
+    }
+    ranges.extend(paragraph);
+    ranges.retain(|range| !content[range.clone()].trim().is_empty());
+    ranges
+}
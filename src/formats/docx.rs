@@ -0,0 +1,141 @@
+//! DOCX support
+//!
+//! only the main document (`word/document.xml`) is translated, headers, footers, footnotes
+//! and the rest of the package are copied as they are
+//!
+//! text of a paragraph is usually split into many runs (one per change of formatting), so
+//! sentences are made out of all the `<w:t>` texts of a paragraph and DeepL gets whole sentences,
+//! when a sentence spans multiple runs its translation is put into the first one and the rest
+//! of them are emptied (see [OriginalDocumentSlice::runs])
+//!
+//! like in [html], entities of `<w:t>` texts are decoded for translation and translations are
+//! escaped again when they're written back
+use super::{
+    package::*,
+    *,
+};
+
+pub const DOCUMENT_PATH: &str = "word/document.xml";
+
+pub fn content_documents(package: &[u8]) -> Result<Vec<(String, String)>> {
+    Ok(vec![(
+        DOCUMENT_PATH.to_owned(),
+        read_entry(package, DOCUMENT_PATH)?,
+    )])
+}
+
+/// byte ranges of `<w:t>` texts, grouped by paragraph
+pub fn paragraphs(content: &str) -> Vec<Vec<Range<usize>>> {
+    let mut paragraphs = vec![];
+    let mut current = vec![];
+    let mut position = 0;
+    while let Some(offset) = content[position..].find('<') {
+        let tag_start = position + offset;
+        let tag_end = content[tag_start..]
+            .find('>')
+            .map(|end| tag_start + end + 1)
+            .unwrap_or(content.len());
+        let tag = &content[tag_start..tag_end];
+        match (tag.starts_with("</"), html::tag_name(tag)) {
+            (_, "w:p") if !current.is_empty() => {
+                paragraphs.push(std::mem::take(&mut current));
+            }
+            (false, "w:t") if !tag.ends_with("/>") => {
+                let text_end = content[tag_end..]
+                    .find("</w:t>")
+                    .map(|end| tag_end + end)
+                    .unwrap_or(content.len());
+                if text_end > tag_end {
+                    current.push(tag_end..text_end);
+                }
+                position = text_end;
+                continue;
+            }
+            _ => {}
+        }
+        position = tag_end;
+    }
+    if !current.is_empty() {
+        paragraphs.push(current);
+    }
+    paragraphs
+}
+
+/// byte ranges every sentence is made of, sentences never cross paragraph boundaries
+/// and are split further when they are longer than `max_segment_length` characters
+pub fn sentence_pieces(content: &str, max_segment_length: usize) -> Vec<Vec<Range<usize>>> {
+    use unicode_segmentation::UnicodeSegmentation;
+    paragraphs(content)
+        .into_iter()
+        .flat_map(|pieces| {
+            let text: String = pieces.iter().map(|piece| &content[piece.clone()]).collect();
+            // where every piece starts in `text`
+            let offsets: Vec<_> = pieces
+                .iter()
+                .scan(0, |offset, piece| {
+                    let start = *offset;
+                    *offset += piece.len();
+                    Some(start)
+                })
+                .collect();
+            text.split_sentence_bound_indices()
+                .flat_map(|(start, sentence)| {
+                    split_overlong(sentence, max_segment_length)
+                        .into_iter()
+                        .map(move |(offset, piece)| (start + offset, piece))
+                })
+                .map(|(start, sentence)| {
+                    let end = start + sentence.len();
+                    pieces
+                        .iter()
+                        .zip(offsets.iter())
+                        .filter_map(|(piece, &offset)| {
+                            let from = start.max(offset);
+                            let to = end.min(offset + piece.len());
+                            (from < to)
+                                .then(|| piece.start + from - offset..piece.start + to - offset)
+                        })
+                        .collect()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// translations may start or end with whitespace (or runs may end up empty),
+/// word drops it unless it's told to preserve it
+pub fn reassembled(content: String) -> String {
+    content.replace("<w:t>", "<w:t xml:space=\"preserve\">")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r#"<w:document><w:body><w:p><w:r><w:t>Tom &amp; </w:t></w:r><w:r><w:b/><w:t>Jerry &lt;3</w:t></w:r></w:p></w:body></w:document>"#;
+
+    #[tokio::test]
+    async fn entities_survive_a_round_trip() -> Result<()> {
+        let entries = vec![(DOCUMENT_PATH.to_owned(), DOCUMENT.to_owned())];
+        let mut segments =
+            TranslationSegments::generate_from_entries(FileFormat::Docx, &entries, 5000).await?;
+        let segment = segments.segments.values_mut().next().unwrap();
+        assert_eq!(segment.original_text, "Tom & Jerry <3");
+        segment.translated_text = Some("Tomek & Jerry <3".to_owned());
+        let translated = reassembled(TranslationWorkspace::apply_translations(
+            FileFormat::Docx,
+            DOCUMENT.to_owned(),
+            segments.segments.into_iter(),
+        )?);
+        assert!(translated.contains(r#"<w:t xml:space="preserve">Tomek &amp; Jerry &lt;3</w:t>"#));
+        let texts: Vec<_> = paragraphs(&translated)
+            .into_iter()
+            .map(|pieces| {
+                let raw: String = pieces.into_iter().map(|piece| &translated[piece]).collect();
+                decoded(FileFormat::Docx, &raw)
+            })
+            .collect();
+        assert_eq!(texts, vec!["Tomek & Jerry <3"]);
+        Ok(())
+    }
+}
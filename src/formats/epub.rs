@@ -0,0 +1,135 @@
+//! EPUB support
+//!
+//! content documents listed in the spine are translated with [html::translatable_ranges],
+//! everything else in the package (OPF, NCX / nav, stylesheets, images...) is copied as it is
+//! by [package::repackaged]
+use super::{
+    package::*,
+    *,
+};
+use quick_xml::events::Event;
+use std::collections::HashMap;
+
+const CONTAINER_PATH: &str = "META-INF/container.xml";
+const XHTML_MEDIA_TYPE: &str = "application/xhtml+xml";
+
+/// attributes of every `element` in the xml document
+pub fn elements(xml: &str, element: &[u8]) -> Result<Vec<HashMap<String, String>>> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut buf = vec![];
+    let mut elements = vec![];
+    loop {
+        match reader.read_event(&mut buf)? {
+            Event::Start(start) | Event::Empty(start) if start.local_name() == element => {
+                let attributes = start
+                    .attributes()
+                    .map(|attribute| {
+                        let attribute = attribute?;
+                        Ok((
+                            String::from_utf8_lossy(attribute.key).into_owned(),
+                            attribute.unescape_and_decode_value(&reader)?,
+                        ))
+                    })
+                    .collect::<Result<_>>()?;
+                elements.push(attributes);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(elements)
+}
+
+/// path of `href` inside of the package, `base` is the directory of the file referencing it
+fn resolved(base: &str, href: &str) -> String {
+    base.split('/')
+        .chain(href.split('/'))
+        .filter(|component| !component.is_empty() && *component != ".")
+        .fold(vec![], |mut path, component| {
+            match component {
+                ".." => {
+                    path.pop();
+                }
+                component => path.push(component),
+            }
+            path
+        })
+        .join("/")
+}
+
+/// names and contents of the xhtml documents in spine order
+pub fn content_documents(package: &[u8]) -> Result<Vec<(String, String)>> {
+    let mut archive = archive(package)?;
+    let container = read(&mut archive, CONTAINER_PATH)?;
+    let package_document = elements(&container, b"rootfile")
+        .wrap_err_with(|| format!("parsing [{CONTAINER_PATH}]"))?
+        .into_iter()
+        .find_map(|mut attributes| attributes.remove("full-path"))
+        .ok_or_else(|| eyre::eyre!("no rootfile in [{CONTAINER_PATH}]"))?;
+    let opf = read(&mut archive, &package_document)?;
+    let base = package_document
+        .rsplit_once('/')
+        .map(|(directory, _)| directory)
+        .unwrap_or_default();
+    let manifest: HashMap<_, _> = elements(&opf, b"item")
+        .wrap_err_with(|| format!("parsing manifest of [{package_document}]"))?
+        .into_iter()
+        .filter_map(|mut attributes| {
+            Some((
+                attributes.remove("id")?,
+                (
+                    attributes.remove("href")?,
+                    attributes.remove("media-type").unwrap_or_default(),
+                ),
+            ))
+        })
+        .collect();
+    let mut documents = vec![];
+    for idref in elements(&opf, b"itemref")
+        .wrap_err_with(|| format!("parsing spine of [{package_document}]"))?
+        .into_iter()
+        .filter_map(|mut attributes| attributes.remove("idref"))
+    {
+        match manifest.get(&idref) {
+            Some((href, media_type)) if media_type == XHTML_MEDIA_TYPE => {
+                let name = resolved(base, href);
+                let content = read(&mut archive, &name)?;
+                documents.push((name, content));
+            }
+            Some(_) => {}
+            None => tracing::warn!("spine item [{idref}] is not in the manifest, skipping"),
+        }
+    }
+    Ok(documents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAPTER: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml"><body><p>Tom &amp; Jerry &lt;3</p></body></html>"#;
+
+    #[tokio::test]
+    async fn entities_survive_a_round_trip() -> Result<()> {
+        let entries = vec![("OEBPS/chapter.xhtml".to_owned(), CHAPTER.to_owned())];
+        let mut segments =
+            TranslationSegments::generate_from_entries(FileFormat::Epub, &entries, 5000).await?;
+        let segment = segments.segments.values_mut().next().unwrap();
+        assert_eq!(segment.original_text, "Tom & Jerry <3");
+        segment.translated_text = Some("Tomek & Jerry <3 <b>".to_owned());
+        let translated = TranslationWorkspace::apply_translations(
+            FileFormat::Epub,
+            CHAPTER.to_owned(),
+            segments.segments.into_iter(),
+        )?;
+        assert!(translated.contains("<p>Tomek &amp; Jerry &lt;3 &lt;b&gt;</p>"));
+        let texts: Vec<_> = html::translatable_ranges(&translated)
+            .into_iter()
+            .map(|range| decoded(FileFormat::Epub, &translated[range]))
+            .collect();
+        assert_eq!(texts, vec!["Tomek & Jerry <3 <b>"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+//! (X)HTML support
+//!
+//! every text node is translated on its own, tags, comments, CDATA sections and the content
+//! of `<script>` / `<style>` elements are left as they are
+//!
+//! entities (`&amp;`) are decoded before the text is translated and translations are escaped
+//! again, see [decoded] and [encoded]
+use super::*;
+
+/// elements whose content is never translated
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style"];
+
+pub fn tag_name(tag: &str) -> &str {
+    tag.trim_start_matches(['<', '/'])
+        .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .next()
+        .unwrap_or_default()
+}
+
+/// range without the leading and trailing whitespace, [None] if there is nothing else in it
+fn trimmed(content: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let text = &content[range.clone()];
+    let start = range.start + (text.len() - text.trim_start().len());
+    let end = range.start + text.trim_end().len();
+    (start < end).then_some(start..end)
+}
+
+pub fn translatable_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut position = 0;
+    let mut skipped_element: Option<&str> = None;
+    while position < content.len() {
+        let rest = &content[position..];
+        let text_len = rest.find('<').unwrap_or(rest.len());
+        if skipped_element.is_none() {
+            ranges.extend(trimmed(content, position..position + text_len));
+        }
+        position += text_len;
+        let rest = &content[position..];
+        if rest.is_empty() {
+            break;
+        }
+        let terminator = match rest {
+            rest if rest.starts_with("<!--") => "-->",
+            rest if rest.starts_with("<![CDATA[") => "]]>",
+            _ => ">",
+        };
+        let tag_len = rest
+            .find(terminator)
+            .map(|end| end + terminator.len())
+            .unwrap_or(rest.len());
+        let tag = &rest[..tag_len];
+        let name = tag_name(tag);
+        match skipped_element {
+            Some(skipped) if tag.starts_with("</") && name.eq_ignore_ascii_case(skipped) => {
+                skipped_element = None
+            }
+            None if !tag.starts_with("</")
+                && !tag.ends_with("/>")
+                && SKIPPED_ELEMENTS
+                    .iter()
+                    .any(|skipped| name.eq_ignore_ascii_case(skipped)) =>
+            {
+                skipped_element = Some(name)
+            }
+            _ => {}
+        }
+        position += tag_len;
+    }
+    ranges
+}
@@ -0,0 +1,60 @@
+//! zip based document packages (epub, docx)
+use super::*;
+use std::{
+    collections::HashMap,
+    io::{
+        Cursor,
+        Read,
+        Write,
+    },
+};
+
+pub type Archive<'a> = zip::ZipArchive<Cursor<&'a [u8]>>;
+
+pub fn archive(package: &[u8]) -> Result<Archive<'_>> {
+    zip::ZipArchive::new(Cursor::new(package)).wrap_err("opening document package")
+}
+
+pub fn read(archive: &mut Archive, name: &str) -> Result<String> {
+    let mut content = String::new();
+    archive
+        .by_name(name)
+        .wrap_err_with(|| format!("[{name}] not found in the package"))?
+        .read_to_string(&mut content)
+        .wrap_err_with(|| format!("reading [{name}]"))?;
+    Ok(content)
+}
+
+pub fn read_entry(package: &[u8], name: &str) -> Result<String> {
+    read(&mut archive(package)?, name)
+}
+
+/// copy of the package with contents of the given entries replaced, the rest is copied byte by byte
+/// (so that eg. `mimetype` of an epub stays first and uncompressed)
+pub fn repackaged(package: &[u8], replacements: &HashMap<String, String>) -> Result<Vec<u8>> {
+    let mut archive = archive(package)?;
+    let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+    for index in 0..archive.len() {
+        let file = archive
+            .by_index(index)
+            .wrap_err_with(|| format!("reading entry {index} of the package"))?;
+        let name = file.name().to_owned();
+        match replacements.get(&name) {
+            Some(content) => {
+                let options =
+                    zip::write::FileOptions::default().compression_method(file.compression());
+                writer
+                    .start_file(name.as_str(), options)
+                    .and_then(|_| Ok(writer.write_all(content.as_bytes())?))
+                    .wrap_err_with(|| format!("writing [{name}]"))?;
+            }
+            None => writer
+                .raw_copy_file(file)
+                .wrap_err_with(|| format!("copying [{name}]"))?,
+        }
+    }
+    Ok(writer
+        .finish()
+        .wrap_err("finishing document package")?
+        .into_inner())
+}
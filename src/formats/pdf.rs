@@ -0,0 +1,12 @@
+//! PDF support (input only)
+//!
+//! text is extracted from the document and translated as plain text, translated documents
+//! are written as `.txt` - putting the translation back into the PDF (and reflowing it)
+//! is out of scope
+use super::*;
+
+/// text of the whole document, extraction is deterministic so segments can point into it
+pub fn extracted_text(document: &[u8]) -> Result<String> {
+    tokio::task::block_in_place(|| pdf_extract::extract_text_from_mem(document))
+        .wrap_err("extracting text from pdf")
+}
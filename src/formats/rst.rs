@@ -0,0 +1,242 @@
+//! reStructuredText support
+//!
+//! translated: paragraphs, section titles, list items and the content of admonitions (`.. note::`)
+//!
+//! left as they are: section adornments, transitions, directives other than admonitions
+//! together with their indented body (`.. code-block::`, `.. image::`...), comments, targets,
+//! substitution definitions, field lists, grid tables and literal blocks (introduced by `::`)
+//!
+//! roles (`:ref:`...``) and other inline markup stay inside of the translated sentence
+//!
+//! translated titles are usually of different length than the original ones, so the adornments
+//! are regenerated in [reassembled] - otherwise the output would not be valid rST
+use super::*;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// characters that can be used for section adornments and transitions
+const ADORNMENT_CHARS: &[char] = &[
+    '=', '-', '`', ':', '\'', '"', '~', '^', '_', '*', '+', '#', '<', '>',
+];
+
+const ADMONITIONS: &[&str] = &[
+    "admonition",
+    "attention",
+    "caution",
+    "danger",
+    "error",
+    "hint",
+    "important",
+    "note",
+    "seealso",
+    "tip",
+    "warning",
+];
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_adornment(line: &str) -> bool {
+    let line = line.trim_end();
+    match line.chars().next() {
+        Some(first) => {
+            line.len() >= 3 && ADORNMENT_CHARS.contains(&first) && line.chars().all(|c| c == first)
+        }
+        None => false,
+    }
+}
+
+/// `:field: value` (field lists, directive options), but not lines starting with a role
+fn is_field(text: &str) -> bool {
+    text.strip_prefix(':')
+        .and_then(|rest| rest.split_once(':'))
+        .map(|(name, value)| {
+            !name.is_empty() && !name.contains('`') && (value.is_empty() || value.starts_with(' '))
+        })
+        .unwrap_or_default()
+}
+
+fn list_marker_len(text: &str) -> Option<usize> {
+    let marker = match text.chars().next()? {
+        '*' | '-' | '+' | '•' => text.chars().next()?.len_utf8(),
+        _ => {
+            let digits = match text.starts_with('#') {
+                true => 1,
+                false => text.chars().take_while(char::is_ascii_digit).count(),
+            };
+            match digits > 0 && matches!(text[digits..].chars().next(), Some('.' | ')')) {
+                true => digits + 1,
+                false => return None,
+            }
+        }
+    };
+    text[marker..].starts_with(' ').then_some(marker + 1)
+}
+
+fn width(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+pub fn translatable_ranges(content: &str) -> Vec<Range<usize>> {
+    let lines: Vec<_> = lines_with_offsets(content).collect();
+    let mut ranges = vec![];
+    let mut paragraph: Option<Range<usize>> = None;
+    // lines indented deeper than this belong to a block that is not translated
+    let mut skip_deeper_than: Option<usize> = None;
+    for (index, &(start, line)) in lines.iter().enumerate() {
+        let indent = indentation(line);
+        if let Some(level) = skip_deeper_than {
+            if line.trim().is_empty() || indent > level {
+                continue;
+            }
+            skip_deeper_than = None;
+        }
+        let end = start + line.trim_end().len();
+        let text = line[indent..].trim_end();
+        if text.is_empty() || is_adornment(line) {
+            ranges.extend(paragraph.take());
+            continue;
+        }
+        if text == "::" || text == ".." {
+            ranges.extend(paragraph.take());
+            skip_deeper_than = Some(indent);
+            continue;
+        }
+        let is_title = lines
+            .get(index + 1)
+            .map(|(_, next)| is_adornment(next))
+            .unwrap_or_default()
+            && (indent == 0 || index > 0 && is_adornment(lines[index - 1].1));
+        if is_title {
+            ranges.extend(paragraph.take());
+            ranges.push(start + indent..end);
+            continue;
+        }
+        if let Some(directive) = text.strip_prefix(".. ") {
+            ranges.extend(paragraph.take());
+            match directive.split_once("::") {
+                Some((name, argument)) if ADMONITIONS.contains(&name.trim()) => {
+                    paragraph = (!argument.trim().is_empty())
+                        .then(|| end - argument.trim_start().len()..end);
+                }
+                _ => skip_deeper_than = Some(indent),
+            }
+            continue;
+        }
+        if is_field(text) || text.starts_with('+') || text.starts_with('|') {
+            ranges.extend(paragraph.take());
+            continue;
+        }
+        match list_marker_len(text) {
+            Some(prefix) => {
+                ranges.extend(paragraph.take());
+                paragraph = Some(start + indent + prefix..end);
+            }
+            None => {
+                paragraph = Some(match paragraph.take() {
+                    Some(current) => current.start..end,
+                    None => start + indent..end,
+                })
+            }
+        }
+        // paragraph introducing a literal block
+        if text.ends_with("::") {
+            ranges.extend(paragraph.take());
+            skip_deeper_than = Some(indent);
+        }
+    }
+    ranges.extend(paragraph);
+    ranges.retain(|range| !content[range.clone()].trim().is_empty());
+    ranges
+}
+
+/// regenerates section adornments so that they match the width of the (translated) titles
+pub fn reassembled(content: String) -> String {
+    let mut lines: Vec<(String, &str)> = content
+        .split_inclusive('\n')
+        .map(|line| {
+            let text = line.trim_end_matches(['\n', '\r']);
+            (text.to_owned(), &line[text.len()..])
+        })
+        .collect();
+    for index in 1..lines.len() {
+        let title = &lines[index - 1].0;
+        if title.trim().is_empty() || is_adornment(title) || !is_adornment(&lines[index].0) {
+            continue;
+        }
+        let has_overline = index >= 2 && is_adornment(&lines[index - 2].0);
+        if indentation(title) > 0 && !has_overline {
+            continue;
+        }
+        let adornment_char = lines[index].0.chars().next().unwrap_or('=');
+        let adornment = adornment_char.to_string().repeat(width(title.trim_end()));
+        if has_overline {
+            lines[index - 2].0 = adornment.clone();
+        }
+        lines[index].0 = adornment;
+    }
+    lines
+        .into_iter()
+        .map(|(text, ending)| text + ending)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = "=====
+Intro
+=====
+
+Short
+-----
+
+See :ref:`setup`.
+
+.. code-block:: python
+
+   print(\"Short\")
+";
+
+    #[tokio::test]
+    async fn adornments_follow_longer_translated_titles() -> Result<()> {
+        let ranges = translatable_ranges(DOCUMENT);
+        let texts: Vec<_> = ranges
+            .iter()
+            .map(|range| &DOCUMENT[range.clone()])
+            .collect();
+        assert_eq!(texts, vec!["Intro", "Short", "See :ref:`setup`."]);
+        let mut segments =
+            TranslationSegments::generate_from_ranges(DOCUMENT, &ranges, 5000).await?;
+        for segment in segments.segments.values_mut() {
+            segment.translated_text = Some(match segment.original_text.as_str() {
+                "Intro" => "Wprowadzenie".to_owned(),
+                "Short" => "Dłuższy tytuł".to_owned(),
+                other => other.replace("See", "Zobacz"),
+            });
+        }
+        let translated = reassembled(TranslationWorkspace::apply_translations(
+            FileFormat::Rst,
+            DOCUMENT.to_owned(),
+            segments.segments.into_iter(),
+        )?);
+        assert_eq!(
+            translated,
+            "============
+Wprowadzenie
+============
+
+Dłuższy tytuł
+-------------
+
+Zobacz :ref:`setup`.
+
+.. code-block:: python
+
+   print(\"Short\")
+"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+//! bilingual glossaries delivered by clients (a source and a target column), imported straight into a dictionary
+use super::*;
+use itertools::Itertools;
+use translation_service::{
+    DictionaryService,
+    LanguagePair,
+};
+
+/// column of the glossary, by its header or its number (counted from 1)
+#[derive(Debug, Clone)]
+pub enum Column {
+    Number(usize),
+    Name(String),
+}
+
+impl std::str::FromStr for Column {
+    type Err = String;
+
+    fn from_str(column: &str) -> std::result::Result<Self, Self::Err> {
+        match column.parse::<usize>() {
+            Ok(0) => Err("columns are counted from 1".to_string()),
+            Ok(number) => Ok(Self::Number(number)),
+            Err(_) => Ok(Self::Name(column.to_string())),
+        }
+    }
+}
+
+impl Column {
+    fn position(&self, headers: Option<&csv::StringRecord>) -> Result<usize> {
+        match (self, headers) {
+            (Self::Number(number), _) => Ok(number - 1),
+            (Self::Name(name), Some(headers)) => headers
+                .iter()
+                .position(|header| header.trim() == name)
+                .ok_or_else(|| {
+                    eyre::eyre!(
+                        "there is no [{name}] column, columns: {}",
+                        headers.iter().join(", ")
+                    )
+                }),
+            (Self::Name(name), None) => {
+                eyre::bail!(
+                    "column [{name}] can only be found by name when the glossary has headers"
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GlossaryReadOptions {
+    pub source_column: Option<Column>,
+    pub target_column: Option<Column>,
+    /// first row names the columns
+    pub has_headers: bool,
+    /// defaults to a tab for `.tsv` files and a comma otherwise
+    pub delimiter: Option<char>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// rows with the same source and target as an earlier one
+    pub duplicates: usize,
+    /// rows with an empty source or target
+    pub skipped: usize,
+}
+
+/// unique (source, target) pairs of the glossary, in the order they're listed in
+pub fn read_glossary(
+    path: &Path,
+    GlossaryReadOptions {
+        source_column,
+        target_column,
+        has_headers,
+        delimiter,
+    }: GlossaryReadOptions,
+) -> Result<(Vec<(String, String)>, ImportSummary)> {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if extension == "xlsx" || extension == "xls" {
+        eyre::bail!("spreadsheets can't be read directly, save [{path:?}] as csv first");
+    }
+    let delimiter = delimiter.unwrap_or(match extension.as_str() {
+        "tsv" => '\t',
+        _ => ',',
+    });
+    if !delimiter.is_ascii() {
+        eyre::bail!("delimiter has to be a single ascii character, got [{delimiter}]");
+    }
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(has_headers)
+        .delimiter(delimiter as u8)
+        .flexible(true)
+        .from_path(path)
+        .wrap_err_with(|| format!("opening glossary [{path:?}]"))?;
+    let headers = match has_headers {
+        true => Some(
+            reader
+                .headers()
+                .wrap_err_with(|| format!("reading headers of [{path:?}]"))?
+                .clone(),
+        ),
+        false => None,
+    };
+    let source = source_column
+        .unwrap_or(Column::Number(1))
+        .position(headers.as_ref())?;
+    let target = target_column
+        .unwrap_or(Column::Number(2))
+        .position(headers.as_ref())?;
+    let mut summary = ImportSummary::default();
+    let mut seen = std::collections::HashSet::new();
+    let mut pairs = vec![];
+    for (row, record) in reader.records().enumerate() {
+        let record = record.wrap_err_with(|| format!("reading row {} of [{path:?}]", row + 1))?;
+        let cell = |position: usize| record.get(position).unwrap_or_default().trim().to_string();
+        let (source, target) = (cell(source), cell(target));
+        if source.is_empty() || target.is_empty() {
+            summary.skipped += 1;
+            continue;
+        }
+        match seen.insert((source.clone(), target.clone())) {
+            true => pairs.push((source, target)),
+            false => summary.duplicates += 1,
+        }
+    }
+    Ok((pairs, summary))
+}
+
+/// saves every entry of the glossary into the project dictionary of `original_document_path`,
+/// or into the global one when it's not given
+pub async fn import_glossary(
+    dictionary_service: DictionaryService,
+    path: &Path,
+    options: GlossaryReadOptions,
+    original_document_path: Option<PathBuf>,
+    language_pair: LanguagePair,
+) -> Result<ImportSummary> {
+    let (pairs, mut summary) = read_glossary(path, options)?;
+    for (original_text, translated_text) in pairs {
+        let dictionary_service = dictionary_service.clone();
+        match original_document_path.clone() {
+            Some(original_document_path) => {
+                dictionary_service
+                    .save_translation(
+                        original_document_path,
+                        language_pair,
+                        original_text,
+                        translated_text,
+                    )
+                    .await
+            }
+            None => {
+                dictionary_service
+                    .save_global_translation(language_pair, original_text, translated_text)
+                    .await
+            }
+        }?;
+        summary.imported += 1;
+    }
+    Ok(summary)
+}
@@ -0,0 +1,45 @@
+//! source text that is never sent for translation (urls, numbers, constants)
+use super::*;
+
+/// compiled [TlumokConfig::ignore_patterns]
+#[derive(Debug, Clone, Default)]
+pub struct IgnorePatterns(Vec<regex::Regex>);
+
+impl IgnorePatterns {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        patterns
+            .iter()
+            .map(|pattern| {
+                regex::Regex::new(pattern)
+                    .wrap_err_with(|| format!("invalid ignore pattern [{pattern}]"))
+            })
+            .collect::<Result<_>>()
+            .map(Self)
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        self.0.iter().any(|pattern| pattern.is_match(text))
+    }
+}
+
+impl TranslationSegments {
+    /// locks the selected segments that still need translation and match one of the patterns,
+    /// so they're kept as they are instead of being sent to deepl, returns how many got locked
+    pub fn lock_ignored(
+        &mut self,
+        patterns: &IgnorePatterns,
+        selection: &SegmentSelection,
+    ) -> usize {
+        let ignored = self
+            .pending(selection)
+            .filter(|(_, segment)| patterns.matches(&segment.original_text))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in ignored.iter() {
+            if let Some(segment) = self.segments.get_mut(key) {
+                segment.set_locked(true);
+            }
+        }
+        ignored.len()
+    }
+}
@@ -0,0 +1,190 @@
+//! everything known about a single segment, for tracking down why a translated document came out wrong
+use super::*;
+use itertools::Itertools;
+use translation_service::{
+    DictionaryService,
+    DictionarySuggestion,
+    MatchType,
+};
+
+impl TranslationWorkspace {
+    /// text the slice of a segment points to in the original document as it is now, runs joined together
+    pub async fn text_at_slice(&self, key: &str) -> Result<String> {
+        let OriginalDocument { path, file_format } = &self.original_document;
+        let slice = &self
+            .segments
+            .segments
+            .get(key)
+            .ok_or_else(|| eyre::eyre!("there is no segment [{key}]"))?
+            .original_document_slice;
+        let source = tokio::fs::read(path)
+            .await
+            .wrap_err_with(|| format!("reading original document at [{path:?}]"))?;
+        let content = match slice.entry.as_deref() {
+            Some(entry) => formats::package::read_entry(&source, entry)?,
+            None => Self::original_content(*file_format, source)
+                .wrap_err_with(|| format!("reading [{path:?}]"))?,
+        };
+        Self::slice_text(*file_format, key, &content, slice)
+    }
+}
+
+/// position of the first character that differs, [None] when the texts are the same
+fn first_difference(left: &str, right: &str) -> Option<usize> {
+    left.chars().zip_longest(right.chars()).position(
+        |pair| !matches!(pair, itertools::EitherOrBoth::Both(left, right) if left == right),
+    )
+}
+
+fn print_suggestions(title: &str, suggestions: Result<Vec<DictionarySuggestion>>) {
+    match suggestions {
+        Ok(suggestions) if suggestions.is_empty() => println!("{title}: no matches"),
+        Ok(suggestions) => {
+            println!("{title}:");
+            for DictionarySuggestion {
+                original_text,
+                translated_text,
+                match_type,
+                created,
+                ..
+            } in suggestions
+            {
+                let added = created
+                    .map(|created| format!(", added {}", created.format("%Y-%m-%d %H:%M:%S")))
+                    .unwrap_or_default();
+                let of = match match_type {
+                    MatchType::Exact => String::new(),
+                    MatchType::PartialPercent(_) => format!(" (of {original_text:?})"),
+                };
+                println!(
+                    "  {:>3}% {translated_text:?}{of}{added}",
+                    match_type.percent()
+                );
+            }
+        }
+        Err(e) => println!("{title}: {e:?}"),
+    }
+}
+
+/// prints the segment, what its slice holds in the original document and the dictionary matches of it
+pub async fn print_segment(
+    translation_workspace: &TranslationWorkspace,
+    dictionary_service: DictionaryService,
+    key: &str,
+) -> Result<()> {
+    let segment = translation_workspace
+        .segments
+        .segments
+        .get(key)
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "there is no segment [{key}], the workspace has {} segments",
+                translation_workspace.segments.segments.len()
+            )
+        })?;
+    let TranslationSegment {
+        original_text,
+        translated_text,
+        confirmed,
+        locked: _,
+        translation_before_lock,
+        source_edited,
+        keep_out_of_memory,
+        note,
+        machine_translation,
+        machine_translation_history,
+        original_document_slice:
+            OriginalDocumentSlice {
+                start,
+                len,
+                entry,
+                runs,
+            },
+        history,
+        needs_attention,
+    } = segment;
+    println!("segment [{key}] ({})", segment.status());
+    println!("original text: {original_text:?}");
+    println!(
+        "slice: bytes {start}..{} of {}",
+        start + len,
+        match entry {
+            Some(entry) => format!("[{entry}]"),
+            None => "the document".to_string(),
+        }
+    );
+    if !runs.is_empty() {
+        println!(
+            "runs: {}",
+            runs.iter().map(|run| format!("{run:?}")).join(", ")
+        );
+    }
+    match translation_workspace.text_at_slice(key).await {
+        Ok(text) => {
+            println!("text at slice: {text:?}");
+            match first_difference(&text, original_text) {
+                None => println!("text at slice matches the original text"),
+                Some(position) => println!(
+                    "text at slice DOES NOT match the original text, first difference at character {position}{}",
+                    match source_edited {
+                        true => " (the source was edited by hand, so that's expected)",
+                        false => "",
+                    }
+                ),
+            }
+        }
+        Err(e) => println!("text at slice: {e:?}"),
+    }
+    println!("translated text: {translated_text:?}");
+    println!("confirmed: {confirmed:?}");
+    println!("machine translation: {machine_translation:?}");
+    if let Some(translation_before_lock) = translation_before_lock {
+        println!("translation before locking: {translation_before_lock:?}");
+    }
+    if let Some(reason) = needs_attention {
+        println!("needs attention: {reason}");
+    }
+    if let Some(note) = note {
+        println!("note: {note:?}");
+    }
+    if *keep_out_of_memory {
+        println!("kept out of memory");
+    }
+    println!("confirmed {} times", history.len());
+    for SegmentEdit { at, previous, new } in history {
+        println!(
+            "  {} {previous:?} -> {new:?}",
+            at.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+    println!(
+        "machine translated {} times",
+        machine_translation_history.len()
+    );
+    for MachineTranslationVariant { at, text } in machine_translation_history {
+        println!("  {} {text:?}", at.format("%Y-%m-%d %H:%M:%S"));
+    }
+    let TlumokTranslationOptions {
+        source_language,
+        target_language,
+        ..
+    } = translation_workspace.translation_options;
+    print_suggestions(
+        "project dictionary",
+        dictionary_service
+            .clone()
+            .get_project_suggestions(
+                translation_workspace.original_document.path.clone(),
+                (source_language, target_language),
+                original_text.clone(),
+            )
+            .await,
+    );
+    print_suggestions(
+        "global dictionaries",
+        dictionary_service
+            .get_global_suggestions((source_language, target_language), original_text.clone())
+            .await,
+    );
+    Ok(())
+}
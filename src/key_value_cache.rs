@@ -4,11 +4,6 @@
 //     GetManyResults,
 // };
 pub mod cache_service;
-use cache_service::{
-    CacheFor,
-    ExpiresAfter,
-    GetManyResults,
-};
 pub type Key<KV> = <KV as KeyValue>::K;
 pub type Value<KV> = <KV as KeyValue>::V;
 pub type Lookup<KV> = HashMap<Key<KV>, Value<KV>>;
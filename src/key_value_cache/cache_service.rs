@@ -4,20 +4,30 @@ use super::*;
 use futures::StreamExt;
 use futures::TryStreamExt;
 use itertools::Itertools;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
 use sled::IVec;
+use std::sync::Weak;
 
 #[derive(Clone, Debug)]
 pub struct CacheFor<KV> {
     key: PathBuf,
-    cache_db: Arc<RwLock<sled::Db>>,
+    cache_db: Arc<RwLock<Arc<sled::Db>>>,
     phantom_data: PhantomData<KV>,
     expires_after: ExpiresAfter,
 }
 
+/// where the dictionaries of the language pair are kept under the given dictionaries directory
+pub fn language_pair_directory(
+    dictionaries_directory: &Path,
+    language_pair: LanguagePair,
+) -> PathBuf {
+    dictionaries_directory.join(format!("{}-{}", language_pair.0, language_pair.1))
+}
+
 #[tracing::instrument(ret)]
 pub fn language_pair_db_key(language_pair: LanguagePair) -> Result<PathBuf> {
-    let dir = crate::filesystem::dictionaries_directory()?
-        .join(format!("{}-{}", language_pair.0, language_pair.1));
+    let dir = language_pair_directory(&crate::filesystem::dictionaries_directory()?, language_pair);
     tracing::info!("language directory detected to be {dir:?}");
     if !dir.exists() {
         std::fs::create_dir_all(&dir).wrap_err("creating language root directory")?;
@@ -27,7 +37,7 @@ pub fn language_pair_db_key(language_pair: LanguagePair) -> Result<PathBuf> {
 
 #[tracing::instrument(ret)]
 pub fn project_part_of_db_key(project_key: String, type_name: &'static str) -> PathBuf {
-    PathBuf::from(&format!("{project_key}")).join(type_name)
+    PathBuf::from(project_key).join(type_name)
 }
 
 #[tracing::instrument(ret)]
@@ -43,12 +53,8 @@ pub fn dictionary_project_key_language_pair_key(
     // let full_path = language.join(project_part);
     let full_path = project_part
         .components()
-        .into_iter()
-        .filter(|p| match p {
-            std::path::Component::RootDir => false,
-            _ => true,
-        })
-        .fold(language, |acc, next| acc.join(dbg!(next)));
+        .filter(|p| !matches!(p, std::path::Component::RootDir))
+        .fold(language, |acc, next| acc.join(next));
     tracing::info!("full: {full_path:?}");
     Ok(full_path)
 }
@@ -56,36 +62,221 @@ pub fn dictionary_project_key_language_pair_key(
 #[tracing::instrument(ret)]
 pub fn original_document_path_project_key(path: &Path) -> String {
     path.components()
-        .into_iter()
         .map(|c| c.as_os_str().to_string_lossy())
         .join("_")
 }
+/// name of the database directory inside of each project's directory
+pub const PROJECT_DICTIONARY_NAME: &str = "dictionary";
+
+#[tracing::instrument(ret)]
+pub fn project_dictionary_path(
+    original_document_path: &Path,
+    language_pair: LanguagePair,
+) -> Result<PathBuf> {
+    let project_key = original_document_path_project_key(original_document_path);
+    dictionary_project_key_language_pair_key(language_pair, project_key, PROJECT_DICTIONARY_NAME)
+}
+
+/// project key of the dictionary shared by every project (approved terminology),
+/// document keys come from absolute paths so they never clash with it
+pub const GLOBAL_DICTIONARY_KEY: &str = "global";
+
+#[tracing::instrument(ret)]
+pub fn global_dictionary_path(language_pair: LanguagePair) -> Result<PathBuf> {
+    dictionary_project_key_language_pair_key(
+        language_pair,
+        GLOBAL_DICTIONARY_KEY.to_string(),
+        PROJECT_DICTIONARY_NAME,
+    )
+}
+
 #[tracing::instrument]
-pub fn project_dictionary(
+pub async fn project_dictionary(
     original_document_path: &Path,
     language_pair: LanguagePair,
 ) -> Result<translation_service::TranslationCache> {
-    let project_key = original_document_path_project_key(original_document_path);
     // translation_service::TranslationCache::new(
     //     ExpiresAfter::Never,
     // )
-    dictionary_at_path(dictionary_project_key_language_pair_key(
+    dictionary_at_path(project_dictionary_path(
+        original_document_path,
         language_pair,
-        project_key,
-        "dictionary",
     )?)
+    .await
+}
+/// every dictionary database stored under [crate::filesystem::dictionaries_directory]
+#[tracing::instrument]
+pub fn all_dictionary_paths() -> Result<Vec<PathBuf>> {
+    fn find(dir: &Path, found: &mut Vec<PathBuf>) -> Result<()> {
+        // sled keeps its config next to the data, so this is how a database directory is recognised
+        if dir.join("conf").is_file() {
+            found.push(dir.to_owned());
+        }
+        for entry in std::fs::read_dir(dir).wrap_err_with(|| format!("reading [{dir:?}]"))? {
+            let path = entry.wrap_err("reading dir entry")?.path();
+            if path.is_dir() {
+                find(&path, found)?;
+            }
+        }
+        Ok(())
+    }
+    let mut found = vec![];
+    find(&crate::filesystem::dictionaries_directory()?, &mut found)?;
+    Ok(found)
 }
+
+/// total size of all files in a directory, in bytes
+pub fn directory_size(dir: &Path) -> Result<u64> {
+    std::fs::read_dir(dir)
+        .wrap_err_with(|| format!("reading [{dir:?}]"))?
+        .map(|entry| -> Result<u64> {
+            let entry = entry.wrap_err("reading dir entry")?;
+            let metadata = entry.metadata().wrap_err("reading file metadata")?;
+            match metadata.is_dir() {
+                true => directory_size(&entry.path()),
+                false => Ok(metadata.len()),
+            }
+        })
+        .sum()
+}
+
 /// this access is unchecked, prefer usage of [project_dictionary]
 
 #[tracing::instrument(ret)]
-pub fn dictionary_at_path(path: PathBuf) -> Result<translation_service::TranslationCache> {
+pub async fn dictionary_at_path(path: PathBuf) -> Result<translation_service::TranslationCache> {
+    if let Some(parent) = path.parent() {
+        if !parent.exists() {
+            std::fs::create_dir_all(parent)
+                .wrap_err_with(|| format!("creating dictionary directory [{parent:?}]"))?;
+        }
+    }
     translation_service::TranslationCache::new(path.clone(), ExpiresAfter::Never)
+        .await
         .wrap_err_with(|| format!("opening dictionary db at path [{path:?}]"))
 }
+/// how many times opening a database locked by another process is retried
+const LOCKED_DB_RETRIES: u32 = 5;
+/// wait before the first retry, doubled after each one
+const LOCKED_DB_BACKOFF: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// sled only reports this as an [std::io::ErrorKind::Other], so the message is all there is
+fn is_locked_by_another_process(error: &sled::Error) -> bool {
+    matches!(error, sled::Error::Io(e) if e.to_string().starts_with("could not acquire lock"))
+}
+
+/// one slot per database directory, filled in while it's being opened
+type OpenDbSlot = Arc<tokio::sync::Mutex<Weak<sled::Db>>>;
+
+/// every database opened by this process, sled only allows a single handle per directory -
+/// handles are only kept alive by their [CacheFor]s, sled flushes and closes a database once the last one is dropped
+static OPEN_DBS: Lazy<Mutex<HashMap<PathBuf, OpenDbSlot>>> = Lazy::new(Default::default);
+
+/// writes every database in [OPEN_DBS] that is still open to disk,
+/// for the ones held until the process exits (sled doesn't get the chance to flush them then)
+pub fn flush_open_dbs() {
+    let slots = OPEN_DBS.lock().clone();
+    for (path, slot) in slots {
+        // a slot that's locked is still being opened, there's nothing to flush yet
+        let db = slot.try_lock().ok().and_then(|db| db.upgrade());
+        if let Some(Err(e)) = db.map(|db| db.flush()) {
+            tracing::error!("flushing [{path:?}]: {e}");
+        }
+    }
+}
+
+/// calls [flush_open_dbs] when dropped
+pub struct FlushOpenDbsOnDrop;
+
+impl Drop for FlushOpenDbsOnDrop {
+    fn drop(&mut self) {
+        flush_open_dbs();
+    }
+}
+
+/// reuses the handle if this database is still open in this process,
+/// only the slot of this path is locked while opening so concurrent tasks don't race each other for the same directory
+/// without waiting on the other ones
+async fn shared_db(path: &Path) -> Result<Arc<sled::Db>> {
+    let slot = {
+        let mut open_dbs = OPEN_DBS.lock();
+        // databases nobody holds anymore are closed already, slots someone else took are still in use
+        open_dbs.retain(|_, slot| {
+            Arc::strong_count(slot) > 1 || slot.try_lock().map_or(true, |db| db.strong_count() > 0)
+        });
+        open_dbs.entry(path.to_owned()).or_default().clone()
+    };
+    let mut slot = slot.lock().await;
+    if let Some(db) = slot.upgrade() {
+        return Ok(db);
+    }
+    let db = Arc::new(open_db(path).await?);
+    *slot = Arc::downgrade(&db);
+    Ok(db)
+}
+
+/// opens a sled database, waiting a bit if someone else (eg. the gui while using the cli) has it open
+async fn open_db(path: &Path) -> Result<sled::Db> {
+    let mut backoff = LOCKED_DB_BACKOFF;
+    let mut retries = 0;
+    loop {
+        let opened = tokio::task::spawn_blocking({
+            let path = path.to_owned();
+            move || sled::open(path)
+        })
+        .await
+        .wrap_err_with(|| format!("opening cache database for key {path:?}"))?;
+        match opened {
+            Ok(db) => return Ok(db),
+            Err(e) if is_locked_by_another_process(&e) && retries < LOCKED_DB_RETRIES => {
+                tracing::warn!("[{path:?}] is locked, retrying in {backoff:?}");
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                retries += 1;
+            }
+            Err(e) if is_locked_by_another_process(&e) => {
+                return Err(e).wrap_err_with(|| {
+                    format!("dictionary [{path:?}] is open in another tlumok window or process, close it and try again")
+                })
+            }
+            Err(e) => {
+                return Err(e)
+                    .wrap_err_with(|| format!("opening cache database for key {path:?} "))
+            }
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct CacheEntry<V> {
     pub value: V,
     pub created: crate::AppTime,
+    /// last time the value was replaced, see [CacheFor::upsert]
+    pub updated: Option<crate::AppTime>,
+}
+
+/// entries written before [CacheEntry::updated] existed, bincode has no defaults for missing fields
+#[derive(Deserialize)]
+struct CacheEntryV1<V> {
+    value: V,
+    created: crate::AppTime,
+}
+
+impl<V: DeserializeOwned> CacheEntry<V> {
+    fn decode(bytes: &[u8]) -> bincode::Result<Self> {
+        bincode::deserialize(bytes).or_else(|e| {
+            bincode::deserialize::<CacheEntryV1<V>>(bytes)
+                .map(|CacheEntryV1 { value, created }| Self {
+                    value,
+                    created,
+                    updated: None,
+                })
+                .map_err(|_| e)
+        })
+    }
+    /// when the value was written last
+    pub fn changed(&self) -> crate::AppTime {
+        self.updated.unwrap_or(self.created)
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -96,17 +287,18 @@ pub enum ExpiresAfter {
 }
 
 enum DbGetResult<KV: KeyValue> {
-    Return(Value<KV>),
+    Return(CacheEntry<Value<KV>>),
     NotFound,
     Remove(Key<KV>),
 }
 
 impl ExpiresAfter {
-    pub fn expired(self, created: crate::AppTime) -> Result<bool> {
+    /// `changed` is when the entry was written last, see [CacheEntry::changed]
+    pub fn expired(self, changed: crate::AppTime) -> Result<bool> {
         let val = match self {
             ExpiresAfter::Never => false,
             ExpiresAfter::After(duration) => {
-                (created + chrono::Duration::from_std(duration).context("bad expiry value")?)
+                (changed + chrono::Duration::from_std(duration).context("bad expiry value")?)
                     < crate::now()
             }
         };
@@ -119,15 +311,12 @@ where
     Key<KV>: Serialize + DeserializeOwned + Clone + std::fmt::Debug,
     Value<KV>: Serialize + DeserializeOwned + std::fmt::Debug,
 {
-    pub fn new(path: PathBuf, expires_after: ExpiresAfter) -> Result<Self> {
-        let base_db_path = crate::filesystem::dictionaries_directory()
-            .wrap_err("finding base directory for dictionaries")?;
+    pub async fn new(path: PathBuf, expires_after: ExpiresAfter) -> Result<Self> {
         Ok(Self {
             expires_after,
             cache_db: Arc::new(RwLock::new(
                 // sled::open(format!("./cache_db/{key}/{type_name}.cache")).with_context(|| {
-                sled::open(&path)
-                    .with_context(|| format!("opening cache database for key {path:?} "))?,
+                shared_db(&path).await?,
             )),
             key: path,
             phantom_data: Default::default(),
@@ -143,7 +332,7 @@ where
                     .with_context(|| format!("reading db for {:?}", self.key))?
                 {
                     Some(v) => Some(
-                        bincode::deserialize(&v[..])
+                        CacheEntry::decode(&v[..])
                             .with_context(|| format!("deserializing {:?}", self.key))?,
                     ),
                     None => None,
@@ -186,6 +375,31 @@ where
             CacheEntry {
                 value,
                 created: crate::now(),
+                updated: None,
+            },
+        )
+        .await
+    }
+    /// like [CacheFor::insert], but replaces the value if the key is already present,
+    /// the entry keeps its [CacheEntry::created] and gets [CacheEntry::updated] instead
+    pub async fn upsert(&self, key: Key<KV>, value: Value<KV>) -> Result<()> {
+        tracing::debug!(
+            "[{:?}] [dictionary] upserting value [{:?}] on key [{:?}]",
+            self.key,
+            value,
+            key
+        );
+        let now = crate::now();
+        let created = self
+            .get_internal(key.clone())
+            .await?
+            .map(|entry| entry.created);
+        self.insert_internal(
+            key,
+            CacheEntry {
+                value,
+                created: created.unwrap_or(now),
+                updated: created.map(|_| now),
             },
         )
         .await
@@ -202,18 +416,26 @@ where
         Ok(())
     }
     pub async fn get(&self, key: Key<KV>) -> Result<Option<Value<KV>>> {
+        Ok(self
+            .get_entry(key)
+            .await?
+            .map(|CacheEntry { value, .. }| value))
+    }
+
+    /// same as [CacheFor::get], but keeps the entry's metadata
+    pub async fn get_entry(&self, key: Key<KV>) -> Result<Option<CacheEntry<Value<KV>>>> {
         let result: DbGetResult<KV> = match self.get_internal(key.clone()).await? {
-            Some(CacheEntry { value, created }) => {
-                if self.expires_after.expired(created)? {
+            Some(entry) => {
+                if self.expires_after.expired(entry.changed())? {
                     DbGetResult::Remove(key)
                 } else {
-                    DbGetResult::Return(value)
+                    DbGetResult::Return(entry)
                 }
             }
             None => DbGetResult::NotFound,
         };
 
-        return Ok(match result {
+        Ok(match result {
             DbGetResult::Return(v) => Some(v),
             DbGetResult::NotFound => None,
             DbGetResult::Remove(key) => {
@@ -222,42 +444,66 @@ where
                 }
                 None
             }
-        });
+        })
     }
 
     pub async fn get_many(&self, keys: Vec<Key<KV>>) -> Result<GetManyResults<KV>> {
-        let found = Vec::with_capacity(keys.len() / 2);
-        let not_found_keys = Vec::with_capacity(keys.len() / 2);
-        let found_keys = Vec::with_capacity(keys.len() / 2);
-        let results: Vec<_> = futures::stream::iter(keys)
-            .map(|key| async {
-                self.get(key.clone()).await.map(|result| match result {
-                    Some(value) => SearchResult::<KV>::Found((key, value)),
-                    None => SearchResult::<KV>::NotFound(key),
-                })
-            })
-            .buffer_unordered(1)
-            .try_collect()
-            .await
-            .context("aggregating many results")?;
+        let GetManyResults {
+            found,
+            not_found_keys,
+            found_keys,
+        } = self.get_many_entries(keys).await?;
+        Ok(GetManyResults {
+            found: found
+                .into_iter()
+                .map(|(key, CacheEntry { value, .. })| (key, value))
+                .collect(),
+            not_found_keys,
+            found_keys,
+        })
+    }
 
-        let (found, not_found_keys, found_keys) = tokio::task::block_in_place(|| {
-            results.into_iter().fold(
-                (found, not_found_keys, found_keys),
-                |(mut found, mut not_found_keys, mut found_keys), search_result| match search_result
-                {
-                    SearchResult::Found((key, value)) => {
-                        found_keys.push(key.clone());
-                        found.push((key, value));
-                        (found, not_found_keys, found_keys)
-                    }
-                    SearchResult::NotFound(v) => {
-                        not_found_keys.push(v);
-                        (found, not_found_keys, found_keys)
+    /// looks all the keys up in a single blocking pass over the database,
+    /// expired entries are reported as not found and removed afterwards
+    pub async fn get_many_entries(
+        &self,
+        keys: Vec<Key<KV>>,
+    ) -> Result<GetManyResults<(Key<KV>, CacheEntry<Value<KV>>)>> {
+        let mut found = Vec::with_capacity(keys.len());
+        let mut not_found_keys = Vec::new();
+        let mut found_keys = Vec::with_capacity(keys.len());
+        let mut expired_keys = Vec::new();
+        {
+            let db = self.cache_db.read().await;
+            tokio::task::block_in_place(|| -> Result<()> {
+                for key in keys {
+                    let entry = db
+                        .get(bincode::serialize(&key).context("serializing key")?)
+                        .with_context(|| format!("reading db for {:?}", self.key))?
+                        .map(|v| CacheEntry::<Value<KV>>::decode(&v[..]))
+                        .transpose()
+                        .with_context(|| format!("deserializing {:?}", self.key))?;
+                    match entry {
+                        Some(entry) if self.expires_after.expired(entry.changed())? => {
+                            expired_keys.push(key.clone());
+                            not_found_keys.push(key);
+                        }
+                        Some(entry) => {
+                            found_keys.push(key.clone());
+                            found.push((key, entry));
+                        }
+                        None => not_found_keys.push(key),
                     }
-                },
-            )
-        });
+                }
+                Ok(())
+            })
+            .context("aggregating many results")?;
+        }
+        for key in expired_keys {
+            if let Err(e) = self.remove(key).await {
+                tracing::error!("failed to remove key :: {e}");
+            }
+        }
 
         Ok(GetManyResults {
             found,
@@ -279,7 +525,48 @@ where
         Ok(())
     }
 
+    /// removes entries that are past `purge_after` (which can be sooner than the cache's own [ExpiresAfter])
+    /// and flushes the database to disk, returns the number of purged entries
+    pub async fn compact(&self, purge_after: ExpiresAfter) -> Result<usize> {
+        let db = self.cache_db.write().await;
+        let purged = tokio::task::block_in_place(|| -> Result<usize> {
+            if let ExpiresAfter::Never = purge_after {
+                return Ok(0);
+            }
+            let mut purged = 0;
+            for item in db.iter() {
+                let (key, value) = item.context("reading entry")?;
+                let entry = CacheEntry::<Value<KV>>::decode(&value)
+                    .with_context(|| format!("deserializing entry of {:?}", self.key))?;
+                if purge_after.expired(entry.changed())? {
+                    db.remove(key).context("removing expired entry")?;
+                    purged += 1;
+                }
+            }
+            Ok(purged)
+        })?;
+        db.flush_async()
+            .await
+            .with_context(|| format!("flushing {:?}", self.key))?;
+        Ok(purged)
+    }
+
     pub async fn get_all(&self) -> Result<GetManyResults<KV>> {
+        let found = self
+            .get_all_entries()
+            .await?
+            .into_iter()
+            .map(|(key, CacheEntry { value, .. })| (key, value))
+            .collect_vec();
+        Ok(GetManyResults {
+            found_keys: found.iter().map(|(key, _)| key.clone()).collect(),
+            not_found_keys: vec![],
+            found,
+        })
+    }
+
+    /// every entry that is not expired, along with when it was created
+    pub async fn get_all_entries(&self) -> Result<Vec<(Key<KV>, CacheEntry<Value<KV>>)>> {
         let results = {
             let db = self.cache_db.read().await;
             tokio::task::block_in_place(|| -> Result<_> {
@@ -295,7 +582,7 @@ where
                     bincode::deserialize::<Key<KV>>(&key)
                         .context("deserializing key")
                         .and_then(|key| {
-                            bincode::deserialize::<CacheEntry<Value<KV>>>(&value)
+                            CacheEntry::<Value<KV>>::decode(&value)
                                 .context("deserializing value")
                                 .map(|value| (key, value))
                         })
@@ -306,23 +593,17 @@ where
                 .into_iter()
                 .map(|(key, value)| {
                     self.expires_after
-                        .expired(value.created)
+                        .expired(value.changed())
                         .map(|expired| ((key, value), expired))
                 })
                 .collect::<Result<Vec<_>>>()?;
 
             Ok(found
                 .into_iter()
-                .filter_map(|(entry, expired)| expired.then_some(entry))
-                .map(|(key, value)| (key, value.value))
+                .filter_map(|(entry, expired)| (!expired).then_some(entry))
                 .collect_vec())
         })?;
-
-        Ok(GetManyResults {
-            found_keys: found.iter().map(|(key, _)| key.clone()).collect(),
-            not_found_keys: vec![],
-            found,
-        })
+        Ok(found)
     }
 }
 pub struct GetManyResults<KV: KeyValue> {
@@ -331,7 +612,119 @@ pub struct GetManyResults<KV: KeyValue> {
     pub found_keys: Vec<Key<KV>>,
 }
 
-enum SearchResult<KV: KeyValue> {
-    Found((Key<KV>, Value<KV>)),
-    NotFound(Key<KV>),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn databases_are_shared_and_closed_with_their_last_handle() -> Result<()> {
+        let path = crate::filesystem::test_directory("shared-db").join("db");
+        let first = CacheFor::<(String, String)>::new(path.clone(), ExpiresAfter::Never).await?;
+        let second = CacheFor::<(String, String)>::new(path.clone(), ExpiresAfter::Never).await?;
+        first.upsert("key".into(), "value".into()).await?;
+        assert_eq!(second.get("key".into()).await?.as_deref(), Some("value"));
+        // other tests opening databases drop dead slots from [OPEN_DBS], so the handle is watched instead
+        let db = Arc::downgrade(&*first.cache_db.read().await);
+        drop((first, second));
+        assert!(db.upgrade().is_none());
+        let reopened = CacheFor::<(String, String)>::new(path, ExpiresAfter::Never).await?;
+        assert_eq!(reopened.get("key".into()).await?.as_deref(), Some("value"));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn upserting_keeps_the_creation_time() -> Result<()> {
+        let cache = CacheFor::<(String, String)>::new(
+            crate::filesystem::test_directory("upsert").join("db"),
+            ExpiresAfter::Never,
+        )
+        .await?;
+        cache.upsert("key".into(), "first".into()).await?;
+        let first = cache.get_entry("key".into()).await?.expect("inserted");
+        assert_eq!(first.updated, None);
+        cache.upsert("key".into(), "second".into()).await?;
+        let second = cache.get_entry("key".into()).await?.expect("inserted");
+        assert_eq!(second.value, "second");
+        assert_eq!(second.created, first.created);
+        assert!(second.updated.is_some());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn compacting_purges_entries_not_changed_recently() -> Result<()> {
+        let cache = CacheFor::<(String, String)>::new(
+            crate::filesystem::test_directory("compact").join("db"),
+            ExpiresAfter::Never,
+        )
+        .await?;
+        let long_ago = crate::now() - chrono::Duration::days(30);
+        for (key, updated) in [("old", None), ("updated", Some(crate::now()))] {
+            cache
+                .insert_internal(
+                    key.into(),
+                    CacheEntry {
+                        value: key.into(),
+                        created: long_ago,
+                        updated,
+                    },
+                )
+                .await?;
+        }
+        cache.upsert("new".into(), "new".into()).await?;
+        assert_eq!(cache.compact(ExpiresAfter::Never).await?, 0);
+        let week = std::time::Duration::from_secs(7 * 24 * 60 * 60);
+        assert_eq!(cache.compact(ExpiresAfter::After(week)).await?, 1);
+        let mut left = cache.get_all().await?.found_keys;
+        left.sort();
+        assert_eq!(left, vec!["new", "updated"]);
+        Ok(())
+    }
+
+    #[test]
+    fn entries_without_an_update_time_are_read() -> Result<()> {
+        #[derive(Serialize)]
+        struct Legacy {
+            value: String,
+            created: crate::AppTime,
+        }
+        let created = crate::now();
+        let legacy = bincode::serialize(&Legacy {
+            value: "value".into(),
+            created,
+        })?;
+        let entry = CacheEntry::<String>::decode(&legacy)?;
+        assert_eq!((entry.value.as_str(), entry.created), ("value", created));
+        assert_eq!(entry.updated, None);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn get_many_over_a_few_thousand_keys() -> Result<()> {
+        const KEYS: usize = 5000;
+        let cache = CacheFor::<(String, String)>::new(
+            crate::filesystem::test_directory("get-many").join("db"),
+            ExpiresAfter::Never,
+        )
+        .await?;
+        cache
+            .update(
+                (0..KEYS)
+                    .step_by(2)
+                    .map(|i| (format!("key {i}"), format!("value {i}"))),
+            )
+            .await?;
+        let keys = (0..KEYS).map(|i| format!("key {i}")).collect_vec();
+        let GetManyResults {
+            found,
+            not_found_keys,
+            found_keys,
+        } = cache.get_many(keys).await?;
+        assert_eq!(found.len(), KEYS / 2);
+        assert_eq!(found_keys.len(), KEYS / 2);
+        assert_eq!(not_found_keys.len(), KEYS / 2);
+        assert!(found
+            .iter()
+            .all(|(key, value)| key.replace("key", "value") == *value));
+        Ok(())
+    }
 }
@@ -0,0 +1,128 @@
+use super::*;
+
+/// kinds of problems a translated segment can have
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum LintIssue {
+    /// translated text is blank while the original is not
+    Empty,
+    /// translated text was never filled in
+    NotTranslated,
+    /// translated text is exactly the original text
+    IdenticalToSource,
+    /// original and translation end with different punctuation
+    TrailingPunctuationMismatch,
+    /// original and translation differ in leading / trailing whitespace
+    WhitespaceMismatch,
+    /// translated text is longer than [TlumokConfig::max_translation_lengths] allows
+    TooLong,
+}
+
+impl std::fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Empty => "empty translation",
+                Self::NotTranslated => "not translated",
+                Self::IdenticalToSource => "identical to source",
+                Self::TrailingPunctuationMismatch => "trailing punctuation mismatch",
+                Self::WhitespaceMismatch => "leading/trailing whitespace mismatch",
+                Self::TooLong => "translation too long",
+            }
+        )
+    }
+}
+
+/// issue type -> keys of segments that have it
+pub type LintReport = IndexMap<LintIssue, Vec<String>>;
+
+fn trailing_punctuation(text: &str) -> Option<char> {
+    text.trim_end()
+        .chars()
+        .last()
+        .filter(|c| c.is_ascii_punctuation() || matches!(c, '…' | '¿' | '¡'))
+}
+
+pub fn leading_whitespace(text: &str) -> &str {
+    &text[..text.len() - text.trim_start().len()]
+}
+
+pub fn trailing_whitespace(text: &str) -> &str {
+    &text[text.trim_end().len()..]
+}
+
+/// segments without any letters (numbers, punctuation, whitespace) are expected to stay as they are
+pub fn has_words(text: &str) -> bool {
+    text.chars().any(|c| c.is_alphabetic())
+}
+
+/// `max_length` (in characters) comes from [TlumokConfig::max_translation_lengths]
+pub fn lint_segment(
+    TranslationSegment {
+        original_text,
+        translated_text,
+        locked,
+        ..
+    }: &TranslationSegment,
+    max_length: Option<usize>,
+) -> Vec<LintIssue> {
+    let mut issues = vec![];
+    if *locked {
+        return issues;
+    }
+    let translated_text = match translated_text {
+        Some(translated_text) => translated_text,
+        None => {
+            issues.push(LintIssue::NotTranslated);
+            return issues;
+        }
+    };
+    if translated_text.trim().is_empty() {
+        if !original_text.trim().is_empty() {
+            issues.push(LintIssue::Empty);
+        }
+        return issues;
+    }
+    if translated_text == original_text && has_words(original_text) {
+        issues.push(LintIssue::IdenticalToSource);
+    }
+    if trailing_punctuation(original_text) != trailing_punctuation(translated_text) {
+        issues.push(LintIssue::TrailingPunctuationMismatch);
+    }
+    if leading_whitespace(original_text) != leading_whitespace(translated_text)
+        || trailing_whitespace(original_text) != trailing_whitespace(translated_text)
+    {
+        issues.push(LintIssue::WhitespaceMismatch);
+    }
+    if matches!(max_length, Some(max_length) if translated_text.chars().count() > max_length) {
+        issues.push(LintIssue::TooLong);
+    }
+    issues
+}
+
+impl TranslationWorkspace {
+    pub fn lint(&self, max_length: Option<usize>) -> LintReport {
+        let mut report = LintReport::new();
+        for (key, segment) in self.segments.segments.iter() {
+            for issue in lint_segment(segment, max_length) {
+                report.entry(issue).or_default().push(key.clone());
+            }
+        }
+        report.sort_keys();
+        report
+    }
+}
+
+pub fn print_report(report: &LintReport) {
+    if report.is_empty() {
+        println!("no issues found");
+        return;
+    }
+    for (issue, keys) in report.iter() {
+        println!("{issue} ({}):", keys.len());
+        for key in keys.iter() {
+            println!("    {key}");
+        }
+    }
+}
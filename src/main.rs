@@ -1,4 +1,3 @@
-#![feature(path_try_exists)]
 use eyre::{
     Result,
     WrapErr,
@@ -16,7 +15,30 @@ use tracing_subscriber::{
 };
 
 use futures::FutureExt;
+pub mod batch;
+pub mod clean;
+pub mod compare;
+pub mod coverage;
+pub mod deepl_languages;
+pub mod doctor;
+pub mod filenames;
+pub mod formats;
+pub mod glossary;
+pub mod ignore;
+pub mod inspect;
 pub mod key_value_cache;
+pub mod lint;
+pub mod partial;
+pub mod post_processing;
+pub mod project;
+pub mod reconcile;
+pub mod review;
+pub mod schema;
+pub mod server;
+pub mod shutdown;
+pub mod terms;
+pub mod tmx;
+pub mod xliff;
 
 pub mod ui;
 pub mod filesystem {
@@ -34,29 +56,554 @@ pub mod filesystem {
         }
         Ok(base_dir)
     }
+    /// writes to a temporary file next to `path` and renames it over `path` once everything is
+    /// on disk, so a crash in the middle of writing never leaves a half written file behind
+    pub async fn write_atomically(path: &Path, content: &[u8]) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| eyre::eyre!("[{path:?}] is not a file path"))?;
+        let temporary = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+        let written = async {
+            let mut file = tokio::fs::File::create(&temporary)
+                .await
+                .wrap_err_with(|| format!("creating [{temporary:?}]"))?;
+            file.write_all(content)
+                .await
+                .wrap_err_with(|| format!("writing [{temporary:?}]"))?;
+            file.sync_all()
+                .await
+                .wrap_err_with(|| format!("flushing [{temporary:?}]"))?;
+            tokio::fs::rename(&temporary, path)
+                .await
+                .wrap_err_with(|| format!("replacing [{path:?}] with [{temporary:?}]"))
+        }
+        .await;
+        if written.is_err() {
+            // the destination is untouched, only the partial copy has to go
+            tokio::fs::remove_file(&temporary).await.ok();
+        }
+        written
+    }
+    /// environment variable pointing to a single directory for dictionaries, logs, caches
+    /// and the config (a portable install), overrides the platform's directories
+    pub const DATA_DIR_ENV_VAR: &str = "TLUMOK_DATA_DIR";
+    fn data_directory_from_env() -> Option<PathBuf> {
+        std::env::var_os(DATA_DIR_ENV_VAR)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    }
+    fn project_directories() -> Option<directories::ProjectDirs> {
+        directories::ProjectDirs::from("", "", "tlumok")
+    }
+    fn created(directory: PathBuf) -> Result<PathBuf> {
+        if !directory.exists() {
+            std::fs::create_dir_all(&directory)
+                .wrap_err_with(|| format!("creating [{directory:?}]"))?;
+        }
+        Ok(directory)
+    }
+    /// where dictionaries, logs and caches are kept: [DATA_DIR_ENV_VAR] or the platform's data
+    /// directory (`~/.local/share/tlumok`, `%APPDATA%\tlumok\data`,
+    /// `~/Library/Application Support/tlumok`), the executable's directory when there's no home
+    pub fn data_directory() -> Result<PathBuf> {
+        created(match (data_directory_from_env(), project_directories()) {
+            (Some(data_dir), _) => data_dir,
+            (None, Some(project_directories)) => project_directories.data_dir().to_owned(),
+            (None, None) => base_directory()?,
+        })
+    }
+    /// where the config is kept, like [data_directory] but with the platform's config
+    /// directory (`~/.config/tlumok`, `%APPDATA%\tlumok\config`)
+    pub fn config_directory() -> Result<PathBuf> {
+        created(match (data_directory_from_env(), project_directories()) {
+            (Some(data_dir), _) => data_dir,
+            (None, Some(project_directories)) => project_directories.config_dir().to_owned(),
+            (None, None) => base_directory()?,
+        })
+    }
+    /// copies a file or a whole directory, used when a rename across filesystems isn't possible
+    fn copy_recursively(from: &Path, to: &Path) -> Result<()> {
+        match from.is_dir() {
+            true => {
+                std::fs::create_dir_all(to).wrap_err_with(|| format!("creating [{to:?}]"))?;
+                for entry in
+                    std::fs::read_dir(from).wrap_err_with(|| format!("reading [{from:?}]"))?
+                {
+                    let entry = entry.wrap_err_with(|| format!("reading [{from:?}]"))?;
+                    copy_recursively(&entry.path(), &to.join(entry.file_name()))?;
+                }
+                Ok(())
+            }
+            false => std::fs::copy(from, to)
+                .map(|_| ())
+                .wrap_err_with(|| format!("copying [{from:?}] to [{to:?}]")),
+        }
+    }
+    /// moves what older versions kept next to the executable into [data_directory] and
+    /// [config_directory], nothing already there gets overwritten, returns what was moved
+    pub fn migrate_from_executable_directory() -> Result<Vec<(PathBuf, PathBuf)>> {
+        let base_dir = base_directory()?;
+        let (data_dir, config_dir) = (data_directory()?, config_directory()?);
+        let mut migrated = vec![];
+        for (from, to) in [
+            (base_dir.join("dictionaries"), data_dir.join("dictionaries")),
+            (base_dir.join("logs"), data_dir.join("logs")),
+            (
+                base_dir.join(deepl_languages::CACHE_FILENAME),
+                data_dir.join(deepl_languages::CACHE_FILENAME),
+            ),
+            (
+                base_dir.join(TlumokConfig::DEFAULT_CONFIG_FILENAME),
+                config_dir.join(TlumokConfig::DEFAULT_CONFIG_FILENAME),
+            ),
+        ] {
+            if from == to || !from.exists() || to.exists() {
+                continue;
+            }
+            if std::fs::rename(&from, &to).is_err() {
+                // another filesystem or a read-only install, the old copy stays where it is,
+                // the new one only shows up once it's complete
+                let file_name = to.file_name().unwrap_or_default().to_string_lossy();
+                let partial = to.with_file_name(format!(".{file_name}.migrating"));
+                let copied = copy_recursively(&from, &partial)
+                    .and_then(|_| {
+                        std::fs::rename(&partial, &to)
+                            .wrap_err_with(|| format!("renaming [{partial:?}] to [{to:?}]"))
+                    })
+                    .wrap_err_with(|| {
+                        format!(
+                            "moving [{from:?}] to [{to:?}], set {DATA_DIR_ENV_VAR} to [{base_dir:?}] to keep using the old location"
+                        )
+                    });
+                if copied.is_err() {
+                    match partial.is_dir() {
+                        true => std::fs::remove_dir_all(&partial).ok(),
+                        false => std::fs::remove_file(&partial).ok(),
+                    };
+                }
+                copied?;
+            }
+            migrated.push((from, to));
+        }
+        Ok(migrated)
+    }
     pub fn dictionaries_directory() -> Result<PathBuf> {
-        let db_path = crate::filesystem::base_directory()?.join("dictionaries");
+        let db_path = crate::filesystem::data_directory()?.join("dictionaries");
+        if !db_path.exists() {
+            std::fs::create_dir_all(&db_path).context("creating dictionaries directory")?;
+        }
         Ok(db_path)
     }
+    /// empty directory of the system's temporary one, for a test to write into
+    #[cfg(test)]
+    pub fn test_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("tlumok-test-{}-{name}", std::process::id()));
+        std::fs::remove_dir_all(&directory).ok();
+        std::fs::create_dir_all(&directory).expect("creating test directory");
+        directory
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[tokio::test]
+        async fn interrupted_write_keeps_the_original_file() -> Result<()> {
+            let directory = test_directory("interrupted-write");
+            let path = directory.join("workspace.json");
+            std::fs::write(&path, "hours of work")?;
+            // the temporary copy can't be written, like when the disk fills up halfway through
+            let temporary = directory.join(".workspace.json.tmp");
+            std::fs::create_dir(&temporary)?;
+            std::fs::write(temporary.join("blocker"), "")?;
+            assert!(write_atomically(&path, b"half of the").await.is_err());
+            assert_eq!(std::fs::read_to_string(&path)?, "hours of work");
+
+            // a partial copy left behind by a crash doesn't get in the way of the next write
+            std::fs::remove_dir_all(&temporary)?;
+            std::fs::write(&temporary, "half of the")?;
+            write_atomically(&path, b"more work").await?;
+            assert_eq!(std::fs::read_to_string(&path)?, "more work");
+            assert!(!temporary.exists());
+            Ok(())
+        }
+    }
+}
+
+/// names of the fields a struct is deserialized from (optional ones included),
+/// taken from what its [Deserialize] implementation asks the deserializer for
+fn struct_fields<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    struct FieldNames<'a>(&'a mut &'static [&'static str]);
+    impl<'de, 'a> serde::Deserializer<'de> for FieldNames<'a> {
+        type Error = serde::de::value::Error;
+        fn deserialize_any<V: serde::de::Visitor<'de>>(
+            self,
+            _: V,
+        ) -> std::result::Result<V::Value, Self::Error> {
+            Err(serde::de::Error::custom("not a struct"))
+        }
+        fn deserialize_struct<V: serde::de::Visitor<'de>>(
+            self,
+            _: &'static str,
+            fields: &'static [&'static str],
+            _: V,
+        ) -> std::result::Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(serde::de::Error::custom("only the field names were needed"))
+        }
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+            option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+        }
+    }
+    let mut fields: &'static [&'static str] = &[];
+    T::deserialize(FieldNames(&mut fields)).ok();
+    fields
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct TlumokConfig {
     pub deepl_api_key: String,
+    /// how many different translations of the same text are kept in a dictionary
+    #[serde(default = "TlumokConfig::default_max_dictionary_variants")]
+    pub max_dictionary_variants: usize,
+    /// how many suggestions the global suggestion panel shows at most
+    #[serde(default = "TlumokConfig::default_max_global_suggestions")]
+    pub max_global_suggestions: usize,
+    /// how many project dictionaries are searched for global suggestions at most,
+    /// the most recently used ones first
+    #[serde(default = "TlumokConfig::default_max_dictionaries_searched")]
+    pub max_dictionaries_searched: usize,
+    /// global suggestions also include dictionary entries that are a part of the searched text
+    /// (or the other way around) or share most of its words, at least this similar (1 - 99),
+    /// every dictionary is read whole for them, so leave it out when lookups get slow
+    #[serde(default)]
+    pub min_partial_match_percent: Option<u32>,
+    /// project and global suggestions less similar than this (0 - 100) are hidden in the gui,
+    /// it's a starting point for the slider above them, the value set there is kept in the
+    /// workspace - partial matches are still only looked up down to `min_partial_match_percent`
+    #[serde(default)]
+    pub min_suggestion_percent: u32,
+    /// how many suggestions of each kind the gui shows before "show more" is clicked
+    #[serde(default = "TlumokConfig::default_max_suggestions_shown")]
+    pub max_suggestions_shown: usize,
+    /// where translated documents are written, relative paths are resolved against the
+    /// original document's directory, defaults to the original document's directory
+    #[serde(default)]
+    pub output_directory: Option<PathBuf>,
+    /// file name of translated documents, see [OutputTemplate] for available tokens
+    #[serde(default = "TlumokConfig::default_output_filename_template")]
+    pub output_filename_template: String,
+    /// `{ext}` of translated documents by the original document's format (`pdf = "md"`),
+    /// formats left out use the extension of the format they're written in
+    #[serde(default)]
+    pub output_extensions: IndexMap<String, String>,
+    /// text displayed in place of segments which are not translated yet
+    #[serde(default = "TlumokConfig::default_not_translated_marker")]
+    pub not_translated_marker: String,
+    /// longer segments are split at word boundaries when a workspace is created,
+    /// so that a single request never goes over DeepL's size limit
+    #[serde(default = "TlumokConfig::default_max_segment_length")]
+    pub max_segment_length: usize,
+    /// format newly created workspaces are saved in, existing ones keep theirs
+    #[serde(default)]
+    pub workspace_format: WorkspaceFormat,
+    /// newly created workspaces are gzipped, worth it for book-length documents
+    #[serde(default)]
+    pub compress_workspaces: bool,
+    /// the gui machine translates every segment without a translation as soon as
+    /// a workspace is opened, leaving only review and confirmation to be done
+    #[serde(default)]
+    pub translate_on_open: bool,
+    /// the machine suggestion panel also shows more and less formal translations when they differ,
+    /// each of them is a separate request that deepl bills like any other translation
+    #[serde(default)]
+    pub machine_translation_alternatives: bool,
+    /// machine translated segments are checked with a second request, made of their first few
+    /// characters and without the source language, so the ones deepl thinks are written in another
+    /// language get flagged - deepl bills it like any other translation
+    #[serde(default)]
+    pub detect_source_language: bool,
+    /// how many of the machine translations a segment got over time are kept in its workspace,
+    /// to see how deepl's take on it changed (eg. after a glossary update), 0 keeps none
+    #[serde(default = "TlumokConfig::default_max_machine_translation_history")]
+    pub max_machine_translation_history: usize,
+    /// regexes matched against the original text of segments, matching segments
+    /// are locked (kept as they are) instead of being sent to deepl
+    #[serde(default = "TlumokConfig::default_ignore_patterns")]
+    pub ignore_patterns: Vec<String>,
+    /// languages used for newly created workspaces
+    #[serde(default)]
+    pub default_translation_options: translation_service::TlumokTranslationOptions,
+    /// longest allowed translation (in characters) by document format (`txt = 42`), longer ones
+    /// are reported by the lint, useful for subtitles and ui strings
+    #[serde(default)]
+    pub max_translation_lengths: IndexMap<String, usize>,
+    /// regex find and replace rules by target language (`[[post_processing.Polish]]` with `find`
+    /// and `replace`), applied to every machine translation right after deepl returns it, before
+    /// it's checked or saved anywhere - rules run in the order they are listed, each one on
+    /// the result of the previous one, after [TlumokConfig::typography]
+    #[serde(default)]
+    pub post_processing: IndexMap<String, Vec<post_processing::PostProcessingRule>>,
+    /// built-in quote, ellipsis, dash and spacing fixes of machine translations
+    #[serde(default)]
+    pub typography: post_processing::Typography,
+    /// named sets of overrides, selected with `--profile` or [TlumokConfig::PROFILE_ENV_VAR]
+    #[serde(default)]
+    pub profiles: IndexMap<String, TlumokProfile>,
+}
+
+impl Default for TlumokConfig {
+    fn default() -> Self {
+        Self {
+            deepl_api_key: Default::default(),
+            max_dictionary_variants: Self::default_max_dictionary_variants(),
+            max_global_suggestions: Self::default_max_global_suggestions(),
+            max_dictionaries_searched: Self::default_max_dictionaries_searched(),
+            min_partial_match_percent: None,
+            min_suggestion_percent: 0,
+            max_suggestions_shown: Self::default_max_suggestions_shown(),
+            output_directory: None,
+            output_filename_template: Self::default_output_filename_template(),
+            output_extensions: Default::default(),
+            not_translated_marker: Self::default_not_translated_marker(),
+            max_segment_length: Self::default_max_segment_length(),
+            workspace_format: Default::default(),
+            compress_workspaces: false,
+            translate_on_open: false,
+            machine_translation_alternatives: false,
+            detect_source_language: false,
+            max_machine_translation_history: Self::default_max_machine_translation_history(),
+            ignore_patterns: Self::default_ignore_patterns(),
+            default_translation_options: Default::default(),
+            max_translation_lengths: Default::default(),
+            post_processing: Default::default(),
+            typography: Default::default(),
+            profiles: Default::default(),
+        }
+    }
+}
+
+/// overrides applied on top of [TlumokConfig] when the profile is active
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct TlumokProfile {
+    pub deepl_api_key: Option<String>,
+    pub default_translation_options: Option<translation_service::TlumokTranslationOptions>,
 }
 
 impl TlumokConfig {
     pub const DEFAULT_CONFIG_FILENAME: &'static str = "tlumok-settings.toml";
+    pub fn default_max_dictionary_variants() -> usize {
+        10
+    }
+    pub fn default_max_global_suggestions() -> usize {
+        5
+    }
+    pub fn default_max_dictionaries_searched() -> usize {
+        200
+    }
+    pub fn default_max_machine_translation_history() -> usize {
+        5
+    }
+    pub fn default_max_suggestions_shown() -> usize {
+        3
+    }
+    pub fn default_max_segment_length() -> usize {
+        5000
+    }
+    pub fn default_not_translated_marker() -> String {
+        NOT_TRANSLATED_MARKER.to_string()
+    }
+    /// urls, email addresses, numbers and CONSTANT_NAMES
+    pub fn default_ignore_patterns() -> Vec<String> {
+        [
+            r"^\s*(https?://|www\.)\S+\s*$",
+            r"^\s*[\w.+-]+@[\w-]+(\.[\w-]+)+\s*$",
+            r"^[\s\d.,:;%+\-/()]+$",
+            r"^\s*[A-Z][A-Z0-9]*(_[A-Z0-9]+)+\s*$",
+        ]
+        .map(ToString::to_string)
+        .to_vec()
+    }
+    /// see [TlumokConfig::max_translation_lengths]
+    pub fn max_translation_length(&self, file_format: FileFormat) -> Option<usize> {
+        self.max_translation_lengths
+            .get(&file_format.to_string())
+            .copied()
+    }
+    pub fn ignore_patterns(&self) -> Result<ignore::IgnorePatterns> {
+        ignore::IgnorePatterns::new(&self.ignore_patterns)
+    }
+    pub fn post_processing(&self) -> Result<post_processing::PostProcessing> {
+        post_processing::PostProcessing::new(&self.post_processing, self.typography)
+    }
+    pub fn default_output_filename_template() -> String {
+        "{stem}.tlumok-translated.{date}.{ext}".to_string()
+    }
+    /// how newly created workspaces are saved
+    pub fn workspace_storage(&self) -> WorkspaceStorage {
+        WorkspaceStorage {
+            format: self.workspace_format,
+            compressed: self.compress_workspaces,
+        }
+    }
+    pub fn output_template(&self) -> OutputTemplate {
+        OutputTemplate {
+            directory: self.output_directory.clone(),
+            filename_template: self.output_filename_template.clone(),
+            extensions: self.output_extensions.clone(),
+        }
+    }
     pub fn default_config_path() -> Result<PathBuf> {
-        Ok(filesystem::base_directory()?.join(Self::DEFAULT_CONFIG_FILENAME))
+        Ok(filesystem::config_directory()?.join(Self::DEFAULT_CONFIG_FILENAME))
     }
+    /// environment variables checked for the deepl api key, in order
+    pub const API_KEY_ENV_VARS: &'static [&'static str] =
+        &["TLUMOK_DEEPL_API_KEY", "DEEPL_API_KEY"];
     pub fn load_default() -> Result<Self> {
-        Self::load(&Self::default_config_path()?)
+        Self::load(&Self::default_config_path()?)?.validated()
+    }
+    /// environment variable selecting the active profile
+    pub const PROFILE_ENV_VAR: &'static str = "TLUMOK_PROFILE";
+    /// loads the default config and validates it, see [TlumokConfig::load_layered]
+    pub fn resolve(api_key: Option<String>, profile: Option<String>) -> Result<Self> {
+        Self::load_layered(api_key, profile)?.validated()
+    }
+    /// loads the default config (if there is one) with the active profile applied,
+    /// api key is taken from (in order of precedence):
+    /// `api_key` (cli flag), [TlumokConfig::API_KEY_ENV_VARS], profile, config file
+    pub fn load_layered(api_key: Option<String>, profile: Option<String>) -> Result<Self> {
+        let default_path = Self::default_config_path()?;
+        let config = match default_path.exists() {
+            true => Self::load(&default_path)?,
+            false => Self::default(),
+        };
+        let profile = profile.or_else(|| {
+            std::env::var(Self::PROFILE_ENV_VAR)
+                .ok()
+                .filter(|profile| !profile.is_empty())
+        });
+        let mut config = match profile {
+            Some(profile) => config.with_profile(&profile)?,
+            None => config,
+        };
+        let api_key = api_key.or_else(|| {
+            Self::API_KEY_ENV_VARS
+                .iter()
+                .find_map(|var| std::env::var(var).ok().filter(|key| !key.is_empty()))
+        });
+        if let Some(api_key) = api_key {
+            config.deepl_api_key = api_key;
+        }
+        Ok(config)
+    }
+    pub fn with_profile(self, name: &str) -> Result<Self> {
+        let TlumokProfile {
+            deepl_api_key,
+            default_translation_options,
+        } = self.profiles.get(name).cloned().ok_or_else(|| {
+            eyre::eyre!(
+                "profile [{name}] not found, available profiles: {:?}",
+                self.profiles.keys().collect::<Vec<_>>()
+            )
+        })?;
+        tracing::info!("using profile [{name}]");
+        Ok(Self {
+            deepl_api_key: deepl_api_key.unwrap_or(self.deepl_api_key),
+            default_translation_options: default_translation_options
+                .unwrap_or(self.default_translation_options),
+            ..self
+        })
     }
     pub fn load(path: &Path) -> Result<Self> {
-        std::fs::read_to_string(path)
-            .wrap_err_with(|| format!("reading [{path:?}]"))
-            .and_then(|content| toml::from_str(&content).wrap_err("parsing config"))
+        let content =
+            std::fs::read_to_string(path).wrap_err_with(|| format!("reading [{path:?}]"))?;
+        Self::warn_about_unknown_keys(&content);
+        toml::from_str(&content).wrap_err("parsing config")
+    }
+    fn warn_about_unknown_keys(content: &str) {
+        for key in Self::unknown_keys(content) {
+            tracing::warn!("unknown config key [{key}], it will be ignored");
+        }
+    }
+    /// top level keys of the config file that aren't fields of [TlumokConfig]
+    fn unknown_keys(content: &str) -> Vec<String> {
+        let known = struct_fields::<Self>();
+        match toml::from_str::<toml::Value>(content) {
+            Ok(toml::Value::Table(table)) => table
+                .keys()
+                .filter(|key| !known.contains(&key.as_str()))
+                .cloned()
+                .collect(),
+            _ => vec![],
+        }
+    }
+    /// the api key is only needed for machine translation, so it's checked when one is requested
+    pub fn checked_api_key(api_key: &str) -> Result<&str> {
+        let api_key = api_key.trim();
+        if api_key.is_empty() {
+            eyre::bail!(
+                "deepl api key is empty, set `deepl_api_key` in [{:?}] (it can be generated with the `generate-default-tlumok-config` command), pass `--api-key` or set one of {:?}",
+                Self::default_config_path().unwrap_or_default(),
+                Self::API_KEY_ENV_VARS,
+            );
+        }
+        Ok(api_key)
+    }
+    /// makes sure the config is usable, see [TlumokConfig::checked_api_key] for the deepl part
+    pub fn validated(self) -> Result<Self> {
+        if self.max_segment_length == 0 {
+            eyre::bail!("`max_segment_length` has to be greater than 0");
+        }
+        if self.max_suggestions_shown == 0 {
+            eyre::bail!("`max_suggestions_shown` has to be greater than 0");
+        }
+        if self.max_dictionaries_searched == 0 {
+            eyre::bail!("`max_dictionaries_searched` has to be greater than 0");
+        }
+        if let Some(percent) = self.min_partial_match_percent {
+            if !(1..=99).contains(&percent) {
+                eyre::bail!(
+                    "`min_partial_match_percent` has to be between 1 and 99, got {percent}"
+                );
+            }
+        }
+        if self.min_suggestion_percent > 100 {
+            eyre::bail!(
+                "`min_suggestion_percent` has to be between 0 and 100, got {}",
+                self.min_suggestion_percent
+            );
+        }
+        self.ignore_patterns()?;
+        self.post_processing()?;
+        let check_format = |setting: &str, format: &str| {
+            if !FileFormat::ALL
+                .iter()
+                .any(|file_format| file_format.to_string() == format)
+            {
+                eyre::bail!(
+                    "`{setting}` has an unknown format [{format}], known formats: {}",
+                    FileFormat::ALL
+                        .map(|file_format| file_format.to_string())
+                        .join(", ")
+                );
+            }
+            Ok(())
+        };
+        for (format, max_length) in self.max_translation_lengths.iter() {
+            check_format("max_translation_lengths", format)?;
+            if *max_length == 0 {
+                eyre::bail!("`max_translation_lengths.{format}` has to be greater than 0");
+            }
+        }
+        for (format, extension) in self.output_extensions.iter() {
+            check_format("output_extensions", format)?;
+            OutputTemplate::check_extension(extension)
+                .wrap_err_with(|| format!("`output_extensions.{format}` is invalid"))?;
+        }
+        Ok(self)
     }
 }
 use clap::{
@@ -69,35 +616,271 @@ use clap::{
 struct Cli {
     #[clap(subcommand)]
     command: Option<Commands>,
+    /// deepl api key, overrides the environment and the config file
+    #[clap(long, global = true, value_name = "KEY")]
+    api_key: Option<String>,
+    /// config profile to use, overrides the TLUMOK_PROFILE environment variable
+    #[clap(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+    /// only log errors
+    #[clap(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// log debug information as well
+    #[clap(short, long, global = true)]
+    verbose: bool,
+}
+
+impl Cli {
+    fn log_level(&self) -> tracing::Level {
+        match (self.quiet, self.verbose) {
+            (true, _) => tracing::Level::ERROR,
+            (_, true) => tracing::Level::DEBUG,
+            _ => tracing::Level::INFO,
+        }
+    }
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// initializes workspace for a given document
     InitializeTranslationWorkspace {
-        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
-        file: PathBuf,
+        /// original document path (can be repeated, `*` and `?` in file names are expanded)
+        #[clap(
+            short,
+            long,
+            parse(from_os_str),
+            value_name = "FILE",
+            multiple_occurrences = true,
+            required = true
+        )]
+        file: Vec<PathBuf>,
+        /// regenerates the workspace even if it already exists, discarding all the work in it
+        #[clap(long)]
+        force: bool,
     },
     /// uses the generated workspace to perform a translation on a target file
     Translate {
-        /// translated file path
-        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
-        file: PathBuf,
+        /// translated file path (can be repeated, `*` and `?` in file names are expanded)
+        #[clap(
+            short,
+            long,
+            parse(from_os_str),
+            value_name = "FILE",
+            multiple_occurrences = true,
+            required = true
+        )]
+        file: Vec<PathBuf>,
+        /// only translate the segment with this key (can be repeated)
+        #[clap(long, value_name = "KEY", multiple_occurrences = true)]
+        only: Vec<String>,
+        /// only translate segments at these positions (in document order, end excluded), eg. `10..20`
+        #[clap(long, value_name = "START..END", parse(try_from_str = parse_segment_range), conflicts_with = "only")]
+        range: Option<std::ops::Range<usize>>,
+        /// only list the segments that would be sent for translation, deepl is not contacted
+        #[clap(long)]
+        dry_run: bool,
     },
     /// generates default config
     GenerateDefaultTlumokConfig, // /// does testing things
     /// creates a new file of the original format, with translations applied
+    /// (pdf documents are input only, their translation is written as plain text)
     ApplyTranslations {
-        /// translated file path
+        /// translated file path (can be repeated, `*` and `?` in file names are expanded)
+        #[clap(
+            short,
+            long,
+            parse(from_os_str),
+            value_name = "FILE",
+            multiple_occurrences = true,
+            required = true
+        )]
+        file: Vec<PathBuf>,
+        /// output path, overrides the output directory and filename template from the config
+        /// (only for a single document)
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        output: Option<PathBuf>,
+        /// extension of the translated documents (without the dot), overrides `output_extensions`
+        /// from the config
+        #[clap(long, value_name = "EXT", conflicts_with = "output")]
+        extension: Option<String>,
+        /// prints what changed since the previous translated document written with the same
+        /// filename template
+        #[clap(long)]
+        compare: bool,
+    },
+    /// reports common translation issues in the workspace of a given document
+    Lint {
+        /// original document path
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        file: PathBuf,
+        /// print the report as json
+        #[clap(long)]
+        json: bool,
+    },
+    /// reports how much of a document is already covered by the dictionaries (exact and fuzzy matches)
+    Coverage {
+        /// original document path
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        file: PathBuf,
+        /// print the report as json
+        #[clap(long)]
+        json: bool,
+    },
+    /// prints everything about one segment of a workspace: its slice of the original document
+    /// (and what is there now), translations, history and dictionary matches
+    Inspect {
+        /// original document path
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        file: PathBuf,
+        /// segment key, eg. `segment_12`
+        #[clap(short, long)]
+        key: String,
+    },
+    /// checks if the segments of a workspace still match its original document
+    Verify {
+        /// original document path
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        file: PathBuf,
+    },
+    /// segments a changed original document again, keeping the translations of segments
+    /// that didn't change and pre-filling similar ones for review
+    Reconcile {
+        /// original document path
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        file: PathBuf,
+    },
+    /// saves only the confirmed translations of a workspace into its project dictionary
+    /// (global suggestions search every project dictionary, so they are reused in other documents too)
+    HarvestMemory {
+        /// original document path
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        file: PathBuf,
+    },
+    /// removes the workspace of a given document, asks for confirmation first
+    Clean {
+        /// original document path
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        file: PathBuf,
+        /// also remove the translated documents (matching the output filename template) and the xliff export
+        #[clap(long)]
+        outputs: bool,
+        /// also remove the project dictionary of the document
+        #[clap(long)]
+        dictionary: bool,
+        /// only list what would be removed
+        #[clap(long)]
+        dry_run: bool,
+        /// don't ask for confirmation
+        #[clap(short, long)]
+        yes: bool,
+    },
+    /// copies the project dictionary of one document into the project dictionary of another
+    MergeDictionaries {
+        /// document whose dictionary is read
+        #[clap(long, parse(from_os_str), value_name = "FILE")]
+        from: PathBuf,
+        /// document whose dictionary receives the entries (its workspace decides the languages)
+        #[clap(long, parse(from_os_str), value_name = "FILE")]
+        into: PathBuf,
+    },
+    /// flushes all dictionary databases to reclaim disk space
+    CompactDictionaries {
+        /// also remove dictionary entries that weren't added or updated in this many days
+        #[clap(long, value_name = "DAYS")]
+        purge_older_than: Option<u64>,
+    },
+    /// exports the workspace of a given document as an XLIFF 1.2 file
+    ExportXliff {
+        /// original document path
         #[clap(short, long, parse(from_os_str), value_name = "FILE")]
         file: PathBuf,
+        /// output path, defaults to `<document>.tlumok.xlf` next to the document
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// exposes workspaces over a JSON HTTP api, see [server]
+    Serve {
+        /// address to listen on
+        #[clap(long, default_value = "127.0.0.1:8377")]
+        address: std::net::SocketAddr,
+    },
+    /// reads translations from an XLIFF 1.2 file back into the workspace of a given document
+    ImportXliff {
+        /// original document path
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        file: PathBuf,
+        /// translated xliff file
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        xliff: PathBuf,
+    },
+    /// checks the config, the deepl api key and the dictionary storage, with hints on what to fix
+    Doctor,
+    /// exports the project dictionary of a given document as a TMX 1.4 translation memory
+    ExportTmx {
+        /// original document path
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        file: PathBuf,
+        /// output path, defaults to `<document>.tlumok.tmx` next to the document
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        output: Option<PathBuf>,
+        /// only entries created at or after this date (`YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS`)
+        #[clap(long, parse(try_from_str = tmx::parse_since), value_name = "DATE")]
+        since: Option<AppTime>,
+    },
+    /// imports a bilingual glossary (a csv file with a source and a target column) into a dictionary
+    ImportGlossary {
+        /// csv (or tsv) file, spreadsheets have to be saved as csv first
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        glossary: PathBuf,
+        /// imports into the project dictionary of this document instead of the global one
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        file: Option<PathBuf>,
+        /// column with the original texts, a header or a number counted from 1 [default: 1]
+        #[clap(long, value_name = "COLUMN")]
+        source_column: Option<glossary::Column>,
+        /// column with the translations [default: 2]
+        #[clap(long, value_name = "COLUMN")]
+        target_column: Option<glossary::Column>,
+        /// the first row is an entry too, not column headers
+        #[clap(long)]
+        no_headers: bool,
+        /// defaults to a tab for `.tsv` files and a comma otherwise
+        #[clap(long)]
+        delimiter: Option<char>,
+    },
+    /// exports every segment of the workspace of a given document as a table (source, translation,
+    /// status, note) for reviewers who don't use tlumok
+    ExportReview {
+        /// original document path
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        file: PathBuf,
+        /// output path, defaults to `<document>.tlumok-review.<format>` next to the document
+        #[clap(short, long, parse(from_os_str), value_name = "FILE")]
+        output: Option<PathBuf>,
+        /// `csv` or `html`, picked by the extension of `--output` when left out [default: html]
+        #[clap(long, value_name = "FORMAT")]
+        format: Option<review::ReportFormat>,
     },
 }
+fn parse_segment_range(range: &str) -> std::result::Result<std::ops::Range<usize>, String> {
+    let (start, end) = range
+        .split_once("..")
+        .ok_or_else(|| format!("expected START..END, got [{range}]"))?;
+    let parse = |bound: &str| {
+        bound
+            .trim()
+            .parse::<usize>()
+            .map_err(|e| format!("bad segment position [{bound}]: {e}"))
+    };
+    Ok(parse(start)?..parse(end)?)
+}
 use serde::{
     Deserialize,
     Serialize,
 };
-pub static NOT_TRANSLATED_MARKER: &'static str = "TODO!!!";
+/// placeholder that older workspaces stored in place of missing translations,
+/// untranslated segments are now represented by [None] in [TranslationSegment::translated_text]
+pub static NOT_TRANSLATED_MARKER: &str = "TODO!!!";
 pub mod translation_service {
     use std::sync::Arc;
 
@@ -119,6 +902,34 @@ pub mod translation_service {
     pub struct TlumokTranslationOptions {
         pub source_language: Language,
         pub target_language: Language,
+        #[serde(default)]
+        pub split_sentences: SentenceSplitting,
+        /// stops deepl from "fixing" capitalization and punctuation of the sent text,
+        /// when left out it's picked by the document format as the workspace is created,
+        /// see [FileFormat::preserves_formatting_by_default]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub preserve_formatting: Option<bool>,
+    }
+    /// whether deepl splits the sent text into sentences before translating it
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Copy, Default)]
+    pub enum SentenceSplitting {
+        /// segments are already sentences, so splitting them again can only reshape the result
+        #[default]
+        None,
+        Punctuation,
+        PunctuationAndNewlines,
+    }
+    impl SentenceSplitting {
+        fn to_deepl(self) -> SplitSentences {
+            match self {
+                Self::None => SplitSentences::None,
+                Self::Punctuation => SplitSentences::Punctuation,
+                Self::PunctuationAndNewlines => SplitSentences::PunctuationAndNewlines,
+            }
+        }
+    }
+    impl Language {
+        pub const ALL: [Self; 2] = [Self::Polish, Self::English];
     }
     pub type LanguagePair = (Language, Language);
     impl std::fmt::Display for Language {
@@ -131,6 +942,8 @@ pub mod translation_service {
             Self {
                 source_language: Language::English,
                 target_language: Language::Polish,
+                split_sentences: Default::default(),
+                preserve_formatting: None,
             }
         }
     }
@@ -149,6 +962,13 @@ pub mod translation_service {
         pub fn deepl_language_opt(self) -> Option<String> {
             Some(self.to_deepl_language())
         }
+
+        /// written from right to left (arabic, hebrew), translations into it are right aligned in the gui
+        pub fn is_right_to_left(self) -> bool {
+            match self {
+                Language::Polish | Language::English => false,
+            }
+        }
     }
 
     impl TlumokTranslationOptions {
@@ -160,6 +980,15 @@ pub mod translation_service {
             }
         }
 
+        pub fn deepl_options(&self) -> TranslationOptions {
+            TranslationOptions {
+                split_sentences: Some(self.split_sentences.to_deepl()),
+                preserve_formatting: self.preserve_formatting,
+                formality: None,
+                glossary_id: None,
+            }
+        }
+
         pub fn translatable_text_list(&self, text: &str) -> TranslatableTextList {
             TranslatableTextList {
                 texts: vec![text.to_string()],
@@ -170,12 +999,52 @@ pub mod translation_service {
 
     pub type Translation = (String, Vec<String>);
     pub type TranslationCache = CacheFor<Translation>;
+    /// dictionary entry along with when it was created
+    pub type DatedEntry = (String, CacheEntry<Vec<String>>);
     use crate::key_value_cache::cache_service::{
         dictionary_at_path,
+        CacheEntry,
         CacheFor,
+        ExpiresAfter,
     };
-    #[derive(Debug, Clone, Default)]
-    pub struct DictionaryService(Arc<RwLock<()>>);
+    #[derive(Debug, Clone)]
+    pub struct DictionaryService {
+        lock: Arc<RwLock<()>>,
+        /// how many translated variants are kept per original text, oldest are dropped first
+        pub max_variants: usize,
+        /// how many suggestions are returned from all the dictionaries combined
+        pub max_global_suggestions: usize,
+        /// how many dictionaries are opened for global suggestions at most
+        pub max_dictionaries_searched: usize,
+        /// global suggestions include partial matches at least this similar, see [crate::partial]
+        pub min_partial_match_percent: Option<u32>,
+    }
+
+    impl Default for DictionaryService {
+        fn default() -> Self {
+            Self::new(&TlumokConfig::default())
+        }
+    }
+
+    impl DictionaryService {
+        pub fn new(
+            TlumokConfig {
+                max_dictionary_variants,
+                max_global_suggestions,
+                max_dictionaries_searched,
+                min_partial_match_percent,
+                ..
+            }: &TlumokConfig,
+        ) -> Self {
+            Self {
+                lock: Default::default(),
+                max_variants: *max_dictionary_variants,
+                max_global_suggestions: *max_global_suggestions,
+                max_dictionaries_searched: *max_dictionaries_searched,
+                min_partial_match_percent: *min_partial_match_percent,
+            }
+        }
+    }
 
     impl DictionaryService {
         pub async fn save_translation(
@@ -185,20 +1054,49 @@ pub mod translation_service {
             original_text: String,
             translated_text: String,
         ) -> Result<()> {
-            let _guard = self.0.write().await;
-            let cache = tokio::task::block_in_place(|| {
-                crate::key_value_cache::cache_service::project_dictionary(
-                    &original_document_path,
-                    language_pair,
-                )
-            })
+            let _guard = self.lock.write().await;
+            let cache = crate::key_value_cache::cache_service::project_dictionary(
+                &original_document_path,
+                language_pair,
+            )
+            .await
             .wrap_err_with(|| format!("fetching db based on project [{original_document_path:?}] and languages [{language_pair:?}]"))?;
+            self.add_variant(&cache, original_text, translated_text)
+                .await
+        }
+        /// saves a confirmed translation into the global dictionary,
+        /// so it outlives the project it came from (it shows up in global suggestions)
+        pub async fn save_global_translation(
+            self,
+            language_pair: LanguagePair,
+            original_text: String,
+            translated_text: String,
+        ) -> Result<()> {
+            let _guard = self.lock.write().await;
+            let cache = dictionary_at_path(
+                crate::key_value_cache::cache_service::global_dictionary_path(language_pair)?,
+            )
+            .await
+            .wrap_err_with(|| format!("fetching global db for languages [{language_pair:?}]"))?;
+            self.add_variant(&cache, original_text, translated_text)
+                .await
+        }
+        /// keeps at most [Self::max_variants] translations of a text, oldest are dropped first
+        async fn add_variant(
+            &self,
+            cache: &TranslationCache,
+            original_text: String,
+            translated_text: String,
+        ) -> Result<()> {
             let current = cache.get(original_text.clone()).await?.unwrap_or_default();
-            let updated = current
-                .into_iter()
-                .chain(std::iter::once(translated_text))
-                .collect();
-            cache.insert(original_text, updated).await?;
+            if current.contains(&translated_text) {
+                return Ok(());
+            }
+            let mut updated = current;
+            updated.push(translated_text);
+            let excess = updated.len().saturating_sub(self.max_variants.max(1));
+            updated.drain(..excess);
+            cache.upsert(original_text, updated).await?;
             Ok(())
         }
         async fn get_suggestions_from_db(
@@ -206,90 +1104,429 @@ pub mod translation_service {
             db: TranslationCache,
             original_text: String,
         ) -> Result<Vec<DictionarySuggestion>> {
-            let _guard = self.0.read().await;
+            let _guard = self.lock.read().await;
             let mut suggestions = vec![];
 
-            if let Some(exact) = db.get(original_text.clone()).await? {
-                for translated_text in exact.into_iter() {
+            if let Some(CacheEntry { value, created, .. }) =
+                db.get_entry(original_text.clone()).await?
+            {
+                for translated_text in value.into_iter() {
                     suggestions.push(DictionarySuggestion {
                         original_text: original_text.clone(),
                         translated_text,
                         match_type: MatchType::Exact,
+                        created: Some(created),
+                        needs_attention: None,
                     });
                 }
             }
             Ok(suggestions)
         }
+        /// exact matches of the dictionary, and partial ones as well when
+        /// [DictionaryService::min_partial_match_percent] is set
+        async fn get_global_suggestions_from_db(
+            self,
+            db: TranslationCache,
+            original_text: String,
+        ) -> Result<Vec<DictionarySuggestion>> {
+            let mut suggestions = self
+                .clone()
+                .get_suggestions_from_db(db.clone(), original_text.clone())
+                .await?;
+            let min_percent = match self.min_partial_match_percent {
+                Some(min_percent) => min_percent,
+                None => return Ok(suggestions),
+            };
+            let _guard = self.lock.read().await;
+            for (stored_text, CacheEntry { value, created, .. }) in db.get_all_entries().await? {
+                if let Some(percent) =
+                    crate::partial::partial_match_percent(&original_text, &stored_text, min_percent)
+                {
+                    suggestions.extend(value.into_iter().map(|translated_text| {
+                        DictionarySuggestion {
+                            original_text: stored_text.clone(),
+                            translated_text,
+                            match_type: MatchType::PartialPercent(percent),
+                            created: Some(created),
+                            needs_attention: None,
+                        }
+                    }));
+                }
+            }
+            Ok(suggestions)
+        }
         pub async fn get_project_suggestions(
             self,
             original_document_path: PathBuf,
             language_pair: LanguagePair,
             original_text: String,
         ) -> Result<Vec<DictionarySuggestion>> {
-            let _guard = (&self.0).read().await;
+            let _guard = self.lock.read().await;
 
-            let cache = tokio::task::block_in_place(|| {
-                crate::key_value_cache::cache_service::project_dictionary(
+            let dictionary_path = tokio::task::block_in_place(|| {
+                crate::key_value_cache::cache_service::project_dictionary_path(
                     &original_document_path,
                     language_pair,
                 )
-            })
+            })?;
+            if !dictionary_path.exists() {
+                tracing::info!("no project dictionary at [{dictionary_path:?}] yet");
+                return Ok(vec![]);
+            }
+            let cache = crate::key_value_cache::cache_service::project_dictionary(
+                &original_document_path,
+                language_pair,
+            )
+            .await
             .wrap_err_with(|| format!("fetching db based on project [{original_document_path:?}] and languages [{language_pair:?}]"))?;
             self.clone()
                 .get_suggestions_from_db(cache, original_text)
                 .await
         }
+        /// exact project suggestions for many texts at once (e.g. every segment of a document),
+        /// texts without any match are left out
+        pub async fn get_project_suggestions_batch(
+            self,
+            original_document_path: PathBuf,
+            language_pair: LanguagePair,
+            texts: Vec<String>,
+        ) -> Result<std::collections::HashMap<String, Vec<DictionarySuggestion>>> {
+            let _guard = self.lock.read().await;
+            let dictionary_path = tokio::task::block_in_place(|| {
+                crate::key_value_cache::cache_service::project_dictionary_path(
+                    &original_document_path,
+                    language_pair,
+                )
+            })?;
+            if !dictionary_path.exists() {
+                tracing::info!("no project dictionary at [{dictionary_path:?}] yet");
+                return Ok(Default::default());
+            }
+            let cache = dictionary_at_path(dictionary_path).await
+                .wrap_err_with(|| format!("fetching db based on project [{original_document_path:?}] and languages [{language_pair:?}]"))?;
+            let texts = texts.into_iter().unique().collect_vec();
+            Ok(cache
+                .get_many_entries(texts)
+                .await?
+                .found
+                .into_iter()
+                .map(|(original_text, CacheEntry { value, created, .. })| {
+                    let suggestions = value
+                        .into_iter()
+                        .map(|translated_text| DictionarySuggestion {
+                            original_text: original_text.clone(),
+                            translated_text,
+                            match_type: MatchType::Exact,
+                            created: Some(created),
+                            needs_attention: None,
+                        })
+                        .collect();
+                    (original_text, suggestions)
+                })
+                .collect())
+        }
+        /// project dictionaries of every document translated in the given language pair
+        fn language_pair_dictionaries(language_pair: LanguagePair) -> Result<Vec<PathBuf>> {
+            let dictionaries_directory = crate::filesystem::dictionaries_directory()?;
+            tokio::task::block_in_place(|| {
+                Self::language_pair_dictionaries_in(&dictionaries_directory, language_pair)
+            })
+        }
+        /// project dictionaries of the language pair under the given dictionaries directory,
+        /// none until something was translated in the pair
+        fn language_pair_dictionaries_in(
+            dictionaries_directory: &Path,
+            language_pair: LanguagePair,
+        ) -> Result<Vec<PathBuf>> {
+            let lang_dir = crate::key_value_cache::cache_service::language_pair_directory(
+                dictionaries_directory,
+                language_pair,
+            );
+            let dictionary_dirs = match std::fs::read_dir(&lang_dir) {
+                Ok(dictionary_dirs) => dictionary_dirs,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    tracing::info!("no dictionaries for [{language_pair:?}] yet");
+                    return Ok(vec![]);
+                }
+                Err(e) => return Err(e).wrap_err("reading all project dictionaries"),
+            };
+            let valid = dictionary_dirs
+                .filter_map(|d| d.ok())
+                .map(|d| {
+                    d.path()
+                        .join(crate::key_value_cache::cache_service::PROJECT_DICTIONARY_NAME)
+                })
+                .filter(|d| d.is_dir())
+                .collect_vec();
+            Ok(valid)
+        }
         pub async fn get_global_suggestions(
             self,
             language_pair: LanguagePair,
             original_text: String,
         ) -> Result<Vec<DictionarySuggestion>> {
-            let lang_dir =
-                crate::key_value_cache::cache_service::language_pair_db_key(language_pair)?;
-            let valid_dictionary_dirs = tokio::task::block_in_place(|| -> Result<_> {
-                let dictionary_dirs =
-                    std::fs::read_dir(&lang_dir).wrap_err("reading all project dictionaries")?;
-                let valid = dictionary_dirs
-                    .into_iter()
-                    .filter_map(|d| d.ok())
-                    .map(|d| d.path())
-                    .filter(|d| d.is_dir())
-                    .collect_vec();
-                Ok(valid)
-            })?;
-            let dictionaries = valid_dictionary_dirs
+            let mut valid_dictionary_dirs = Self::language_pair_dictionaries(language_pair)?
                 .into_iter()
-                .filter_map(|path| dictionary_at_path(path).ok());
-            let suggestions: Vec<Vec<_>> = futures::stream::iter(dictionaries)
-                .map(|db| {
-                    self.clone()
-                        .get_suggestions_from_db(db, original_text.clone())
+                // sled touches the files whenever a dictionary is opened, not only when it's written to
+                .map(|path| (modified_at(&path).ok(), path))
+                .collect_vec();
+            valid_dictionary_dirs.sort_by(|(a, _), (b, _)| b.cmp(a));
+            if valid_dictionary_dirs.len() > self.max_dictionaries_searched {
+                tracing::debug!(
+                    "searching only {} most recently used of {} dictionaries",
+                    self.max_dictionaries_searched,
+                    valid_dictionary_dirs.len()
+                );
+                valid_dictionary_dirs.truncate(self.max_dictionaries_searched);
+            }
+            let limit = self.max_global_suggestions;
+            // opened one by one as the stream gets to them, the ones that can't be opened are skipped
+            let mut suggestions = futures::stream::iter(valid_dictionary_dirs)
+                .map(|(_, path)| {
+                    let service = self.clone();
+                    let original_text = original_text.clone();
+                    async move {
+                        match dictionary_at_path(path).await {
+                            Ok(db) => {
+                                service
+                                    .get_global_suggestions_from_db(db, original_text)
+                                    .await
+                            }
+                            Err(_) => Ok(vec![]),
+                        }
+                    }
                 })
-                .buffer_unordered(10)
-                .try_collect()
-                .await?;
-            let mut out = vec![];
-            let mut uniques = std::collections::HashSet::new();
-            for suggestion in suggestions.into_iter().flatten() {
-                if uniques.contains(&suggestion.translated_text) {
-                    continue;
+                .buffer_unordered(10);
+            let mut out: Vec<DictionarySuggestion> = vec![];
+            while let Some(found) = suggestions.try_next().await? {
+                out.extend(found);
+                // only the best ones are kept, no matter how many dictionaries there are
+                out.sort_by(|a, b| {
+                    b.match_type
+                        .percent()
+                        .cmp(&a.match_type.percent())
+                        .then_with(|| b.created.cmp(&a.created))
+                });
+                // the same translation found as a partial match and an exact one is shown as exact
+                let mut uniques = std::collections::HashSet::new();
+                out.retain(|suggestion| uniques.insert(suggestion.translated_text.clone()));
+                out.truncate(limit);
+                if out.len() >= limit && out.iter().all(|s| s.match_type == MatchType::Exact) {
+                    // nothing can outrank an exact match, no need to open the rest of the dictionaries
+                    break;
                 }
-                uniques.insert(suggestion.translated_text.clone());
-                out.push(suggestion);
             }
             Ok(out)
         }
     }
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-    pub enum MatchType {
-        Exact,
-        PartialPercent(u32),
+    impl DictionaryService {
+        /// every original text stored in any dictionary of the given language pair
+        pub async fn all_original_texts(
+            self,
+            language_pair: LanguagePair,
+        ) -> Result<std::collections::HashSet<String>> {
+            let _guard = self.lock.read().await;
+            let mut texts = std::collections::HashSet::new();
+            for path in Self::language_pair_dictionaries(language_pair)? {
+                let dictionary = dictionary_at_path(path.clone()).await?;
+                texts.extend(
+                    dictionary
+                        .get_all()
+                        .await
+                        .wrap_err_with(|| format!("reading dictionary [{path:?}]"))?
+                        .found_keys,
+                );
+            }
+            Ok(texts)
+        }
+        /// every entry of every dictionary of the given language pair,
+        /// variants of texts found in more than one dictionary are merged
+        pub async fn all_entries(
+            self,
+            language_pair: LanguagePair,
+        ) -> Result<std::collections::HashMap<String, Vec<String>>> {
+            let _guard = self.lock.read().await;
+            let mut entries = std::collections::HashMap::<String, Vec<String>>::new();
+            for path in Self::language_pair_dictionaries(language_pair)? {
+                let dictionary = dictionary_at_path(path.clone()).await?;
+                for (original_text, variants) in dictionary
+                    .get_all()
+                    .await
+                    .wrap_err_with(|| format!("reading dictionary [{path:?}]"))?
+                    .found
+                {
+                    let known = entries.entry(original_text).or_default();
+                    for variant in variants {
+                        if !known.contains(&variant) {
+                            known.push(variant);
+                        }
+                    }
+                }
+            }
+            Ok(entries)
+        }
+        /// entries of a project dictionary created or updated at or after `since` (all of them when it's not given), least recently changed first
+        pub async fn project_entries(
+            self,
+            original_document_path: PathBuf,
+            language_pair: LanguagePair,
+            since: Option<AppTime>,
+        ) -> Result<Vec<DatedEntry>> {
+            use crate::key_value_cache::cache_service::project_dictionary;
+            let _guard = self.lock.read().await;
+            let dictionary = project_dictionary(&original_document_path, language_pair).await?;
+            let mut entries = dictionary
+                .get_all_entries()
+                .await
+                .wrap_err_with(|| format!("reading dictionary of [{original_document_path:?}]"))?
+                .into_iter()
+                .filter(|(_, entry)| !matches!(since, Some(since) if entry.changed() < since))
+                .collect_vec();
+            entries.sort_by_key(|(_, entry)| entry.changed());
+            Ok(entries)
+        }
     }
-    #[derive(Debug, Clone)]
-    pub struct DictionarySuggestion {
-        pub original_text: String,
-        pub translated_text: String,
+    impl DictionaryService {
+        /// copies every entry of one project dictionary into another one,
+        /// returns the number of translated variants that were added
+        pub async fn merge_dictionaries(
+            self,
+            from: PathBuf,
+            into: PathBuf,
+            language_pair: LanguagePair,
+        ) -> Result<usize> {
+            use crate::key_value_cache::cache_service::project_dictionary;
+            let _guard = self.lock.write().await;
+            let from_cache = project_dictionary(&from, language_pair)
+                .await
+                .wrap_err_with(|| format!("opening dictionary of [{from:?}]"))?;
+            let into_cache = project_dictionary(&into, language_pair)
+                .await
+                .wrap_err_with(|| format!("opening dictionary of [{into:?}]"))?;
+            let mut added = 0;
+            for (original_text, variants) in from_cache.get_all().await?.found {
+                let current = into_cache
+                    .get(original_text.clone())
+                    .await?
+                    .unwrap_or_default();
+                let merged = current
+                    .iter()
+                    .chain(variants.iter())
+                    .unique()
+                    .cloned()
+                    .collect_vec();
+                if merged.len() == current.len() {
+                    continue;
+                }
+                added += merged.len() - current.len();
+                into_cache.upsert(original_text, merged).await?;
+            }
+            tracing::info!("merged [{from:?}] into [{into:?}], {added} new variants");
+            Ok(added)
+        }
+        /// saves the confirmed translations of a workspace into its project dictionary,
+        /// machine drafts are skipped so only reviewed translations are reused later
+        /// (and so are segments marked with [TranslationSegment::keep_out_of_memory])
+        pub async fn harvest_memory(self, workspace: &TranslationWorkspace) -> Result<usize> {
+            let TlumokTranslationOptions {
+                source_language,
+                target_language,
+                ..
+            } = workspace.translation_options;
+            let pairs = workspace
+                .segments
+                .segments
+                .values()
+                .filter(|segment| !segment.locked && !segment.keep_out_of_memory)
+                .filter_map(|segment| {
+                    segment
+                        .confirmed
+                        .clone()
+                        .map(|confirmed| (segment.original_text.clone(), confirmed))
+                })
+                .unique()
+                .collect_vec();
+            for (original_text, confirmed) in pairs.iter().cloned() {
+                self.clone()
+                    .save_translation(
+                        workspace.original_document.path.clone(),
+                        (source_language, target_language),
+                        original_text,
+                        confirmed,
+                    )
+                    .await?;
+            }
+            Ok(pairs.len())
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct CompactionResult {
+        pub path: PathBuf,
+        pub purged_entries: usize,
+        pub size_before: u64,
+        pub size_after: u64,
+    }
+
+    impl DictionaryService {
+        /// flushes every dictionary on disk, dropping the entries past `purge_after`
+        pub async fn compact_dictionaries(
+            self,
+            purge_after: ExpiresAfter,
+        ) -> Result<Vec<CompactionResult>> {
+            use crate::key_value_cache::cache_service::{
+                all_dictionary_paths,
+                directory_size,
+            };
+            let _guard = self.lock.write().await;
+            let paths = tokio::task::block_in_place(all_dictionary_paths)?;
+            let mut results = vec![];
+            for path in paths {
+                let size_before = directory_size(&path)?;
+                let purged_entries = {
+                    let dictionary = dictionary_at_path(path.clone()).await?;
+                    dictionary
+                        .compact(purge_after)
+                        .await
+                        .wrap_err_with(|| format!("compacting [{path:?}]"))?
+                };
+                let size_after = directory_size(&path)?;
+                tracing::info!("compacted [{path:?}] :: purged {purged_entries} entries, {size_before} -> {size_after} bytes");
+                results.push(CompactionResult {
+                    path,
+                    purged_entries,
+                    size_before,
+                    size_after,
+                });
+            }
+            Ok(results)
+        }
+    }
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub enum MatchType {
+        Exact,
+        PartialPercent(u32),
+    }
+
+    impl MatchType {
+        /// similarity to the searched text, 100 being identical
+        pub fn percent(self) -> u32 {
+            match self {
+                MatchType::Exact => 100,
+                MatchType::PartialPercent(percent) => percent,
+            }
+        }
+    }
+    #[derive(Debug, Clone, Serialize)]
+    pub struct DictionarySuggestion {
+        pub original_text: String,
+        pub translated_text: String,
         pub match_type: MatchType,
+        /// when the dictionary entry was added, [None] for suggestions not coming from a dictionary
+        pub created: Option<AppTime>,
+        /// set for machine translations that shouldn't be trusted blindly
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub needs_attention: Option<AttentionReason>,
     }
     // #[derive(Debug, Clone)]
     // pub struct DictionarySuggestions {
@@ -297,11 +1534,53 @@ pub mod translation_service {
     //     pub global_suggestions: Vec<DictionarySuggestion>,
     // }
 
+    /// translated text together with the source language deepl detected
+    #[derive(Debug, Clone)]
+    pub struct MachineTranslation {
+        pub text: String,
+        pub detected_source_language: String,
+    }
+
+    impl MachineTranslation {
+        /// why the translation should be reviewed before it's used, [None] if it looks fine
+        pub fn attention_reason(
+            &self,
+            original_text: &str,
+            translation_options: TlumokTranslationOptions,
+        ) -> Option<AttentionReason> {
+            let expected = translation_options
+                .source_language
+                .to_deepl_language_static();
+            if !self.detected_source_language.eq_ignore_ascii_case(expected) {
+                return Some(AttentionReason::SourceLanguageMismatch {
+                    detected: self.detected_source_language.clone(),
+                });
+            }
+            if self.text.trim() == original_text.trim() && crate::lint::has_words(original_text) {
+                return Some(AttentionReason::UnchangedTranslation);
+            }
+            None
+        }
+    }
+
     #[derive(Clone)]
     pub struct TranslationService {
-        pub deepl_client: Arc<Mutex<DeepL>>,
+        /// connected on the first machine translation, see [TranslationService::translate_text]
+        pub deepl_client: Arc<Mutex<Option<DeepL>>>,
+        deepl_api_key: String,
         /// hidden behind a RwLock to prevent data-races
         pub dictionary_service: DictionaryService,
+        /// segments matching these are never sent for translation
+        pub ignore_patterns: crate::ignore::IgnorePatterns,
+        pub post_processing: crate::post_processing::PostProcessing,
+        /// loaded before the first translation, see [TranslationService::check_language_pair]
+        pub supported_languages: Arc<Mutex<crate::deepl_languages::LanguageSupport>>,
+        /// see [TranslationService::translate_alternatives]
+        pub machine_translation_alternatives: bool,
+        /// see [TlumokConfig::detect_source_language]
+        pub detect_source_language: bool,
+        /// see [TlumokConfig::max_machine_translation_history]
+        pub max_machine_translation_history: usize,
     }
     impl std::fmt::Debug for TranslationService {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -310,72 +1589,317 @@ pub mod translation_service {
     }
 
     impl TranslationService {
-        pub async fn new(deepl_api_key: String) -> Result<Self> {
-            let deepl_client = DeepL::new(deepl_api_key);
-            tracing::info!(
-                "{:#?}",
-                deepl_client
-                    .usage_information()
-                    .await
-                    .map_err(|e| eyre::eyre!("{e:?}"))
-                    .wrap_err("connecting to deepl api")?
-            );
-            let deepl_client = Arc::new(Mutex::new(deepl_client));
-            Ok(Self {
-                deepl_client,
-                dictionary_service: Default::default(),
-            })
+        /// doesn't contact deepl, so reviewing workspaces and dictionaries works offline (and without a key)
+        pub fn new(config: &TlumokConfig) -> Self {
+            Self {
+                deepl_client: Default::default(),
+                deepl_api_key: config.deepl_api_key.clone(),
+                dictionary_service: DictionaryService::new(config),
+                ignore_patterns: Default::default(),
+                post_processing: Default::default(),
+                supported_languages: Default::default(),
+                machine_translation_alternatives: false,
+                detect_source_language: false,
+                max_machine_translation_history: 0,
+            }
+            .with_config(config)
+        }
+        /// same deepl connection and dictionaries, with the patterns and rules of the given config
+        /// (eg. one with [crate::project::ProjectOptions] applied)
+        pub fn with_config(&self, config: &TlumokConfig) -> Self {
+            Self {
+                ignore_patterns: config.ignore_patterns().unwrap_or_else(|e| {
+                    tracing::warn!("{e:?}, no segments will be ignored");
+                    Default::default()
+                }),
+                post_processing: config.post_processing().unwrap_or_else(|e| {
+                    tracing::warn!("{e:?}, translations will not be post processed");
+                    Default::default()
+                }),
+                machine_translation_alternatives: config.machine_translation_alternatives,
+                detect_source_language: config.detect_source_language,
+                max_machine_translation_history: config.max_machine_translation_history,
+                ..self.clone()
+            }
+        }
+        async fn connect(&self) -> Result<DeepL> {
+            let api_key = TlumokConfig::checked_api_key(&self.deepl_api_key)
+                .wrap_err("machine translation needs a deepl api key")?;
+            let deepl_client = DeepL::new(api_key.to_string());
+            // only informative, a failing request is reported on its own anyway
+            match deepl_client.usage_information().await {
+                Ok(usage_information) => tracing::info!("{usage_information:#?}"),
+                Err(e) => tracing::warn!("could not connect to deepl api: {e:?}"),
+            }
+            Ok(deepl_client)
         }
     }
 
+    /// deepl accepts at most 50 texts per request, but also caps the request body at 128 KiB - 25 segments
+    /// of the default 5000 characters still fit under it
+    pub const TRANSLATION_BATCH_SIZE: usize = 25;
+
+    /// characters of each text sent to deepl to detect its language, see
+    /// [TranslationService::detect_source_languages]
+    pub const DETECTION_SAMPLE_LENGTH: usize = 100;
+
     impl TranslationService {
         #[tracing::instrument(skip(self), level = "info")]
         pub async fn translate_text(
             self,
             text: String,
             translation_options: TlumokTranslationOptions,
-        ) -> Result<String> {
-            let translatable_text_list = translation_options.translatable_text_list(&text);
-            let deepl_client = self.deepl_client.lock().await;
-            let translated = deepl_client
-                .translate(None, translatable_text_list)
+        ) -> Result<MachineTranslation> {
+            self.translate_texts(vec![text], translation_options)
+                .await?
+                .pop()
+                .flatten()
+                .ok_or_else(|| eyre::eyre!("deepl returned no translation"))
+        }
+        /// the translation of the text followed by the more and less formal ones that differ from it,
+        /// only the first one unless [TlumokConfig::machine_translation_alternatives] is on
+        pub async fn translate_alternatives(
+            self,
+            text: String,
+            translation_options: TlumokTranslationOptions,
+        ) -> Result<Vec<MachineTranslation>> {
+            let mut translations = vec![
+                self.clone()
+                    .translate_text(text.clone(), translation_options)
+                    .await?,
+            ];
+            if !self.machine_translation_alternatives {
+                return Ok(translations);
+            }
+            for formality in [Formality::More, Formality::Less] {
+                let description = format!("{formality:?}");
+                match self
+                    .clone()
+                    .translate_texts_with_formality(
+                        vec![text.clone()],
+                        translation_options,
+                        Some(formality),
+                    )
+                    .await
+                {
+                    Ok(mut alternative) => {
+                        if let Some(alternative) = alternative.pop().flatten() {
+                            if translations
+                                .iter()
+                                .all(|translation| translation.text != alternative.text)
+                            {
+                                translations.push(alternative);
+                            }
+                        }
+                    }
+                    // not every target language has formality, the others are still worth showing
+                    Err(e) => tracing::debug!("no [{description}] formality alternative: {e:?}"),
+                }
+            }
+            Ok(translations)
+        }
+        /// translates many texts in a single request, results are matched to the texts by position,
+        /// texts deepl returned nothing for are left as [None]
+        pub async fn translate_texts(
+            self,
+            texts: Vec<String>,
+            translation_options: TlumokTranslationOptions,
+        ) -> Result<Vec<Option<MachineTranslation>>> {
+            self.translate_texts_with_formality(texts, translation_options, None)
                 .await
-                .map_err(|e| eyre::eyre!("{e:?}"))
-                .wrap_err_with(|| format!("getting translation info from deepl"))?;
-
-            let translated = translated
-                .get(0)
-                .ok_or_else(|| eyre::eyre!("parsing deepl response"))?;
-            let translated = translated.text.clone();
-            tracing::info!("translated: \n[{text}]\n->\n[{translated}]");
-            Ok(translated)
         }
-        pub async fn translate_segment(
+        async fn translate_texts_with_formality(
             self,
-            segment: TranslationSegment,
+            texts: Vec<String>,
             translation_options: TlumokTranslationOptions,
-        ) -> Result<TranslationSegment> {
-            let TranslationSegment {
-                original_text,
-                confirmed,
-                original_document_slice,
-                ..
-            } = segment.clone();
-            if confirmed.is_some() {
-                Ok(segment)
-            } else {
-                let translated_text = self
-                    .translate_text(original_text.clone(), translation_options)
-                    .await?;
-                Ok(TranslationSegment {
-                    original_text,
-                    translated_text,
-                    original_document_slice,
-                    confirmed,
+            formality: Option<Formality>,
+        ) -> Result<Vec<Option<MachineTranslation>>> {
+            // blank texts are kept as they are, deepl can leave them out of the response
+            let mut results = texts
+                .iter()
+                .map(|text| {
+                    text.trim().is_empty().then(|| MachineTranslation {
+                        text: text.clone(),
+                        detected_source_language: translation_options
+                            .source_language
+                            .to_deepl_language(),
+                    })
+                })
+                .collect_vec();
+            let sent = results
+                .iter()
+                .positions(|result| result.is_none())
+                .collect_vec();
+            if sent.is_empty() {
+                return Ok(results);
+            }
+            let translatable_text_list = TranslatableTextList {
+                texts: sent
+                    .iter()
+                    .map(|&position| texts[position].clone())
+                    .collect(),
+                ..translation_options.default_translatable_text_list()
+            };
+            let mut deepl_client = self.deepl_client.lock().await;
+            if deepl_client.is_none() {
+                *deepl_client = Some(self.connect().await?);
+            }
+            let deepl_client = deepl_client
+                .as_ref()
+                .ok_or_else(|| eyre::eyre!("deepl client is not connected"))?;
+            self.check_language_pair(
+                deepl_client,
+                (
+                    translation_options.source_language,
+                    translation_options.target_language,
+                ),
+            )
+            .await?;
+            // alternatives are made of the same text, it was checked with the first translation
+            let detect_source_language = self.detect_source_language && formality.is_none();
+            let translated = deepl_client
+                .translate(
+                    Some(TranslationOptions {
+                        formality,
+                        ..translation_options.deepl_options()
+                    }),
+                    translatable_text_list,
+                )
+                .await
+                .map_err(|e| eyre::eyre!("{e:?}"))
+                .wrap_err("getting translation info from deepl")?;
+            if translated.len() != sent.len() {
+                tracing::warn!(
+                    "deepl returned {} translations for {} texts, the missing ones are left untranslated",
+                    translated.len(),
+                    sent.len()
+                );
+            }
+            let mut detected = match detect_source_language {
+                true => {
+                    let sent_texts = sent.iter().map(|&position| texts[position].as_str());
+                    self.detect_source_languages(deepl_client, sent_texts, translation_options)
+                        .await
+                }
+                false => vec![],
+            }
+            .into_iter();
+            let translated = sent
+                .iter()
+                .zip(translated)
+                .map(|(&position, translated)| {
+                    let text = &texts[position];
+                    let translated = MachineTranslation {
+                        text: self
+                            .post_processing
+                            .apply(translation_options.target_language, translated.text),
+                        detected_source_language: detected
+                            .next()
+                            .unwrap_or(translated.detected_source_language),
+                    };
+                    tracing::info!("translated: \n[{text}]\n->\n[{}]", translated.text);
+                    if let Some(reason) = translated.attention_reason(text, translation_options) {
+                        tracing::warn!("[{text}] needs attention: {reason}");
+                    }
+                    translated
                 })
+                .collect_vec();
+            place_by_position(&mut results, &sent, translated);
+            Ok(results)
+        }
+        /// languages deepl detects for the texts when it's not told the source one, only the
+        /// beginning of each text is sent since that's enough to tell - the translation itself always
+        /// uses the configured source language. empty when the request fails, it's only informative
+        async fn detect_source_languages(
+            &self,
+            deepl_client: &DeepL,
+            texts: impl Iterator<Item = &str>,
+            translation_options: TlumokTranslationOptions,
+        ) -> Vec<String> {
+            let translatable_text_list = TranslatableTextList {
+                source_language: None,
+                texts: texts
+                    .map(|text| text.chars().take(DETECTION_SAMPLE_LENGTH).collect())
+                    .collect(),
+                ..translation_options.default_translatable_text_list()
+            };
+            match deepl_client
+                .translate(
+                    Some(translation_options.deepl_options()),
+                    translatable_text_list,
+                )
+                .await
+            {
+                Ok(detected) => detected
+                    .into_iter()
+                    .map(|detected| detected.detected_source_language)
+                    .collect(),
+                Err(e) => {
+                    tracing::warn!("could not detect the source language: {e:?}");
+                    vec![]
+                }
             }
         }
     }
+
+    /// puts the translations back where their texts came from, `sent` holds the position of each text
+    /// in the batch - texts deepl left without a result stay [None]
+    fn place_by_position(
+        results: &mut [Option<MachineTranslation>],
+        sent: &[usize],
+        translated: Vec<MachineTranslation>,
+    ) {
+        for (&position, translated) in sent.iter().zip(translated) {
+            results[position] = Some(translated);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn no_global_suggestions_before_any_dictionary_exists() -> Result<()> {
+            let dictionaries_directory = crate::filesystem::test_directory("cold-start");
+            let language_pair = (Language::Polish, Language::English);
+            let dictionaries = || {
+                DictionaryService::language_pair_dictionaries_in(
+                    &dictionaries_directory,
+                    language_pair,
+                )
+            };
+            assert!(dictionaries()?.is_empty());
+            std::fs::create_dir_all(
+                crate::key_value_cache::cache_service::language_pair_directory(
+                    &dictionaries_directory,
+                    language_pair,
+                ),
+            )?;
+            assert!(dictionaries()?.is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn short_response_leaves_the_rest_untranslated() {
+            let translation = |text: &str| MachineTranslation {
+                text: text.into(),
+                detected_source_language: String::new(),
+            };
+            let mut results = vec![None, Some(translation(" ")), None, None];
+            place_by_position(
+                &mut results,
+                &[0, 2, 3],
+                vec![translation("A"), translation("B")],
+            );
+            assert_eq!(
+                results
+                    .iter()
+                    .map(|result| result.as_ref().map(|result| result.text.as_str()))
+                    .collect_vec(),
+                vec![Some("A"), Some(" "), Some("B"), None]
+            );
+        }
+    }
 }
 
 /// this represents the original file that is being translated
@@ -390,18 +1914,322 @@ pub struct OriginalDocument {
 pub struct OriginalDocumentSlice {
     pub start: usize,
     pub len: usize,
+    /// file inside of the document package the slice points into (eg. content documents of an epub)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+    /// pieces of text the segment is made of when it is spread across multiple runs (docx),
+    /// `start` and `len` then cover all of them, together with the markup in between
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub runs: Vec<std::ops::Range<usize>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct TranslationSegment {
     pub original_text: String,
-    pub translated_text: String,
+    /// [None] until the segment gets translated
+    pub translated_text: Option<String>,
     pub confirmed: Option<String>,
+    /// segment is kept as it is in the original (brand names, code, urls),
+    /// it is never sent for translation and can't be edited
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+    /// translation the segment had before it got locked, it's brought back once it's unlocked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation_before_lock: Option<String>,
+    /// `original_text` was corrected by hand (eg. after a bad extraction),
+    /// so it is no longer checked against the original document
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub source_edited: bool,
+    /// translation only makes sense in this document (a placeholder, a one-off),
+    /// so confirming it doesn't save it into the project dictionary
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub keep_out_of_memory: bool,
+    /// translator's remark for a follow-up ("check with the client"), doesn't affect the translation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// last translation received from deepl, see [TranslationSegment::revert_to_machine]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub machine_translation: Option<String>,
+    /// machine translations received over time, oldest first, one is only added when it differs
+    /// from the previous one, see [TlumokConfig::max_machine_translation_history]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub machine_translation_history: Vec<MachineTranslationVariant>,
     pub original_document_slice: OriginalDocumentSlice,
+    /// every confirmation of this segment, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<SegmentEdit>,
+    /// machine translation that has to be reviewed, cleared once it's confirmed or edited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub needs_attention: Option<AttentionReason>,
+}
+
+/// why a machine translation can't be trusted blindly
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "reason")]
+pub enum AttentionReason {
+    /// deepl detected a different source language than the configured one
+    SourceLanguageMismatch { detected: String },
+    /// translation is the same as the original text
+    UnchangedTranslation,
+    /// deepl left the segment out of its response, so it's still untranslated
+    MissingTranslation,
+    /// translation of a similar (by the given percent) segment of the previous version of the document
+    FuzzyMatch { percent: u32 },
+}
+
+impl std::fmt::Display for AttentionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SourceLanguageMismatch { detected } => {
+                write!(f, "deepl detected [{detected}] as the source language")
+            }
+            Self::UnchangedTranslation => write!(f, "translation is the same as the original"),
+            Self::MissingTranslation => write!(f, "deepl returned no translation"),
+            Self::FuzzyMatch { percent } => write!(
+                f,
+                "taken from a {percent}% similar segment of the previous version"
+            ),
+        }
+    }
+}
+
+/// a single confirmation of a segment's translation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentEdit {
+    pub at: AppTime,
+    pub previous: Option<String>,
+    pub new: String,
+}
+
+/// a machine translation of a segment and when it was received
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MachineTranslationVariant {
+    pub at: AppTime,
+    pub text: String,
+}
+
+/// where a segment is in the translation process, derived from [TranslationSegment]'s fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SegmentStatus {
+    /// there is no translation yet
+    Untranslated,
+    /// translated (by a machine or by hand), but nobody confirmed it yet
+    MachineTranslated,
+    /// current translation is the confirmed one
+    Confirmed,
+    /// translation was confirmed and then changed
+    EditedAfterConfirm,
+    /// segment is not translated at all, see [TranslationSegment::locked]
+    Locked,
+}
+
+impl SegmentStatus {
+    /// segment can be put in the translated document as it is
+    pub fn is_final(self) -> bool {
+        matches!(self, Self::Confirmed | Self::Locked)
+    }
+}
+
+impl std::fmt::Display for SegmentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Untranslated => "untranslated",
+                Self::MachineTranslated => "translated",
+                Self::Confirmed => "confirmed",
+                Self::EditedAfterConfirm => "edited after confirmation",
+                Self::Locked => "locked",
+            }
+        )
+    }
+}
+
+impl TranslationSegment {
+    pub fn status(&self) -> SegmentStatus {
+        if self.locked {
+            return SegmentStatus::Locked;
+        }
+        match (&self.translated_text, &self.confirmed) {
+            (None, None) => SegmentStatus::Untranslated,
+            (Some(_), None) => SegmentStatus::MachineTranslated,
+            (translated, Some(confirmed)) => match translated.as_ref() == Some(confirmed) {
+                true => SegmentStatus::Confirmed,
+                false => SegmentStatus::EditedAfterConfirm,
+            },
+        }
+    }
+    /// segment made of (possibly) multiple pieces of an entry's content, see [OriginalDocumentSlice::runs]
+    fn from_pieces(content: &str, pieces: Vec<std::ops::Range<usize>>, entry: &str) -> Self {
+        let original_text = pieces.iter().map(|piece| &content[piece.clone()]).collect();
+        let start = pieces.first().map(|piece| piece.start).unwrap_or_default();
+        let end = pieces.last().map(|piece| piece.end).unwrap_or_default();
+        Self {
+            original_text,
+            translated_text: None,
+            confirmed: None,
+            locked: false,
+            translation_before_lock: None,
+            source_edited: false,
+            keep_out_of_memory: false,
+            note: None,
+            original_document_slice: OriginalDocumentSlice {
+                start,
+                len: end - start,
+                entry: Some(entry.to_owned()),
+                runs: match pieces.len() {
+                    1 => vec![],
+                    _ => pieces,
+                },
+            },
+            history: vec![],
+            needs_attention: None,
+            machine_translation: None,
+            machine_translation_history: vec![],
+        }
+    }
+    /// stores the result of machine translating the original text, flagging the segment when
+    /// it needs a closer look (or when there is no result at all)
+    pub fn set_machine_translation(
+        &mut self,
+        result: Option<translation_service::MachineTranslation>,
+        translation_options: TlumokTranslationOptions,
+        max_history: usize,
+    ) {
+        match result {
+            Some(translated) => {
+                self.needs_attention =
+                    translated.attention_reason(&self.original_text, translation_options);
+                self.record_machine_translation(translated.text.clone(), max_history);
+                self.translated_text = Some(translated.text);
+            }
+            None => self.needs_attention = Some(AttentionReason::MissingTranslation),
+        }
+    }
+    /// keeps the machine translation as the revert point and in [TranslationSegment::machine_translation_history],
+    /// which is cut down to the `max_history` latest ones
+    pub fn record_machine_translation(&mut self, text: String, max_history: usize) {
+        let changed = self
+            .machine_translation_history
+            .last()
+            .map(|variant| variant.text != text)
+            .unwrap_or(true);
+        if changed {
+            self.machine_translation_history
+                .push(MachineTranslationVariant {
+                    at: now(),
+                    text: text.clone(),
+                });
+        }
+        let excess = self
+            .machine_translation_history
+            .len()
+            .saturating_sub(max_history);
+        self.machine_translation_history.drain(..excess);
+        self.machine_translation = Some(text);
+    }
+    /// segments which nobody confirmed yet are (re)translated by the translation service
+    pub fn needs_translation(&self) -> bool {
+        matches!(
+            self.status(),
+            SegmentStatus::Untranslated | SegmentStatus::MachineTranslated
+        )
+    }
+    /// replaces the text extracted from the document, see [TranslationSegment::source_edited]
+    pub fn edit_original_text(&mut self, original_text: String) {
+        self.original_text = original_text;
+        self.source_edited = true;
+    }
+    /// puts the original's leading and trailing whitespace around a (possibly trimmed) translation,
+    /// so applied suggestions don't glue words together in the translated document
+    pub fn with_original_spacing(&self, translated_text: &str) -> String {
+        format!(
+            "{}{}{}",
+            crate::lint::leading_whitespace(&self.original_text),
+            translated_text.trim(),
+            crate::lint::trailing_whitespace(&self.original_text)
+        )
+    }
+    /// goes back to the last machine translation, dropping the confirmation,
+    /// returns false if the segment was never machine translated
+    pub fn revert_to_machine(&mut self) -> bool {
+        match self.machine_translation.clone() {
+            Some(machine_translation) => {
+                self.translated_text = Some(machine_translation);
+                self.confirmed = None;
+                true
+            }
+            None => false,
+        }
+    }
+    /// locked segments are translated as the original text, unlocking brings back the previous translation
+    pub fn set_locked(&mut self, locked: bool) {
+        match (self.locked, locked) {
+            (false, true) => {
+                self.translation_before_lock =
+                    self.translated_text.replace(self.original_text.clone());
+            }
+            (true, false) => self.translated_text = self.translation_before_lock.take(),
+            _ => {}
+        }
+        self.locked = locked;
+    }
+    /// marks current translation as confirmed and records it in the segment's history,
+    /// returns the confirmed text, or [None] if there is nothing to confirm yet
+    pub fn confirm(&mut self) -> Option<String> {
+        let translated_text = self.translated_text.clone()?;
+        self.needs_attention = None;
+        let previous = self.confirmed.replace(translated_text.clone());
+        self.history.push(SegmentEdit {
+            at: now(),
+            previous,
+            new: translated_text.clone(),
+        });
+        Some(translated_text)
+    }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FileFormat {
     Txt,
+    Adoc,
+    Rst,
+    Epub,
+    Docx,
+    /// input only, see [formats::pdf]
+    Pdf,
+}
+
+impl FileFormat {
+    pub const ALL: [Self; 6] = [
+        Self::Txt,
+        Self::Adoc,
+        Self::Rst,
+        Self::Epub,
+        Self::Docx,
+        Self::Pdf,
+    ];
+    /// format translated documents are written in
+    pub fn output_format(self) -> Self {
+        match self {
+            Self::Pdf => Self::Txt,
+            other => other,
+        }
+    }
+    /// extension of translated documents unless [TlumokConfig::output_extensions] says otherwise
+    pub fn output_extension(self) -> String {
+        self.output_format().to_string()
+    }
+    /// document is a zip package, only some of the files inside of it get translated
+    pub fn is_package(self) -> bool {
+        matches!(self, Self::Epub | Self::Docx)
+    }
+    /// markup documents are mostly headings, list items and table cells - fragments
+    /// that deepl would otherwise capitalize and punctuate like sentences
+    pub fn preserves_formatting_by_default(self) -> bool {
+        matches!(self, Self::Adoc | Self::Rst)
+    }
 }
 
 impl std::fmt::Display for FileFormat {
@@ -411,6 +2239,11 @@ impl std::fmt::Display for FileFormat {
             "{}",
             match self {
                 Self::Txt => "txt",
+                Self::Adoc => "adoc",
+                Self::Rst => "rst",
+                Self::Epub => "epub",
+                Self::Docx => "docx",
+                Self::Pdf => "pdf",
             }
         )
     }
@@ -426,73 +2259,500 @@ pub struct TranslationSegments {
     pub segments: TranslationSegmentMap,
 }
 
+/// how a workspace file is stored, workspaces are kept next to their documents
+/// as `<document name>.tlumok-workspace.<format>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceFormat {
+    #[default]
+    Toml,
+    /// handles multi-line texts without any escaping trouble
+    Json,
+    Ron,
+}
+
+impl WorkspaceFormat {
+    pub const ALL: [Self; 3] = [Self::Toml, Self::Json, Self::Ron];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Toml => "tlumok-workspace.toml",
+            Self::Json => "tlumok-workspace.json",
+            Self::Ron => "tlumok-workspace.ron",
+        }
+    }
+    /// format of a workspace file, [None] if the path is not a workspace file at all
+    pub fn of_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy();
+        let name = name
+            .strip_suffix(WorkspaceStorage::COMPRESSED_SUFFIX)
+            .unwrap_or(&name);
+        Self::ALL
+            .into_iter()
+            .find(|format| name.ends_with(&format!(".{}", format.extension())))
+    }
+    pub fn serialize(self, workspace: &TranslationWorkspace) -> Result<String> {
+        match self {
+            Self::Toml => toml::to_string_pretty(workspace).map_err(eyre::Report::from),
+            Self::Json => serde_json::to_string_pretty(workspace).map_err(eyre::Report::from),
+            Self::Ron => ron::ser::to_string_pretty(workspace, Default::default())
+                .map_err(eyre::Report::from),
+        }
+        .wrap_err_with(|| format!("serializing workspace as {self:?}"))
+    }
+}
+
+/// everything about how a workspace file is stored, both are picked by the file's extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WorkspaceStorage {
+    pub format: WorkspaceFormat,
+    /// gzipped, `.gz` is appended to the file name
+    pub compressed: bool,
+}
+
+impl WorkspaceStorage {
+    pub const COMPRESSED_SUFFIX: &'static str = ".gz";
+
+    pub fn all() -> impl Iterator<Item = Self> {
+        WorkspaceFormat::ALL.into_iter().flat_map(|format| {
+            [false, true]
+                .into_iter()
+                .map(move |compressed| Self { format, compressed })
+        })
+    }
+    /// [None] if the path is not a workspace file at all
+    pub fn of_path(path: &Path) -> Option<Self> {
+        Some(Self {
+            format: WorkspaceFormat::of_path(path)?,
+            compressed: path.to_string_lossy().ends_with(Self::COMPRESSED_SUFFIX),
+        })
+    }
+    pub fn extension(self) -> String {
+        match self.compressed {
+            true => format!("{}{}", self.format.extension(), Self::COMPRESSED_SUFFIX),
+            false => self.format.extension().to_string(),
+        }
+    }
+    pub fn encode(self, workspace: &TranslationWorkspace) -> Result<Vec<u8>> {
+        use std::io::Write;
+        let content = self.format.serialize(workspace)?;
+        if !self.compressed {
+            return Ok(content.into_bytes());
+        }
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(content.as_bytes())
+            .and_then(|_| encoder.finish())
+            .wrap_err("compressing workspace")
+    }
+    pub fn decode(self, content: &[u8]) -> Result<TranslationWorkspace> {
+        use std::io::Read;
+        let content = match self.compressed {
+            true => {
+                let mut decompressed = String::new();
+                flate2::read::GzDecoder::new(content)
+                    .read_to_string(&mut decompressed)
+                    .wrap_err("decompressing workspace")?;
+                decompressed
+            }
+            false => {
+                String::from_utf8(content.to_vec()).wrap_err("workspace is not valid utf-8")?
+            }
+        };
+        self.format.deserialize(&content)
+    }
+}
+/// how many directories deep [TranslationWorkspace::find_all] looks
+pub const WORKSPACE_SEARCH_DEPTH: usize = 4;
+
+/// the latest [schema] of workspace files, unknown fields are rejected
+/// rather than silently dropped on the next save
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct TranslationWorkspace {
+    /// see [schema::WORKSPACE_SCHEMA_VERSION]
+    pub schema_version: u32,
     pub tlumok_version: String,
+    /// [source_checksum] of the original document at the time the workspace was created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_checksum: Option<u32>,
+    /// see [OutputTemplate::TRANSLATED_STEM], can be corrected by hand
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translated_stem: Option<String>,
+    /// last threshold set with the gui's slider, used instead of
+    /// [TlumokConfig::min_suggestion_percent] for this document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_suggestion_percent: Option<u32>,
     pub original_document: OriginalDocument,
     pub translation_options: TlumokTranslationOptions,
     pub segments: TranslationSegments,
 }
+/// which segments of a workspace a command works on
+#[derive(Debug, Clone)]
+pub enum SegmentSelection {
+    All,
+    Keys(Vec<String>),
+    /// positions in document order
+    Range(std::ops::Range<usize>),
+}
+
+impl SegmentSelection {
+    pub fn contains(&self, position: usize, key: &str) -> bool {
+        match self {
+            SegmentSelection::All => true,
+            SegmentSelection::Keys(keys) => keys.iter().any(|selected| selected == key),
+            SegmentSelection::Range(range) => range.contains(&position),
+        }
+    }
+    /// fails on keys the workspace doesn't have, so a typo doesn't silently select nothing
+    pub fn check(&self, segments: &TranslationSegments) -> Result<()> {
+        if let SegmentSelection::Keys(keys) = self {
+            let unknown = keys
+                .iter()
+                .filter(|key| !segments.segments.contains_key(key.as_str()))
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            if !unknown.is_empty() {
+                eyre::bail!("unknown segments: {}", unknown.join(", "));
+            }
+        }
+        Ok(())
+    }
+}
+
 impl TranslationSegments {
+    /// machine translates the selected segments that need it, the rest is left untouched
+    /// `on_progress` gets how many of the pending segments are translated (and how many there are)
+    /// every time a batch comes back
     pub async fn translate(
-        self,
+        mut self,
         translation_service: &translation_service::TranslationService,
         translation_options: TlumokTranslationOptions,
+        selection: &SegmentSelection,
+        mut on_progress: impl FnMut(usize, usize),
     ) -> Result<Self> {
-        let segments = futures::stream::iter(self.segments)
-            .map(|(index, segment)| {
+        let locked = self.lock_ignored(&translation_service.ignore_patterns, selection);
+        if locked > 0 {
+            tracing::info!("locked {locked} segments matching the ignore patterns");
+        }
+        let pending = self
+            .pending(selection)
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        let batches = pending
+            .chunks(translation_service::TRANSLATION_BATCH_SIZE)
+            .map(|keys| {
+                let texts = keys
+                    .iter()
+                    .map(|key| self.segments[key].original_text.clone())
+                    .collect::<Vec<_>>();
+                (keys, texts)
+            })
+            .collect::<Vec<_>>();
+        let mut translated = futures::stream::iter(batches)
+            .map(|(keys, texts)| {
                 translation_service
                     .clone()
-                    .translate_segment(segment, translation_options)
-                    .map(|result| result.map(|translated| (index, translated)))
+                    .translate_texts(texts, translation_options)
+                    .map(move |results| results.map(|results| (keys, results)))
             })
-            .buffer_unordered(4)
-            .try_collect()
+            .buffer_unordered(4);
+        let mut done = 0;
+        while let Some((keys, results)) = translated
+            .try_next()
             .await
-            .wrap_err("translating document segments")?;
+            .wrap_err("translating document segments")?
+        {
+            self.set_machine_translations(
+                keys,
+                results,
+                translation_options,
+                translation_service.max_machine_translation_history,
+            );
+            done += keys.len();
+            on_progress(done, pending.len());
+            if crate::shutdown::is_requested() {
+                tracing::warn!(
+                    "interrupted after translating {done} of {} segments",
+                    pending.len()
+                );
+                break;
+            }
+        }
+        Ok(self)
+    }
+    /// stores the results of a batch, batches finish in any order, but every segment stays
+    /// where it is in the document
+    fn set_machine_translations(
+        &mut self,
+        keys: &[String],
+        results: Vec<Option<translation_service::MachineTranslation>>,
+        translation_options: TlumokTranslationOptions,
+        max_history: usize,
+    ) {
+        for (key, result) in keys.iter().zip(results) {
+            if let Some(segment) = self.segments.get_mut(key) {
+                segment.set_machine_translation(result, translation_options, max_history);
+            }
+        }
+    }
+    /// makes sure the slices of the segments are disjoint and in document order (separately for each
+    /// package entry), which is what [TranslationWorkspace::create_translated_document] relies on
+    pub fn check_slices(&self) -> Result<()> {
+        let mut previous: std::collections::HashMap<Option<&str>, (&str, usize)> =
+            Default::default();
+        let mut problems = vec![];
+        for (key, segment) in self.segments.iter() {
+            let OriginalDocumentSlice {
+                start,
+                len,
+                entry,
+                runs,
+            } = &segment.original_document_slice;
+            let end = start + len;
+            if let Some(run) = runs
+                .iter()
+                .find(|run| run.start < *start || run.end > end || run.start > run.end)
+            {
+                problems.push(format!(
+                    "[{key}] has a run ({run:?}) outside of its slice ({start}..{end})"
+                ));
+            }
+            if runs.windows(2).any(|pair| pair[0].end > pair[1].start) {
+                problems.push(format!("[{key}] has overlapping runs ({runs:?})"));
+            }
+            if let Some((previous_key, previous_end)) =
+                previous.insert(entry.as_deref(), (key, end))
+            {
+                if *start < previous_end {
+                    problems.push(format!(
+                        "[{key}] ({start}..{end}) overlaps [{previous_key}] (ending at {previous_end})"
+                    ));
+                }
+            }
+        }
+        if !problems.is_empty() {
+            eyre::bail!(
+                "segments point into overlapping parts of the document:\n{}",
+                problems.join("\n")
+            );
+        }
+        Ok(())
+    }
+    /// share of segments that are done (confirmed or locked), from 0 to 1
+    pub fn completion(&self) -> f32 {
+        match self.segments.len() {
+            0 => 0.0,
+            total => {
+                self.segments
+                    .values()
+                    .filter(|segment| segment.status().is_final())
+                    .count() as f32
+                    / total as f32
+            }
+        }
+    }
+    /// selected segments that [TranslationSegments::translate] sends for translation
+    pub fn pending<'a>(
+        &'a self,
+        selection: &'a SegmentSelection,
+    ) -> impl Iterator<Item = (&'a String, &'a TranslationSegment)> + 'a {
+        self.segments
+            .iter()
+            .enumerate()
+            .filter(|(position, (key, _))| selection.contains(*position, key))
+            .map(|(_, entry)| entry)
+            .filter(|(_, segment)| segment.needs_translation())
+    }
+    /// number of characters that will be sent to the translation service by [TranslationSegments::translate]
+    pub fn pending_char_count(&self, selection: &SegmentSelection) -> usize {
+        self.pending(selection)
+            .map(|(_, segment)| segment.original_text.chars().count())
+            .sum()
+    }
+    /// key of the `index`-th segment (in document order) of a document or one of its entries,
+    /// segments are generated in document order so regenerating an unchanged document gives the same keys
+    pub fn segment_key(entry: Option<&str>, index: usize) -> String {
+        match entry {
+            Some(entry) => format!("{entry}/segment_{index}"),
+            None => format!("segment_{index}"),
+        }
+    }
+    /// renames the segments to their [TranslationSegments::segment_key], in case the key format changed,
+    /// segment contents (and so their translations) stay as they are
+    pub fn with_normalized_keys(self) -> Self {
+        let mut counters: std::collections::HashMap<Option<String>, usize> = Default::default();
+        let segments = self
+            .segments
+            .into_iter()
+            .map(|(key, segment)| {
+                let entry = segment.original_document_slice.entry.clone();
+                let index = counters.entry(entry.clone()).or_default();
+                let normalized = Self::segment_key(entry.as_deref(), *index);
+                *index += 1;
+                if normalized != key {
+                    tracing::debug!("renaming segment [{key}] to [{normalized}]");
+                }
+                (normalized, segment)
+            })
+            .collect();
+        Self { segments }
+    }
+    pub async fn generate_from(text: &str, max_segment_length: usize) -> Result<Self> {
+        Self::generate_from_ranges(
+            text,
+            std::slice::from_ref(&(0..text.len())),
+            max_segment_length,
+        )
+        .await
+    }
+    /// like [TranslationSegments::generate_from], but only the given byte ranges are split into segments
+    pub async fn generate_from_ranges(
+        text: &str,
+        ranges: &[std::ops::Range<usize>],
+        max_segment_length: usize,
+    ) -> Result<Self> {
+        tracing::info!("generating segments");
+        let segments = Self::split_ranges(text, ranges, None, max_segment_length)
+            .enumerate()
+            .map(|(index, segment)| (Self::segment_key(None, index), segment))
+            .collect();
         Ok(Self { segments })
     }
-    pub async fn generate_from(text: &str) -> Result<Self> {
-        use unicode_segmentation::UnicodeSegmentation;
+    /// segments of documents made of multiple files (`(entry, content)`),
+    /// keys are prefixed with the entry so that they stay unique
+    pub async fn generate_from_entries(
+        file_format: FileFormat,
+        entries: &[(String, String)],
+        max_segment_length: usize,
+    ) -> Result<Self> {
         tracing::info!("generating segments");
-        let empty_segments: Vec<_> = text
-            .split_sentence_bound_indices()
-            .into_iter()
-            .map(|(start, sentence)| TranslationSegment {
+        let segments = entries
+            .iter()
+            .flat_map(|(entry, content)| {
+                let segments: Vec<_> = match file_format {
+                    FileFormat::Docx => formats::docx::sentence_pieces(content, max_segment_length)
+                        .into_iter()
+                        .map(|pieces| TranslationSegment::from_pieces(content, pieces, entry))
+                        .collect(),
+                    _ => {
+                        let ranges = formats::translatable_ranges(file_format, content);
+                        Self::split_ranges(content, &ranges, Some(entry), max_segment_length)
+                            .collect()
+                    }
+                };
+                segments
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(index, mut segment)| {
+                        segment.original_text =
+                            formats::decoded(file_format, &segment.original_text);
+                        (Self::segment_key(Some(entry), index), segment)
+                    })
+            })
+            .collect();
+        Ok(Self { segments })
+    }
+    fn split_ranges<'a>(
+        text: &'a str,
+        ranges: &'a [std::ops::Range<usize>],
+        entry: Option<&'a str>,
+        max_segment_length: usize,
+    ) -> impl Iterator<Item = TranslationSegment> + 'a {
+        use unicode_segmentation::UnicodeSegmentation;
+        ranges
+            .iter()
+            .flat_map(|range| {
+                text[range.clone()]
+                    .split_sentence_bound_indices()
+                    .map(move |(start, sentence)| (range.start + start, sentence))
+            })
+            .flat_map(move |(start, sentence)| {
+                formats::split_overlong(sentence, max_segment_length)
+                    .into_iter()
+                    .map(move |(offset, piece)| (start + offset, piece))
+            })
+            .map(move |(start, sentence)| TranslationSegment {
                 original_text: sentence.to_string(),
-                translated_text: NOT_TRANSLATED_MARKER.to_string(),
+                translated_text: None,
                 confirmed: None,
+                locked: false,
+                translation_before_lock: None,
+                source_edited: false,
+                keep_out_of_memory: false,
+                note: None,
                 original_document_slice: OriginalDocumentSlice {
                     start,
                     len: sentence.len(),
+                    entry: entry.map(ToOwned::to_owned),
+                    runs: vec![],
                 },
+                history: vec![],
+                needs_attention: None,
+                machine_translation: None,
+                machine_translation_history: vec![],
             })
-            .collect();
-        tracing::info!("translating segments");
-
-        let segments = empty_segments
-            .into_iter()
-            .enumerate()
-            .map(|(index, segment)| (format!("segment_{index}"), segment))
-            .collect();
-        Ok(Self { segments })
     }
 
     pub async fn for_document(
         OriginalDocument { path, file_format }: &OriginalDocument,
+        max_segment_length: usize,
     ) -> Result<Self> {
-        match file_format {
+        let segments = match file_format {
             FileFormat::Txt => {
                 let content = tokio::fs::read_to_string(path)
                     .await
                     .wrap_err_with(|| format!("opening {path:?} for segment generation"))?;
-                Ok(Self::generate_from(&content)
+                Self::generate_from(&content, max_segment_length)
+                    .await
+                    .context("generating default translation segments")?
+            }
+            FileFormat::Adoc | FileFormat::Rst => {
+                let content = tokio::fs::read_to_string(path)
+                    .await
+                    .wrap_err_with(|| format!("opening {path:?} for segment generation"))?;
+                let ranges = formats::translatable_ranges(*file_format, &content);
+                Self::generate_from_ranges(&content, &ranges, max_segment_length)
                     .await
-                    .context("generating default translation segments")?)
+                    .wrap_err_with(|| format!("generating {file_format} translation segments"))?
             }
+            FileFormat::Pdf => {
+                let document = tokio::fs::read(path)
+                    .await
+                    .wrap_err_with(|| format!("opening {path:?} for segment generation"))?;
+                let content = formats::pdf::extracted_text(&document)
+                    .wrap_err_with(|| format!("reading text of {path:?}"))?;
+                Self::generate_from(&content, max_segment_length)
+                    .await
+                    .wrap_err_with(|| format!("generating {file_format} translation segments"))?
+            }
+            FileFormat::Epub | FileFormat::Docx => {
+                let package = tokio::fs::read(path)
+                    .await
+                    .wrap_err_with(|| format!("opening {path:?} for segment generation"))?;
+                let documents = formats::content_documents(*file_format, &package)
+                    .wrap_err_with(|| format!("reading content documents of {path:?}"))?;
+                Self::generate_from_entries(*file_format, &documents, max_segment_length)
+                    .await
+                    .wrap_err_with(|| format!("generating {file_format} translation segments"))?
+            }
+        };
+        // otherwise the workspace would have nothing (or only whitespace) to translate
+        if segments
+            .segments
+            .values()
+            .all(|segment| segment.original_text.trim().is_empty())
+        {
+            eyre::bail!("{path:?} has no text to translate, there is no point in creating a workspace for it");
         }
+        Ok(segments)
     }
 }
+/// cheap way of telling if the original document changed since the workspace was created
+pub fn source_checksum(source: &[u8]) -> u32 {
+    crc32fast::hash(source)
+}
+
 impl OriginalDocument {
     pub fn from_file(path: &Path) -> Result<Self> {
         if !path.exists() {
@@ -502,8 +2762,13 @@ impl OriginalDocument {
             .extension()
             .map(|extension| extension.to_string_lossy().to_string());
 
-        let file_format = match extension.as_ref().map(|v| v.as_str()) {
+        let file_format = match extension.as_deref() {
             Some("txt") => FileFormat::Txt,
+            Some("adoc" | "asciidoc" | "asc") => FileFormat::Adoc,
+            Some("rst" | "rest") => FileFormat::Rst,
+            Some("epub") => FileFormat::Epub,
+            Some("docx") => FileFormat::Docx,
+            Some("pdf") => FileFormat::Pdf,
             e => eyre::bail!("bad extension :: {e:?}"),
         };
         Ok(Self {
@@ -517,52 +2782,289 @@ pub type AppTime = chrono::NaiveDateTime;
 pub fn now() -> AppTime {
     chrono::Local::now().naive_local()
 }
+/// last modification time of a file
+pub fn modified_at(path: &Path) -> Result<AppTime> {
+    let modified = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .wrap_err_with(|| format!("reading modification time of [{path:?}]"))?;
+    Ok(chrono::DateTime::<chrono::Local>::from(modified).naive_local())
+}
+
+static FILE_SAFE_DATETIME: &str = "%Y-%m-%d--%H-%M-%S";
+
+/// decides where translated documents end up
+///
+/// available tokens in `filename_template`:
+/// - `{stem}` - original file name without the extension
+/// - `{translated_stem}` - `{stem}` translated, see [OutputTemplate::TRANSLATED_STEM]
+/// - `{ext}` - extension of the output format, see [OutputTemplate::extension_for]
+/// - `{lang}` - target language code, eg. `pl`
+/// - `{date}` - time of saving
+#[derive(Debug, Clone)]
+pub struct OutputTemplate {
+    pub directory: Option<PathBuf>,
+    pub filename_template: String,
+    /// `{ext}` by the original document's format, see [TlumokConfig::output_extensions]
+    pub extensions: IndexMap<String, String>,
+}
+
+impl OutputTemplate {
+    /// extensions go straight into file names, so they can't be empty or have a path in them
+    pub fn check_extension(extension: &str) -> Result<()> {
+        if extension.is_empty() {
+            eyre::bail!("extension can't be empty");
+        }
+        if extension.starts_with('.') {
+            eyre::bail!("extension [{extension}] should be given without the leading dot");
+        }
+        if extension.contains(['/', '\\']) {
+            eyre::bail!("extension [{extension}] can't contain a path separator");
+        }
+        Ok(())
+    }
+    pub fn extension_for(&self, file_format: FileFormat) -> String {
+        self.extensions
+            .get(&file_format.to_string())
+            .cloned()
+            .unwrap_or_else(|| file_format.output_extension())
+    }
+    pub fn path_for(&self, workspace: &TranslationWorkspace) -> PathBuf {
+        let OriginalDocument { path, file_format } = &workspace.original_document;
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let lang = workspace
+            .translation_options
+            .target_language
+            .to_deepl_language()
+            .to_lowercase();
+        let date = now().format(FILE_SAFE_DATETIME).to_string();
+        let translated_stem = workspace.translated_stem.as_deref().unwrap_or(&stem);
+        let file_name = self
+            .filename_template
+            .replace(Self::TRANSLATED_STEM, translated_stem)
+            .replace("{stem}", &stem)
+            .replace("{ext}", &self.extension_for(*file_format))
+            .replace("{lang}", &lang)
+            .replace("{date}", &date);
+        let document_dir = path.parent().map(Path::to_owned).unwrap_or_default();
+        let directory = match &self.directory {
+            Some(directory) => document_dir.join(directory),
+            None => document_dir,
+        };
+        directory.join(file_name)
+    }
+}
 
-static FILE_SAFE_DATETIME: &'static str = "%Y-%m-%d--%H-%M-%S";
 impl TranslationWorkspace {
-    pub async fn save_translated_document(self) -> Result<()> {
-        let document_path = self.original_document.path.clone();
-        let original_extension = self.original_document.file_format.clone();
-        let translated_document = self.create_translated_document().await?;
-        let now_pretty = now().format(FILE_SAFE_DATETIME);
-        let translated_document_path = document_path.with_extension(format!(
-            "tlumok-translated.{now_pretty}.{original_extension}"
-        ));
-        tokio::fs::write(&translated_document_path, &translated_document)
+    /// writes the translated document to `output_path`, returns the path it was written to
+    pub async fn save_translated_document(self, output_path: PathBuf) -> Result<PathBuf> {
+        let translated_document = match self.original_document.file_format.is_package() {
+            true => self.create_translated_package().await?,
+            false => self.create_translated_document().await?.into_bytes(),
+        };
+        if let Some(parent) = output_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .wrap_err_with(|| format!("creating output directory {parent:?}"))?;
+        }
+        filesystem::write_atomically(&output_path, &translated_document)
             .await
-            .wrap_err_with(|| {
-                format!("saving translated document to {translated_document_path:?}")
-            })?;
-        Ok(())
+            .wrap_err_with(|| format!("saving translated document to {output_path:?}"))?;
+        tracing::info!("translated document saved to [{output_path:?}]");
+        Ok(output_path)
+    }
+    pub async fn create_translated_document(self) -> Result<String> {
+        let Self {
+            original_document: OriginalDocument { path, file_format },
+            segments: TranslationSegments { segments },
+            ..
+        } = self.validated()?;
+        if file_format.is_package() {
+            eyre::bail!("{file_format} documents are packages, not text, see [TranslationWorkspace::create_translated_package]");
+        }
+        let content = tokio::fs::read(&path)
+            .await
+            .map_err(eyre::Report::from)
+            .and_then(|source| Self::original_content(file_format, source))
+            .wrap_err_with(|| format!("reading original document at [{path:?}]"))?;
+        let translated_content =
+            Self::apply_translations(file_format, content, segments.into_iter())?;
+
+        Ok(formats::reassembled(file_format, translated_content))
+    }
+    /// translated document for formats made of multiple files, see [OriginalDocumentSlice::entry]
+    pub async fn create_translated_package(self) -> Result<Vec<u8>> {
+        let Self {
+            original_document: OriginalDocument { path, file_format },
+            segments: TranslationSegments { segments },
+            ..
+        } = self.validated()?;
+        let package = tokio::fs::read(&path)
+            .await
+            .wrap_err_with(|| format!("reading original document at [{path:?}]"))?;
+        let mut entries: IndexMap<String, Vec<(String, TranslationSegment)>> = IndexMap::new();
+        for (key, segment) in segments.into_iter() {
+            let entry = segment
+                .original_document_slice
+                .entry
+                .clone()
+                .ok_or_else(|| {
+                    eyre::eyre!("segment [{key}] does not point into any file of the package")
+                })?;
+            entries.entry(entry).or_default().push((key, segment));
+        }
+        let replacements = entries
+            .into_iter()
+            .map(|(entry, segments)| {
+                let content = formats::package::read_entry(&package, &entry)?;
+                let translated_content =
+                    Self::apply_translations(file_format, content, segments.into_iter())
+                        .wrap_err_with(|| format!("translating [{entry}]"))?;
+                Ok((entry, formats::reassembled(file_format, translated_content)))
+            })
+            .collect::<Result<_>>()?;
+        formats::package::repackaged(&package, &replacements)
+            .wrap_err_with(|| format!("repackaging [{path:?}]"))
+    }
+    /// text the slices of a (non-package) document point into
+    fn original_content(file_format: FileFormat, source: Vec<u8>) -> Result<String> {
+        match file_format {
+            FileFormat::Pdf => formats::pdf::extracted_text(&source),
+            _ => String::from_utf8(source).wrap_err("original document is not valid utf-8"),
+        }
+    }
+    /// byte ranges of `content` that make up the segment, checked to be within the document
+    fn slice_runs(
+        key: &str,
+        content: &str,
+        OriginalDocumentSlice {
+            start, len, runs, ..
+        }: &OriginalDocumentSlice,
+    ) -> Result<Vec<std::ops::Range<usize>>> {
+        let runs = match runs.is_empty() {
+            true => std::iter::once(*start..(start + len)).collect(),
+            false => runs.clone(),
+        };
+        if let Some(run) = runs.iter().find(|run| {
+            run.end > content.len()
+                || !content.is_char_boundary(run.start)
+                || !content.is_char_boundary(run.end)
+        }) {
+            eyre::bail!(
+                "segment [{key}] points outside of the document ({run:?}, document is {} bytes long)",
+                content.len()
+            );
+        }
+        Ok(runs)
     }
-    pub async fn create_translated_document(self) -> Result<String> {
-        let Self {
-            original_document: OriginalDocument { path, .. },
-            segments: TranslationSegments { segments },
-            ..
-        } = self.validated()?;
-        let mut translated_content = tokio::fs::read_to_string(&path)
-            .await
-            .wrap_err_with(|| format!("reading original document at [{path:?}]"))?;
+    /// text of the segment's slice of `content`, comparable with [TranslationSegment::original_text]
+    fn slice_text(
+        file_format: FileFormat,
+        key: &str,
+        content: &str,
+        slice: &OriginalDocumentSlice,
+    ) -> Result<String> {
+        let raw: String = Self::slice_runs(key, content, slice)?
+            .into_iter()
+            .map(|run| &content[run])
+            .collect();
+        Ok(formats::decoded(file_format, &raw))
+    }
+    /// puts translations in place of the original text, segments have to be in document order
+    fn apply_translations(
+        file_format: FileFormat,
+        mut content: String,
+        segments: impl DoubleEndedIterator<Item = (String, TranslationSegment)>,
+    ) -> Result<String> {
         for (
-            _,
+            key,
             TranslationSegment {
                 original_text,
                 translated_text,
-                original_document_slice: OriginalDocumentSlice { start, len },
+                source_edited,
+                original_document_slice,
                 ..
             },
-        ) in segments.into_iter().rev()
+        ) in segments.rev()
         {
-            let range = start..(start + len);
-            let document_content = &translated_content[range.clone()];
-            if document_content != original_text {
-                eyre::bail!("original text from workspace file did not match actual contents of document\ndocument content: [{document_content}]\n according to workspace document: [{original_text}]");
+            let runs = Self::slice_runs(&key, &content, &original_document_slice)?;
+            let document_content: String = runs.iter().map(|run| &content[run.clone()]).collect();
+            let document_text = formats::decoded(file_format, &document_content);
+            if !source_edited && document_text != original_text {
+                eyre::bail!("original text from workspace file did not match actual contents of document\ndocument content: [{document_text}]\n according to workspace document: [{original_text}]");
+            }
+            // untranslated segments are left as they were in the original
+            let translated_text = match translated_text {
+                Some(translated_text) => formats::encoded(file_format, &translated_text),
+                None => document_content,
+            };
+            // whole translation goes into the first run, the rest of them are emptied
+            for (index, run) in runs.into_iter().enumerate().rev() {
+                content.replace_range(
+                    run,
+                    match index {
+                        0 => translated_text.as_str(),
+                        _ => "",
+                    },
+                );
             }
-            translated_content.replace_range(range, &translated_text);
         }
-
-        Ok(translated_content)
+        Ok(content)
+    }
+    /// keys of segments whose original text no longer matches the original document,
+    /// the full comparison is skipped if the document's checksum did not change
+    pub async fn stale_segments(&self) -> Result<Vec<String>> {
+        let OriginalDocument { path, file_format } = &self.original_document;
+        let source = tokio::fs::read(path)
+            .await
+            .wrap_err_with(|| format!("reading original document at [{path:?}]"))?;
+        if self.source_checksum == Some(source_checksum(&source)) {
+            return Ok(vec![]);
+        }
+        tracing::info!("[{path:?}] changed since the workspace was created, comparing segments");
+        let mut contents: std::collections::HashMap<Option<&str>, String> = Default::default();
+        let mut stale = vec![];
+        for (key, segment) in self
+            .segments
+            .segments
+            .iter()
+            .filter(|(_, segment)| !segment.source_edited)
+        {
+            let slice = &segment.original_document_slice;
+            let content = match contents.entry(slice.entry.as_deref()) {
+                std::collections::hash_map::Entry::Occupied(content) => content.into_mut(),
+                std::collections::hash_map::Entry::Vacant(vacant) => {
+                    vacant.insert(match slice.entry.as_deref() {
+                        Some(entry) => formats::package::read_entry(&source, entry)?,
+                        None => Self::original_content(*file_format, source.clone())
+                            .wrap_err_with(|| format!("reading [{path:?}]"))?,
+                    })
+                }
+            };
+            let matches = Self::slice_text(*file_format, key, content, slice)
+                .map(|text| text == segment.original_text)
+                .unwrap_or(false);
+            if !matches {
+                stale.push(key.clone());
+            }
+        }
+        Ok(stale)
+    }
+    /// logs a warning when [TranslationWorkspace::stale_segments] finds anything,
+    /// so that nobody spends time translating text that is not in the document anymore
+    pub async fn warn_if_stale(&self) -> Result<()> {
+        let stale = self.stale_segments().await?;
+        if !stale.is_empty() {
+            tracing::warn!(
+                "original document [{:?}] changed since the workspace was created, {} segments no longer match it (bring it up to date with the `reconcile` command): {}",
+                self.original_document.path,
+                stale.len(),
+                stale.join(", ")
+            );
+        }
+        Ok(())
     }
     pub fn validated(self) -> Result<Self> {
         let validated = &self;
@@ -578,85 +3080,411 @@ impl TranslationWorkspace {
             .segments
             .segments
             .iter()
-            .find(|(_, segment)| !segment.confirmed.is_none())
+            .find(|(_, segment)| !segment.status().is_final())
         {
-            eyre::bail!("segment [{index}] is not checked\n\n{segment:#?}");
+            eyre::bail!(
+                "segment [{index}] is not checked ({})\n\n{segment:#?}",
+                segment.status()
+            );
         }
+        validated.segments.check_slices()?;
 
         Ok(self)
     }
-    pub async fn translate(self, translation_service: &TranslationService) -> Result<Self> {
+    /// see [TranslationSegments::translate]
+    pub async fn translate(
+        self,
+        translation_service: &TranslationService,
+        selection: &SegmentSelection,
+        on_progress: impl FnMut(usize, usize),
+    ) -> Result<Self> {
         let translation_options = self.translation_options;
         Ok(Self {
             segments: self
                 .segments
-                .translate(translation_service, translation_options)
+                .translate(
+                    translation_service,
+                    translation_options,
+                    selection,
+                    on_progress,
+                )
                 .await?,
             ..self
         })
     }
-    pub fn default_path_for_document(OriginalDocument { path, .. }: &OriginalDocument) -> PathBuf {
-        path.with_extension("tlumok-workspace.toml")
+    /// brings workspaces written by older versions up to date
+    pub fn migrated(mut self) -> Self {
+        let current_version = clap::crate_version!();
+        if self.tlumok_version != current_version {
+            tracing::info!(
+                "upgrading workspace written by tlumok {} to {current_version}",
+                self.tlumok_version
+            );
+            self.tlumok_version = current_version.to_string();
+        }
+        // segments are in document order (see [TranslationSegments::check_slices]), so this is a no-op
+        // for workspaces using the current key format
+        self.segments = self.segments.with_normalized_keys();
+        self
+    }
+    pub fn path_for_document(
+        OriginalDocument { path, .. }: &OriginalDocument,
+        workspace_storage: WorkspaceStorage,
+    ) -> PathBuf {
+        path.with_extension(workspace_storage.extension())
+    }
+    /// existing workspace of the document in whichever format it was saved,
+    /// or where a new uncompressed [WorkspaceFormat::Toml] one would go
+    pub fn default_path_for_document(original_document: &OriginalDocument) -> PathBuf {
+        WorkspaceStorage::all()
+            .map(|storage| Self::path_for_document(original_document, storage))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| {
+                Self::path_for_document(original_document, WorkspaceStorage::default())
+            })
+    }
+    /// paths of all workspace files under `root`, hidden directories are skipped
+    pub fn find_all(root: &Path) -> Result<Vec<PathBuf>> {
+        fn find(dir: &Path, depth: usize, found: &mut Vec<PathBuf>) -> Result<()> {
+            for entry in std::fs::read_dir(dir).wrap_err_with(|| format!("reading [{dir:?}]"))? {
+                let path = entry.wrap_err("reading dir entry")?.path();
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                match path.is_dir() {
+                    true if depth > 0 && !name.starts_with('.') => find(&path, depth - 1, found)?,
+                    true => {}
+                    false if WorkspaceStorage::of_path(&path).is_some() => found.push(path),
+                    false => {}
+                }
+            }
+            Ok(())
+        }
+        let mut found = vec![];
+        find(root, WORKSPACE_SEARCH_DEPTH, &mut found)?;
+        Ok(found)
     }
 
     #[tracing::instrument]
     pub async fn load(path: &Path) -> Result<Self> {
-        let content = tokio::fs::read_to_string(path)
+        let content = tokio::fs::read(path)
             .await
             .wrap_err_with(|| format!("reading workspace from {path:?}"))?;
-        let workspace = toml::from_str(&content)
+        let workspace = WorkspaceStorage::of_path(path)
+            .unwrap_or_default()
+            .decode(&content)
             .wrap_err_with(|| format!("reading contents of [{path:?}] file"))?;
         tracing::info!("loaded workspace to [{path:?}]");
+        workspace
+            .segments
+            .check_slices()
+            .wrap_err_with(|| format!("validating [{path:?}]"))?;
 
-        Ok(workspace)
+        Ok(workspace.migrated())
     }
 
     #[tracing::instrument(skip(self))]
     pub async fn save(&self, path: &Path) -> Result<()> {
-        let content = toml::to_string_pretty(self).wrap_err("serializing workspace")?;
-        tokio::fs::write(path, &content)
+        let content = WorkspaceStorage::of_path(path)
+            .unwrap_or_default()
+            .encode(self)?;
+        filesystem::write_atomically(path, &content)
             .await
             .wrap_err_with(|| format!("writing workspace to [{path:?}]"))?;
         tracing::info!("saved workspace to [{path:?}]");
         Ok(())
     }
 
-    pub async fn for_document(original_document: OriginalDocument) -> Result<Self> {
-        let segments = TranslationSegments::for_document(&original_document)
+    pub async fn for_document(
+        original_document: OriginalDocument,
+        translation_options: TlumokTranslationOptions,
+        max_segment_length: usize,
+    ) -> Result<Self> {
+        let segments = TranslationSegments::for_document(&original_document, max_segment_length)
             .await
             .context("generating translation segments")?;
+        let preserve_formatting = translation_options.preserve_formatting.unwrap_or_else(|| {
+            original_document
+                .file_format
+                .preserves_formatting_by_default()
+        });
+        let translation_options = TlumokTranslationOptions {
+            preserve_formatting: Some(preserve_formatting),
+            ..translation_options
+        };
+        let source = tokio::fs::read(&original_document.path)
+            .await
+            .wrap_err_with(|| {
+                format!(
+                    "reading original document at [{:?}]",
+                    original_document.path
+                )
+            })?;
         Ok(Self {
             original_document,
             segments,
+            schema_version: schema::WORKSPACE_SCHEMA_VERSION,
             tlumok_version: clap::crate_version!().to_string(),
-            translation_options: Default::default(),
+            source_checksum: Some(source_checksum(&source)),
+            translated_stem: None,
+            min_suggestion_percent: None,
+            translation_options,
         })
     }
-    pub async fn get_or_create_for_document(original_document: OriginalDocument) -> Result<Self> {
+    /// `workspace_storage` is only used when a new workspace is created
+    pub async fn get_or_create_for_document(
+        original_document: OriginalDocument,
+        translation_options: TlumokTranslationOptions,
+        max_segment_length: usize,
+        workspace_storage: WorkspaceStorage,
+    ) -> Result<Self> {
         let default_path = Self::default_path_for_document(&original_document);
-        let translation_workspace = if default_path.exists() {
-            Self::load(&default_path).await?
+        let (path, translation_workspace) = if default_path.exists() {
+            tracing::info!("workspace [{default_path:?}] already exists, keeping it");
+            let translation_workspace = Self::load(&default_path).await?;
+            translation_workspace.warn_if_stale().await?;
+            (default_path, translation_workspace)
         } else {
-            Self::for_document(original_document).await?
+            let path = Self::path_for_document(&original_document, workspace_storage);
+            tracing::info!("creating new workspace at [{path:?}]");
+            let translation_workspace =
+                Self::for_document(original_document, translation_options, max_segment_length)
+                    .await?;
+            (path, translation_workspace)
         };
-        translation_workspace.save(&default_path).await?;
+        translation_workspace.save(&path).await?;
         Ok(translation_workspace)
     }
 
-    pub async fn get_or_create_for_path(path: PathBuf) -> Result<Self> {
+    pub async fn get_or_create_for_path(
+        path: PathBuf,
+        translation_options: TlumokTranslationOptions,
+        max_segment_length: usize,
+        workspace_storage: WorkspaceStorage,
+    ) -> Result<Self> {
         let original_document = OriginalDocument::from_file(&path)?;
-        Self::get_or_create_for_document(original_document).await
+        Self::get_or_create_for_document(
+            original_document,
+            translation_options,
+            max_segment_length,
+            workspace_storage,
+        )
+        .await
+    }
+}
+
+/// asks a yes/no question on the terminal, anything other than "yes" / "y" counts as no
+pub fn confirm_on_stdin(question: &str) -> Result<bool> {
+    use std::io::Write;
+    eprint!("{question} [y/N] ");
+    std::io::stderr().flush().wrap_err("flushing stderr")?;
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .wrap_err("reading answer")?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// machine translates the selected segments of a document's workspace,
+/// without a translation service it only lists what would be sent
+async fn translate_document(
+    file: PathBuf,
+    selection: &SegmentSelection,
+    config: &TlumokConfig,
+    translation_service: Option<&TranslationService>,
+) -> Result<()> {
+    let file = file.canonicalize()?;
+    Path::try_exists(&file).wrap_err("opening document for translation")?;
+    let config = config.clone().for_document(&file)?;
+    let original_document = OriginalDocument::from_file(&file)
+        .wrap_err_with(|| format!("opening original document {file:?}"))?;
+    let default_path = TranslationWorkspace::default_path_for_document(&original_document);
+    default_path
+        .try_exists()
+        .wrap_err("translation workspace does not exist")?;
+    let mut translation_workspace = TranslationWorkspace::load(&default_path).await?;
+    selection.check(&translation_workspace.segments)?;
+    // ignored segments are never sent, so they're not counted either
+    let locked = translation_workspace
+        .segments
+        .lock_ignored(&config.ignore_patterns()?, selection);
+    if locked > 0 {
+        tracing::info!("locked {locked} segments matching the ignore patterns");
+    }
+    let translation_service = match translation_service {
+        Some(translation_service) => translation_service.with_config(&config),
+        None => {
+            let pending = translation_workspace
+                .segments
+                .pending(selection)
+                .collect::<Vec<_>>();
+            for (key, segment) in pending.iter() {
+                println!("{key}\t{}", segment.original_text.chars().count());
+            }
+            println!(
+                "{} segments, {} characters would be sent for translation",
+                pending.len(),
+                translation_workspace.segments.pending_char_count(selection)
+            );
+            return Ok(());
+        }
+    };
+    translation_workspace.warn_if_stale().await?;
+    // what's translated by the time ctrl+c is pressed is already paid for, so it's saved
+    let _handled = shutdown::handled_here();
+    tracing::info!(
+        "sending {} characters for translation",
+        translation_workspace.segments.pending_char_count(selection)
+    );
+    // a bar when someone is watching, lines in the log otherwise
+    let progress =
+        indicatif::ProgressBar::with_draw_target(0, indicatif::ProgressDrawTarget::stderr());
+    progress.set_style(
+        indicatif::ProgressStyle::default_bar()
+            .template("{bar:40} {pos}/{len} segments, {elapsed} elapsed, eta {eta}"),
+    );
+    let translation_workspace = translation_workspace
+        .translate(
+            &translation_service,
+            selection,
+            |done, total| match progress.is_hidden() {
+                true => tracing::info!("translated {done}/{total} segments"),
+                false => {
+                    // ignored segments get locked first, so the total is only known from here
+                    progress.set_length(total as u64);
+                    progress.set_position(done as u64);
+                }
+            },
+        )
+        .await;
+    progress.finish_and_clear();
+    let translation_workspace = translation_workspace?;
+    translation_workspace.save(&default_path).await?;
+    if shutdown::is_requested() {
+        eyre::bail!("interrupted, segments translated so far were saved to [{default_path:?}]");
+    }
+    let needing_attention = translation_workspace
+        .segments
+        .segments
+        .values()
+        .filter(|segment| segment.needs_attention.is_some())
+        .count();
+    if needing_attention > 0 {
+        tracing::warn!(
+            "{needing_attention} segments need attention, review them before confirming"
+        );
+    }
+    Ok(())
+}
+
+async fn initialize_document(file: PathBuf, force: bool, config: &TlumokConfig) -> Result<()> {
+    let file = file.canonicalize()?;
+    Path::try_exists(&file).wrap_err("opening document for translation")?;
+    let config = &config.clone().for_document(&file)?;
+    let original_document = OriginalDocument::from_file(&file)
+        .wrap_err_with(|| format!("opening original document {file:?}"))?;
+    let default_path = TranslationWorkspace::default_path_for_document(&original_document);
+    let overwrite = default_path.exists()
+        && force
+        && confirm_on_stdin(&format!(
+            "[{default_path:?}] already exists, all translations in it will be lost. regenerate it?"
+        ))?;
+    if default_path.exists() && !overwrite {
+        tracing::error!(
+            "[{default_path:?}] already exists, keeping the existing workspace (use --force to regenerate it)"
+        );
+        return Ok(());
+    }
+    // regenerated workspaces keep their format
+    let default_path = match overwrite {
+        true => default_path,
+        false => {
+            TranslationWorkspace::path_for_document(&original_document, config.workspace_storage())
+        }
+    };
+    let translation_workspace = TranslationWorkspace::for_document(
+        original_document,
+        config.default_translation_options,
+        config.max_segment_length,
+    )
+    .await
+    .wrap_err_with(|| format!("creating workspace for [{file:?}]"))?;
+    translation_workspace.save(&default_path).await?;
+    tracing::info!("new workspace generated at [{default_path:?}]");
+    Ok(())
+}
+
+async fn apply_document(
+    file: PathBuf,
+    output: Option<PathBuf>,
+    extension: Option<String>,
+    compare: bool,
+    config: &TlumokConfig,
+) -> Result<()> {
+    let file = file.canonicalize()?;
+    Path::try_exists(&file).wrap_err("opening document for translation")?;
+    let config = &config.clone().for_document(&file)?;
+    let original_document = OriginalDocument::from_file(&file)
+        .wrap_err_with(|| format!("opening original document {file:?}"))?;
+    let default_path = TranslationWorkspace::default_path_for_document(&original_document);
+    let mut translation_workspace = TranslationWorkspace::load(&default_path).await?;
+    let mut output_template = config.output_template();
+    if let Some(extension) = extension {
+        output_template
+            .extensions
+            .insert(original_document.file_format.to_string(), extension);
+    }
+    if output.is_none()
+        && output_template.translates_stem()
+        && translation_workspace
+            .ensure_translated_stem(&TranslationService::new(config))
+            .await?
+    {
+        translation_workspace.save(&default_path).await?;
+    }
+    let translation_workspace = translation_workspace.validated()?;
+    let output = match output {
+        Some(output) => output,
+        None => output_template.path_for(&translation_workspace),
+    };
+    let previous = match compare {
+        true => output_template.previous_output(&translation_workspace, &output)?,
+        false => None,
+    };
+    let file_format = translation_workspace
+        .original_document
+        .file_format
+        .output_format();
+    let output = translation_workspace
+        .save_translated_document(output)
+        .await?;
+    match (compare, previous) {
+        (false, _) => {}
+        (true, None) => {
+            println!("there is no previous translated document of [{file:?}] to compare with")
+        }
+        (true, Some(previous)) => {
+            match compare::compare_documents(file_format, &previous, &output).await? {
+                Some(report) => println!("{report}"),
+                None => println!("nothing changed since [{previous:?}]"),
+            }
+        }
     }
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let logs_dir = filesystem::base_directory()?.join("logs");
+    let cli = Cli::parse();
+    // before logging starts, so the old logs can still be moved
+    let migrated = filesystem::migrate_from_executable_directory();
+    let logs_dir = filesystem::data_directory()?.join("logs");
     let file_appender = tracing_appender::rolling::daily(&logs_dir, "log.txt");
     let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
     let subscriber = tracing_subscriber::registry()
-        .with(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
-        .with(fmt::Layer::new().with_writer(std::io::stdout))
+        .with(EnvFilter::from_default_env().add_directive(cli.log_level().into()))
+        .with(fmt::Layer::new().with_writer(std::io::stderr))
         .with(
             fmt::Layer::new()
                 .compact()
@@ -665,8 +3493,18 @@ async fn main() -> Result<()> {
         );
     tracing::subscriber::set_global_default(subscriber)
         .context("Unable to set a global subscriber")?;
+    let _flush_dbs = key_value_cache::cache_service::FlushOpenDbsOnDrop;
+    shutdown::listen();
+    for (from, to) in migrated? {
+        tracing::info!("migrated [{from:?}] to [{to:?}]");
+    }
 
-    let Cli { command } = Cli::parse();
+    let Cli {
+        command,
+        api_key,
+        profile,
+        ..
+    } = cli;
     match command {
         Some(command) => match command {
             Commands::GenerateDefaultTlumokConfig => {
@@ -682,68 +3520,621 @@ async fn main() -> Result<()> {
                 )
                 .wrap_err("writing default config")?;
             }
-            Commands::Translate { file } => {
+            Commands::Translate {
+                file,
+                only,
+                range,
+                dry_run,
+            } => {
+                let selection = match (only.is_empty(), range) {
+                    (_, Some(range)) => SegmentSelection::Range(range),
+                    (false, None) => SegmentSelection::Keys(only),
+                    (true, None) => SegmentSelection::All,
+                };
+                let (config, translation_service) = match dry_run {
+                    true => (TlumokConfig::load_layered(api_key, profile)?, None),
+                    false => {
+                        let config = TlumokConfig::resolve(api_key, profile)?;
+                        // fail before anything gets translated rather than on the first segment
+                        TlumokConfig::checked_api_key(&config.deepl_api_key)?;
+                        let translation_service = TranslationService::new(&config);
+                        (config, Some(translation_service))
+                    }
+                };
+                batch::for_each_document(file, &config, |file| {
+                    translate_document(file, &selection, &config, translation_service.as_ref())
+                })
+                .await?;
+            }
+            Commands::InitializeTranslationWorkspace { file, force } => {
+                let config = TlumokConfig::load_layered(api_key, profile)?;
+                batch::for_each_document(file, &config, |file| {
+                    initialize_document(file, force, &config)
+                })
+                .await?;
+            }
+            Commands::ApplyTranslations {
+                file,
+                output,
+                extension,
+                compare,
+            } => {
+                if output.is_some() && file.len() > 1 {
+                    eyre::bail!("--output can only be used with a single document");
+                }
+                if let Some(extension) = extension.as_deref() {
+                    OutputTemplate::check_extension(extension).wrap_err("invalid --extension")?;
+                }
+                let config = TlumokConfig::load_layered(api_key, profile)?;
+                batch::for_each_document(file, &config, |file| {
+                    apply_document(file, output.clone(), extension.clone(), compare, &config)
+                })
+                .await?;
+            }
+            Commands::Lint { file, json } => {
+                let file = file.canonicalize()?;
+                let original_document = OriginalDocument::from_file(&file)
+                    .wrap_err_with(|| format!("opening original document {file:?}"))?;
+                let default_path =
+                    TranslationWorkspace::default_path_for_document(&original_document);
+                let translation_workspace = TranslationWorkspace::load(&default_path).await?;
+                let config = TlumokConfig::resolve(api_key, profile)?.for_document(&file)?;
+                let report =
+                    translation_workspace.lint(config.max_translation_length(
+                        translation_workspace.original_document.file_format,
+                    ));
+                match json {
+                    true => println!(
+                        "{}",
+                        serde_json::to_string_pretty(&report)
+                            .wrap_err("serializing lint report")?
+                    ),
+                    false => lint::print_report(&report),
+                }
+            }
+            Commands::Coverage { file, json } => {
+                let file = file.canonicalize()?;
+                let original_document = OriginalDocument::from_file(&file)
+                    .wrap_err_with(|| format!("opening original document {file:?}"))?;
+                let default_path =
+                    TranslationWorkspace::default_path_for_document(&original_document);
+                let translation_workspace = TranslationWorkspace::load(&default_path).await?;
+                let report = translation_workspace
+                    .coverage(translation_service::DictionaryService::default())
+                    .await?;
+                match json {
+                    true => println!(
+                        "{}",
+                        serde_json::to_string_pretty(&report)
+                            .wrap_err("serializing coverage report")?
+                    ),
+                    false => coverage::print_report(&report),
+                }
+            }
+            Commands::Inspect { file, key } => {
+                let file = file.canonicalize()?;
+                let original_document = OriginalDocument::from_file(&file)
+                    .wrap_err_with(|| format!("opening original document {file:?}"))?;
+                let default_path =
+                    TranslationWorkspace::default_path_for_document(&original_document);
+                let translation_workspace = TranslationWorkspace::load(&default_path).await?;
+                let config = TlumokConfig::resolve(api_key, profile)?.for_document(&file)?;
+                inspect::print_segment(
+                    &translation_workspace,
+                    translation_service::DictionaryService::new(&config),
+                    &key,
+                )
+                .await?;
+            }
+            Commands::Verify { file } => {
+                let file = file.canonicalize()?;
+                let original_document = OriginalDocument::from_file(&file)
+                    .wrap_err_with(|| format!("opening original document {file:?}"))?;
+                let default_path =
+                    TranslationWorkspace::default_path_for_document(&original_document);
+                let translation_workspace = TranslationWorkspace::load(&default_path).await?;
+                let stale = translation_workspace.stale_segments().await?;
+                if !stale.is_empty() {
+                    eyre::bail!(
+                        "{} segments no longer match [{file:?}]:\n{}",
+                        stale.len(),
+                        stale.join("\n")
+                    );
+                }
+                let source_edited = translation_workspace
+                    .segments
+                    .segments
+                    .values()
+                    .filter(|segment| segment.source_edited)
+                    .count();
+                println!(
+                    "all {} segments match [{file:?}]",
+                    translation_workspace.segments.segments.len() - source_edited
+                );
+                if source_edited > 0 {
+                    println!("{source_edited} segments with a hand edited source were not checked");
+                }
+            }
+            Commands::Reconcile { file } => {
                 let file = file.canonicalize()?;
-                Path::try_exists(&file).wrap_err("opening document for translation")?;
                 let original_document = OriginalDocument::from_file(&file)
                     .wrap_err_with(|| format!("opening original document {file:?}"))?;
                 let default_path =
                     TranslationWorkspace::default_path_for_document(&original_document);
-                default_path
-                    .try_exists()
-                    .wrap_err("translation workspace does not exist")?;
-                let TlumokConfig { deepl_api_key } = TlumokConfig::load_default()?;
-                let translation_service = TranslationService::new(deepl_api_key).await?;
-
                 let translation_workspace = TranslationWorkspace::load(&default_path).await?;
-                let translation_workspace = translation_workspace
-                    .translate(&translation_service)
+                let config = TlumokConfig::load_layered(api_key, profile)?;
+                let (translation_workspace, report) = translation_workspace
+                    .reconciled(
+                        config.max_segment_length,
+                        translation_service::DictionaryService::new(&config),
+                    )
                     .await?;
+                // reconciling can't be undone, so the previous workspace is kept next to it
+                let mut backup_path = default_path.clone().into_os_string();
+                backup_path.push(".bak");
+                let backup_path = PathBuf::from(backup_path);
+                tokio::fs::copy(&default_path, &backup_path)
+                    .await
+                    .wrap_err_with(|| {
+                        format!("backing up [{default_path:?}] to [{backup_path:?}]")
+                    })?;
                 translation_workspace.save(&default_path).await?;
+                println!("previous workspace kept at [{backup_path:?}]");
+                let reconcile::ReconcileReport {
+                    carried_over,
+                    from_dictionary,
+                    fuzzy,
+                    new,
+                    dropped,
+                } = report;
+                println!("carried over:    {carried_over}");
+                println!("from dictionary: {from_dictionary}");
+                println!("fuzzy (review):  {fuzzy}");
+                println!("new:             {new}");
+                if dropped > 0 {
+                    println!("{dropped} translated segments are no longer in the document");
+                }
+            }
+            Commands::HarvestMemory { file } => {
+                let file = file.canonicalize()?;
+                let original_document = OriginalDocument::from_file(&file)
+                    .wrap_err_with(|| format!("opening original document {file:?}"))?;
+                let default_path =
+                    TranslationWorkspace::default_path_for_document(&original_document);
+                let translation_workspace = TranslationWorkspace::load(&default_path).await?;
+                let harvested = translation_service::DictionaryService::default()
+                    .harvest_memory(&translation_workspace)
+                    .await?;
+                println!("harvested {harvested} confirmed translations");
             }
-            Commands::InitializeTranslationWorkspace { file } => {
+            Commands::Clean {
+                file,
+                outputs,
+                dictionary,
+                dry_run,
+                yes,
+            } => {
                 let file = file.canonicalize()?;
-                Path::try_exists(&file).wrap_err("opening document for translation")?;
                 let original_document = OriginalDocument::from_file(&file)
                     .wrap_err_with(|| format!("opening original document {file:?}"))?;
                 let default_path =
                     TranslationWorkspace::default_path_for_document(&original_document);
-                if default_path.exists() {
-                    tracing::error!("[{default_path:?}] already exists");
-                } else {
-                    let translation_workspace =
-                        TranslationWorkspace::for_document(original_document)
-                            .await
-                            .context("creating workspace for [{file:?}]")?;
-                    translation_workspace.save(&default_path).await?;
-                    tracing::info!("new workspace generated at [{default_path:?}]");
+                let translation_workspace = TranslationWorkspace::load(&default_path).await?;
+                let config = TlumokConfig::load_layered(api_key, profile)?;
+                let artifacts = translation_workspace.artifacts(
+                    &config.output_template(),
+                    clean::CleanOptions {
+                        outputs,
+                        project_dictionary: dictionary,
+                    },
+                )?;
+                for path in artifacts.iter() {
+                    println!("{}", path.display());
+                }
+                if artifacts.is_empty() {
+                    println!("nothing to remove");
+                } else if dry_run {
+                    println!("{} files would be removed", artifacts.len());
+                } else if yes || confirm_on_stdin(&format!("remove {} files?", artifacts.len()))? {
+                    clean::remove_artifacts(&artifacts)?;
+                    println!("removed {} files", artifacts.len());
                 }
             }
-            Commands::ApplyTranslations { file } => {
+            Commands::MergeDictionaries { from, into } => {
+                let from = from.canonicalize()?;
+                let into = into.canonicalize()?;
+                let original_document = OriginalDocument::from_file(&into)
+                    .wrap_err_with(|| format!("opening original document {into:?}"))?;
+                let default_path =
+                    TranslationWorkspace::default_path_for_document(&original_document);
+                let TlumokTranslationOptions {
+                    source_language,
+                    target_language,
+                    ..
+                } = TranslationWorkspace::load(&default_path)
+                    .await?
+                    .translation_options;
+                let added = translation_service::DictionaryService::default()
+                    .merge_dictionaries(from, into, (source_language, target_language))
+                    .await?;
+                println!("merged {added} translations");
+            }
+            Commands::CompactDictionaries { purge_older_than } => {
+                let purge_after = match purge_older_than {
+                    Some(days) => key_value_cache::cache_service::ExpiresAfter::After(
+                        std::time::Duration::from_secs(days * 24 * 60 * 60),
+                    ),
+                    None => key_value_cache::cache_service::ExpiresAfter::Never,
+                };
+                let results = translation_service::DictionaryService::default()
+                    .compact_dictionaries(purge_after)
+                    .await?;
+                let purged: usize = results.iter().map(|r| r.purged_entries).sum();
+                let before: u64 = results.iter().map(|r| r.size_before).sum();
+                let after: u64 = results.iter().map(|r| r.size_after).sum();
+                println!(
+                    "compacted {} dictionaries, purged {purged} entries, reclaimed {} bytes ({before} -> {after})",
+                    results.len(),
+                    before.saturating_sub(after),
+                );
+            }
+            Commands::ExportXliff { file, output } => {
+                let file = file.canonicalize()?;
+                let original_document = OriginalDocument::from_file(&file)
+                    .wrap_err_with(|| format!("opening original document {file:?}"))?;
+                let default_path =
+                    TranslationWorkspace::default_path_for_document(&original_document);
+                let translation_workspace = TranslationWorkspace::load(&default_path).await?;
+                let output = output.unwrap_or_else(|| {
+                    TranslationWorkspace::default_xliff_path_for_document(&original_document)
+                });
+                translation_workspace.export_xliff(&output).await?;
+                tracing::info!("exported xliff to [{output:?}]");
+            }
+            Commands::Serve { address } => {
+                let config = TlumokConfig::resolve(api_key, profile)?;
+                let translation_service = TranslationService::new(&config);
+                server::serve(address, translation_service, config).await?;
+            }
+            Commands::ImportXliff { file, xliff } => {
+                let file = file.canonicalize()?;
+                let original_document = OriginalDocument::from_file(&file)
+                    .wrap_err_with(|| format!("opening original document {file:?}"))?;
+                let default_path =
+                    TranslationWorkspace::default_path_for_document(&original_document);
+                let translation_workspace = TranslationWorkspace::load(&default_path).await?;
+                let (translation_workspace, summary) =
+                    translation_workspace.import_xliff(&xliff).await?;
+                translation_workspace.save(&default_path).await?;
+                let xliff::ImportSummary {
+                    updated,
+                    confirmed,
+                    skipped,
+                } = summary;
+                println!(
+                    "updated {updated} segments, confirmed {confirmed}, skipped {skipped} units"
+                );
+            }
+            Commands::Doctor => doctor::run(api_key, profile).await?,
+            Commands::ExportTmx {
+                file,
+                output,
+                since,
+            } => {
+                let file = file.canonicalize()?;
+                let original_document = OriginalDocument::from_file(&file)
+                    .wrap_err_with(|| format!("opening original document {file:?}"))?;
+                let default_path =
+                    TranslationWorkspace::default_path_for_document(&original_document);
+                let TlumokTranslationOptions {
+                    source_language,
+                    target_language,
+                    ..
+                } = TranslationWorkspace::load(&default_path)
+                    .await?
+                    .translation_options;
+                let output = output
+                    .unwrap_or_else(|| tmx::default_tmx_path_for_document(&original_document));
+                let units = tmx::export_tmx(
+                    translation_service::DictionaryService::default(),
+                    original_document.path,
+                    (source_language, target_language),
+                    since,
+                    &output,
+                )
+                .await?;
+                tracing::info!("exported {units} translation units to [{output:?}]");
+            }
+            Commands::ImportGlossary {
+                glossary,
+                file,
+                source_column,
+                target_column,
+                no_headers,
+                delimiter,
+            } => {
+                let config = TlumokConfig::resolve(api_key, profile)?;
+                let (original_document_path, translation_options) = match file {
+                    Some(file) => {
+                        let file = file.canonicalize()?;
+                        let original_document = OriginalDocument::from_file(&file)
+                            .wrap_err_with(|| format!("opening original document {file:?}"))?;
+                        let default_path =
+                            TranslationWorkspace::default_path_for_document(&original_document);
+                        let translation_workspace =
+                            TranslationWorkspace::load(&default_path).await?;
+                        (
+                            Some(original_document.path),
+                            translation_workspace.translation_options,
+                        )
+                    }
+                    None => (None, config.default_translation_options),
+                };
+                let TlumokTranslationOptions {
+                    source_language,
+                    target_language,
+                    ..
+                } = translation_options;
+                let glossary::ImportSummary {
+                    imported,
+                    duplicates,
+                    skipped,
+                } = glossary::import_glossary(
+                    translation_service::DictionaryService::new(&config),
+                    &glossary,
+                    glossary::GlossaryReadOptions {
+                        source_column,
+                        target_column,
+                        has_headers: !no_headers,
+                        delimiter,
+                    },
+                    original_document_path,
+                    (source_language, target_language),
+                )
+                .await?;
+                println!(
+                    "imported {imported} entries, {duplicates} duplicates and {skipped} incomplete rows were left out"
+                );
+            }
+            Commands::ExportReview {
+                file,
+                output,
+                format,
+            } => {
                 let file = file.canonicalize()?;
-
-                Path::try_exists(&file).wrap_err("opening document for translation")?;
-
                 let original_document = OriginalDocument::from_file(&file)
                     .wrap_err_with(|| format!("opening original document {file:?}"))?;
                 let default_path =
                     TranslationWorkspace::default_path_for_document(&original_document);
                 let translation_workspace = TranslationWorkspace::load(&default_path).await?;
-                let translation_workspace = translation_workspace.validated()?;
-                // let translated_document = translation_workspace.create_translated_document().await?;
-                translation_workspace.save_translated_document().await?;
+                let format = format.unwrap_or_else(|| match output.as_deref() {
+                    Some(output) => review::ReportFormat::for_path(output),
+                    None => review::ReportFormat::Html,
+                });
+                let output = output.unwrap_or_else(|| {
+                    TranslationWorkspace::default_review_path_for_document(
+                        &original_document,
+                        format,
+                    )
+                });
+                translation_workspace.export_review(format, &output).await?;
+                tracing::info!("exported review report to [{output:?}]");
             }
         },
         None => {
-            tracing::info!("getting deepl api key");
-            let TlumokConfig { deepl_api_key } = TlumokConfig::load_default()?;
-            tracing::info!("connecting to deepl api and setting up dictionary databases");
-            let translation_service = TranslationService::new(deepl_api_key).await?;
+            tracing::info!("loading config");
+            let config = TlumokConfig::resolve(api_key, profile)?;
+            tracing::info!("setting up dictionary databases");
+            let translation_service = TranslationService::new(&config);
             tracing::info!("starting graphical interface");
-            <ui::TlumokState as iced::pure::Application>::run(iced::Settings::with_flags((
-                translation_service,
-            )))?;
+            // ctrl+c in the terminal closes the window like its close button does
+            let _handled = shutdown::handled_here();
+            <ui::TlumokState as iced::pure::Application>::run(iced::Settings {
+                // closing with unsaved changes asks first, see [ui::Message::CloseRequested]
+                exit_on_close_request: false,
+                ..iced::Settings::with_flags((translation_service, config))
+            })?;
+            tracing::info!("shutting down");
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use translation_service::MachineTranslation;
+
+    fn machine_translation(text: &str) -> Option<MachineTranslation> {
+        Some(MachineTranslation {
+            text: text.to_owned(),
+            detected_source_language: "EN".to_owned(),
+        })
+    }
+
+    #[tokio::test]
+    async fn segments_stay_in_order_when_batches_finish_out_of_order() -> Result<()> {
+        let mut segments =
+            TranslationSegments::generate_from("One. Two. Three. Four.", 5000).await?;
+        let keys: Vec<_> = segments.segments.keys().cloned().collect();
+        for batch in keys.chunks(2).rev() {
+            let results = batch
+                .iter()
+                .map(|key| machine_translation(&format!("translated {key}")))
+                .collect();
+            segments.set_machine_translations(batch, results, Default::default(), 0);
+        }
+        assert_eq!(segments.segments.keys().cloned().collect::<Vec<_>>(), keys);
+        for (key, segment) in segments.segments.iter() {
+            assert_eq!(segment.translated_text, Some(format!("translated {key}")));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn missing_results_are_flagged_and_left_untranslated() -> Result<()> {
+        let mut segments = TranslationSegments::generate_from("One. Two.", 5000).await?;
+        let keys: Vec<_> = segments.segments.keys().cloned().collect();
+        segments.set_machine_translations(
+            &keys,
+            vec![machine_translation("Jeden. "), None],
+            Default::default(),
+            0,
+        );
+        let missing = &segments.segments[&keys[1]];
+        assert_eq!(missing.translated_text, None);
+        assert_eq!(
+            missing.needs_attention,
+            Some(AttentionReason::MissingTranslation)
+        );
+        assert_eq!(
+            segments.segments[&keys[0]].translated_text.as_deref(),
+            Some("Jeden. ")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unlocking_brings_back_the_previous_translation() -> Result<()> {
+        let mut segments = TranslationSegments::generate_from("Tlumok.", 5000).await?;
+        let segment = segments.segments.values_mut().next().expect("one segment");
+        segment.translated_text = Some("Tłumok.".into());
+        segment.set_locked(true);
+        segment.set_locked(true);
+        assert_eq!(segment.translated_text.as_deref(), Some("Tlumok."));
+        segment.set_locked(false);
+        assert_eq!(segment.translated_text.as_deref(), Some("Tłumok."));
+        assert_eq!(segment.translation_before_lock, None);
+        Ok(())
+    }
+
+    #[test]
+    fn optional_config_keys_are_known() {
+        let content = "output_directory = \"translated\"\nmax_segment_lenght = 100\n";
+        assert_eq!(
+            TlumokConfig::unknown_keys(content),
+            vec!["max_segment_lenght"]
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_documents_get_no_workspace() -> Result<()> {
+        let directory = filesystem::test_directory("empty-documents");
+        for (name, content) in [("empty.txt", ""), ("blank.txt", " \n\n\t\n")] {
+            let path = directory.join(name);
+            std::fs::write(&path, content)?;
+            let error = TranslationSegments::for_document(
+                &OriginalDocument {
+                    path,
+                    file_format: FileFormat::Txt,
+                },
+                5000,
+            )
+            .await
+            .unwrap_err();
+            assert!(format!("{error:?}").contains("has no text to translate"));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn a_single_huge_line_is_split_under_the_limit() -> Result<()> {
+        const MAX_SEGMENT_LENGTH: usize = 5000;
+        let text = "zażółć gęślą jaźń ".repeat(100_000 / 18);
+        let text = text.trim_end();
+        assert!(text.chars().count() > 99_000);
+        let segments = TranslationSegments::generate_from(text, MAX_SEGMENT_LENGTH).await?;
+        assert!(segments.segments.len() > 1);
+        let mut next_start = 0;
+        for segment in segments.segments.values() {
+            let OriginalDocumentSlice { start, len, .. } = segment.original_document_slice;
+            assert!(segment.original_text.chars().count() <= MAX_SEGMENT_LENGTH);
+            assert_eq!(start, next_start);
+            assert_eq!(&text[start..start + len], segment.original_text);
+            next_start = start + len;
+        }
+        assert_eq!(next_start, text.len());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn overlapping_slices_are_reported() -> Result<()> {
+        let mut segments = TranslationSegments::generate_from("One. Two. Three.", 5000).await?;
+        segments.check_slices()?;
+        let slice = &mut segments.segments["segment_1"].original_document_slice;
+        slice.start -= 2;
+        slice.len += 2;
+        let error = format!("{:?}", segments.check_slices().unwrap_err());
+        assert!(error.contains("[segment_1] (3..10) overlaps [segment_0] (ending at 5)"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn slices_outside_of_the_document_are_reported() -> Result<()> {
+        let document = "One. Two. Three.";
+        let mut segments = TranslationSegments::generate_from(document, 5000).await?;
+        segments.segments["segment_2"].original_document_slice.len += 10;
+        let error = TranslationWorkspace::apply_translations(
+            FileFormat::Txt,
+            document.to_owned(),
+            segments.segments.into_iter(),
+        )
+        .unwrap_err();
+        assert!(format!("{error:?}").contains("segment [segment_2] points outside of the document"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn keys_are_stable_across_regeneration() -> Result<()> {
+        let text = "One. Two. Three.";
+        let first = TranslationSegments::generate_from(text, 5000).await?;
+        let second = TranslationSegments::generate_from(text, 5000).await?;
+        let keys = |segments: &TranslationSegments| {
+            segments
+                .segments
+                .iter()
+                .map(|(key, segment)| (key.clone(), segment.original_text.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&first), keys(&second));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn normalizing_keys_keeps_translations() -> Result<()> {
+        let segments = TranslationSegments::generate_from("One. Two.", 5000).await?;
+        // keys of some older format
+        let segments = TranslationSegments {
+            segments: segments
+                .segments
+                .into_iter()
+                .enumerate()
+                .map(|(index, (_, mut segment))| {
+                    segment.translated_text = Some(format!("{index}"));
+                    segment.confirmed = segment.translated_text.clone();
+                    (format!("old-{index}"), segment)
+                })
+                .collect(),
+        };
+        let normalized = segments.with_normalized_keys();
+        let normalized: Vec<_> = normalized
+            .segments
+            .iter()
+            .map(|(key, segment)| {
+                (
+                    key.as_str(),
+                    segment.original_text.as_str(),
+                    segment.confirmed.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            normalized,
+            vec![
+                ("segment_0", "One. ", Some("0")),
+                ("segment_1", "Two.", Some("1"))
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+//! partial matches of dictionary entries, for ui strings that get reused in pieces - a stored
+//! "Save the file" is worth showing when translating "Save the file now"
+use unicode_segmentation::UnicodeSegmentation;
+
+/// how much of the longer text (in characters) the shorter one covers, [None] unless one
+/// contains the other
+fn substring_percent(text: &str, candidate: &str) -> Option<u32> {
+    let (text, candidate) = (text.trim().to_lowercase(), candidate.trim().to_lowercase());
+    let (shorter, longer) = match text.chars().count() <= candidate.chars().count() {
+        true => (&text, &candidate),
+        false => (&candidate, &text),
+    };
+    if shorter.is_empty() || !longer.contains(shorter.as_str()) {
+        return None;
+    }
+    Some((shorter.chars().count() * 100 / longer.chars().count()) as u32)
+}
+
+/// words the texts have in common, relative to the words of both of them
+fn word_overlap_percent(text: &str, candidate: &str) -> u32 {
+    let words = |text: &str| {
+        text.unicode_words()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+    };
+    let (words, mut candidate_words) = (words(text), words(candidate));
+    let total = words.len() + candidate_words.len();
+    if words.is_empty() || candidate_words.is_empty() {
+        return 0;
+    }
+    // a repeated word only counts as many times as the other text has it
+    let common = words
+        .iter()
+        .filter(|word| {
+            candidate_words
+                .iter()
+                .position(|candidate_word| candidate_word == *word)
+                .map(|position| candidate_words.swap_remove(position))
+                .is_some()
+        })
+        .count();
+    (common * 200 / total) as u32
+}
+
+/// similarity of a stored text to the searched one, [None] when it's below `min_percent`,
+/// identical texts are exact matches, so this never goes above 99
+pub fn partial_match_percent(text: &str, candidate: &str, min_percent: u32) -> Option<u32> {
+    if text == candidate {
+        return None;
+    }
+    let percent = substring_percent(text, candidate)
+        .unwrap_or_default()
+        .max(word_overlap_percent(text, candidate))
+        .min(99);
+    (percent >= min_percent).then_some(percent)
+}
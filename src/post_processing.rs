@@ -0,0 +1,130 @@
+//! project specific fixups of machine translations (quotes, spacing, number formatting)
+use super::*;
+use translation_service::Language;
+
+/// regex find and replace, `replace` can refer to groups of `find` (`$1`, `${name}`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostProcessingRule {
+    pub find: String,
+    pub replace: String,
+}
+
+/// built-in typographic normalization of the target language, each rule can be turned off on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Typography {
+    /// none of the rules below run unless this is set
+    pub enabled: bool,
+    /// `"cytat"` -> `„cytat”` in polish, `"quote"` -> `“quote”` in english
+    pub quotes: bool,
+    /// `...` -> `…`
+    pub ellipsis: bool,
+    /// hyphens between spaces -> `–`
+    pub dashes: bool,
+    /// polish only, non-breaking space after single letter words (`w domu`), so they don't end a line
+    pub single_letter_words: bool,
+}
+
+impl Default for Typography {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            quotes: true,
+            ellipsis: true,
+            dashes: true,
+            single_letter_words: true,
+        }
+    }
+}
+
+impl Typography {
+    /// find and replace pairs of the enabled rules for the target language
+    fn rules(self, language: Language) -> Vec<(&'static str, &'static str)> {
+        let Self {
+            enabled,
+            quotes,
+            ellipsis,
+            dashes,
+            single_letter_words,
+        } = self;
+        if !enabled {
+            return vec![];
+        }
+        let mut rules = vec![];
+        if quotes {
+            rules.push((
+                r#"["“„]([^"“”„\n]*)["”]"#,
+                match language {
+                    Language::Polish => "„$1”",
+                    Language::English => "“$1”",
+                },
+            ));
+        }
+        if ellipsis {
+            rules.push((r"\.\.\.", "…"));
+        }
+        if dashes {
+            rules.push((r"(\s)--?(\s)", "$1–$2"));
+        }
+        if single_letter_words && language == Language::Polish {
+            rules.push((r"\b([aiouwzAIOUWZ]) +", "$1\u{a0}"));
+        }
+        rules
+    }
+}
+
+/// compiled [TlumokConfig::typography] and [TlumokConfig::post_processing], by target language
+#[derive(Debug, Clone, Default)]
+pub struct PostProcessing(Vec<(Language, Vec<(regex::Regex, String)>)>);
+
+impl PostProcessing {
+    pub fn new(
+        rules: &IndexMap<String, Vec<PostProcessingRule>>,
+        typography: Typography,
+    ) -> Result<Self> {
+        if let Some(language) = rules.keys().find(|language| {
+            !Language::ALL
+                .iter()
+                .any(|known| &format!("{known:?}") == *language)
+        }) {
+            eyre::bail!(
+                "`post_processing` has an unknown language [{language}], known languages: {}",
+                Language::ALL.map(|known| format!("{known:?}")).join(", ")
+            );
+        }
+        let mut compiled = vec![];
+        for language in Language::ALL {
+            let built_in = typography
+                .rules(language)
+                .into_iter()
+                .map(|(find, replace)| {
+                    regex::Regex::new(find)
+                        .map(|find| (find, replace.to_string()))
+                        .wrap_err_with(|| format!("invalid typography pattern [{find}]"))
+                });
+            let configured = rules
+                .get(&format!("{language:?}"))
+                .into_iter()
+                .flatten()
+                .map(|PostProcessingRule { find, replace }| {
+                    regex::Regex::new(find)
+                        .map(|find| (find, replace.clone()))
+                        .wrap_err_with(|| format!("invalid post processing pattern [{find}]"))
+                });
+            compiled.push((language, built_in.chain(configured).collect::<Result<_>>()?));
+        }
+        Ok(Self(compiled))
+    }
+
+    /// runs the [Typography] rules of the target language first and then the configured ones,
+    /// in the order they are listed in, each one on the result of the previous one
+    pub fn apply(&self, target_language: Language, text: String) -> String {
+        self.0
+            .iter()
+            .filter(|(language, _)| *language == target_language)
+            .flat_map(|(_, rules)| rules.iter())
+            .fold(text, |text, (find, replace)| {
+                find.replace_all(&text, replace.as_str()).into_owned()
+            })
+    }
+}
@@ -0,0 +1,95 @@
+//! per project overrides of [TlumokConfig], read from a [ProjectOptions::FILENAME] next to
+//! the document (or in the closest directory above it), so documents of one client can be
+//! translated differently than the rest
+use super::*;
+
+/// every setting is optional, the ones left out come from the global config,
+/// tables (`max_translation_lengths`, `output_extensions`, `post_processing`) are merged by key
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectOptions {
+    pub ignore_patterns: Option<Vec<String>>,
+    /// languages and sentence splitting of workspaces created in the project,
+    /// existing workspaces keep the options they were created with
+    pub default_translation_options: Option<translation_service::TlumokTranslationOptions>,
+    pub typography: Option<post_processing::Typography>,
+    /// legal texts want closer matches than marketing copy does
+    pub min_suggestion_percent: Option<u32>,
+    pub max_translation_lengths: IndexMap<String, usize>,
+    pub output_extensions: IndexMap<String, String>,
+    pub post_processing: IndexMap<String, Vec<post_processing::PostProcessingRule>>,
+}
+
+impl ProjectOptions {
+    pub const FILENAME: &'static str = "tlumok-project.toml";
+
+    /// closest project options file of a document, [None] if it's not a part of any project
+    pub fn find(document: &Path) -> Option<PathBuf> {
+        document
+            .parent()?
+            .ancestors()
+            .map(|directory| directory.join(Self::FILENAME))
+            .find(|path| path.is_file())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            std::fs::read_to_string(path).wrap_err_with(|| format!("reading [{path:?}]"))?;
+        toml::from_str(&content).wrap_err_with(|| format!("parsing [{path:?}]"))
+    }
+}
+
+impl TlumokConfig {
+    /// the config with the project options of the document applied on top of it,
+    /// see [ProjectOptions]
+    pub fn for_document(self, document: &Path) -> Result<Self> {
+        let path = match ProjectOptions::find(document) {
+            Some(path) => path,
+            None => return Ok(self),
+        };
+        let ProjectOptions {
+            ignore_patterns,
+            default_translation_options,
+            typography,
+            min_suggestion_percent,
+            max_translation_lengths,
+            output_extensions,
+            post_processing,
+        } = ProjectOptions::load(&path)?;
+        tracing::info!("using project options from [{path:?}]");
+        let mut config = Self {
+            ignore_patterns: ignore_patterns.unwrap_or(self.ignore_patterns),
+            default_translation_options: default_translation_options
+                .unwrap_or(self.default_translation_options),
+            typography: typography.unwrap_or(self.typography),
+            min_suggestion_percent: min_suggestion_percent.unwrap_or(self.min_suggestion_percent),
+            ..self
+        };
+        config
+            .max_translation_lengths
+            .extend(max_translation_lengths);
+        config.output_extensions.extend(output_extensions);
+        config.post_processing.extend(post_processing);
+        config
+            .validated()
+            .wrap_err_with(|| format!("applying project options from [{path:?}]"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_suggestion_threshold_is_validated() -> Result<()> {
+        let directory = filesystem::test_directory("project-threshold");
+        let document = directory.join("contract.txt");
+        let project = directory.join(ProjectOptions::FILENAME);
+        std::fs::write(&project, "min_suggestion_percent = 90\n")?;
+        let config = TlumokConfig::default().for_document(&document)?;
+        assert_eq!(config.min_suggestion_percent, 90);
+        std::fs::write(&project, "min_suggestion_percent = 101\n")?;
+        assert!(TlumokConfig::default().for_document(&document).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+//! carrying the work done in a workspace over to a new version of its original document
+use super::*;
+use std::collections::HashMap;
+
+/// old segments at least this similar to a new one lend it their translation (to be reviewed)
+pub const FUZZY_MATCH_PERCENT: u32 = 85;
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ReconcileReport {
+    /// same text as an old segment, taken over as it was
+    pub carried_over: usize,
+    /// exact match in the project dictionary, confirmed
+    pub from_dictionary: usize,
+    /// similar to an old segment, translated but flagged for review
+    pub fuzzy: usize,
+    /// nothing to reuse
+    pub new: usize,
+    /// old segments with a translation that didn't make it into the new version
+    pub dropped: usize,
+}
+
+impl TranslationWorkspace {
+    /// segments the current version of the original document again,
+    /// new segments are filled from the matching old ones or the project dictionary
+    pub async fn reconciled(
+        self,
+        max_segment_length: usize,
+        dictionary_service: translation_service::DictionaryService,
+    ) -> Result<(Self, ReconcileReport)> {
+        let TlumokTranslationOptions {
+            source_language,
+            target_language,
+            ..
+        } = self.translation_options;
+        let mut reconciled = Self::for_document(
+            self.original_document.clone(),
+            self.translation_options,
+            max_segment_length,
+        )
+        .await
+        .wrap_err("segmenting the new version of the document")?;
+        // settings of the document that don't depend on its content
+        reconciled.translated_stem = self.translated_stem.clone();
+        reconciled.min_suggestion_percent = self.min_suggestion_percent;
+        // confirmed segments win over drafts with the same text
+        let mut old = HashMap::<&str, &TranslationSegment>::new();
+        for segment in self
+            .segments
+            .segments
+            .values()
+            .filter(|segment| segment.translated_text.is_some())
+        {
+            let known = old.entry(&segment.original_text).or_insert(segment);
+            if known.confirmed.is_none() && segment.confirmed.is_some() {
+                *known = segment;
+            }
+        }
+        let mut report = ReconcileReport::default();
+        let mut used = std::collections::HashSet::new();
+        for segment in reconciled.segments.segments.values_mut() {
+            if let Some(previous) = old.get(segment.original_text.as_str()) {
+                used.insert(segment.original_text.clone());
+                *segment = TranslationSegment {
+                    original_document_slice: segment.original_document_slice.clone(),
+                    // the text was just extracted from the document, so it matches it again
+                    source_edited: false,
+                    ..(*previous).clone()
+                };
+                report.carried_over += 1;
+            }
+        }
+        let unmatched = reconciled
+            .segments
+            .segments
+            .values()
+            .filter(|segment| segment.translated_text.is_none())
+            .map(|segment| segment.original_text.clone())
+            .collect::<Vec<_>>();
+        let dictionary = dictionary_service
+            .get_project_suggestions_batch(
+                self.original_document.path.clone(),
+                (source_language, target_language),
+                unmatched,
+            )
+            .await
+            .wrap_err("reading the project dictionary")?;
+        tokio::task::block_in_place(|| {
+            for segment in reconciled
+                .segments
+                .segments
+                .values_mut()
+                .filter(|segment| segment.translated_text.is_none())
+            {
+                if let Some(suggestion) = dictionary
+                    .get(&segment.original_text)
+                    .and_then(|suggestions| suggestions.last())
+                {
+                    segment.translated_text = Some(suggestion.translated_text.clone());
+                    segment.confirm();
+                    report.from_dictionary += 1;
+                    continue;
+                }
+                let closest =
+                    crate::coverage::best_match(&segment.original_text, old.keys().copied())
+                        .filter(|(_, percent)| *percent >= FUZZY_MATCH_PERCENT);
+                match closest {
+                    Some((text, percent)) => {
+                        used.insert(text.to_owned());
+                        let previous = old[text];
+                        segment.translated_text = previous
+                            .confirmed
+                            .clone()
+                            .or_else(|| previous.translated_text.clone());
+                        segment.needs_attention = Some(AttentionReason::FuzzyMatch { percent });
+                        report.fuzzy += 1;
+                    }
+                    None => report.new += 1,
+                }
+            }
+        });
+        report.dropped = old.keys().filter(|text| !used.contains(**text)).count();
+        Ok((reconciled, report))
+    }
+}
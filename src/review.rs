@@ -0,0 +1,174 @@
+//! review report of a workspace, a table of every segment (source, translation, status, note)
+//! that can be sent to a client for sign-off, no tlumok needed to read it
+use super::*;
+use itertools::Itertools;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    /// a single self-contained page, styles included
+    Html,
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> std::result::Result<Self, Self::Err> {
+        match format.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "html" | "htm" => Ok(Self::Html),
+            other => Err(format!("expected csv or html, got [{other}]")),
+        }
+    }
+}
+
+impl ReportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Html => "html",
+        }
+    }
+
+    /// picked by the extension of the output path, html when it's neither
+    pub fn for_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|extension| extension.to_string_lossy().parse().ok())
+            .unwrap_or(Self::Html)
+    }
+}
+
+const COLUMNS: [&str; 5] = ["segment", "source", "translation", "status", "note"];
+
+fn escaped(text: &str) -> String {
+    String::from_utf8_lossy(&quick_xml::escape::escape(text.as_bytes())).into_owned()
+}
+
+fn status_class(status: SegmentStatus) -> &'static str {
+    match status {
+        SegmentStatus::Untranslated => "untranslated",
+        SegmentStatus::MachineTranslated => "translated",
+        SegmentStatus::Confirmed => "confirmed",
+        SegmentStatus::EditedAfterConfirm => "edited",
+        SegmentStatus::Locked => "locked",
+    }
+}
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border: 1px solid #ccc; padding: 0.4em 0.6em; text-align: left; vertical-align: top; white-space: pre-wrap; }
+th { background: #eee; position: sticky; top: 0; }
+td.status { white-space: nowrap; }
+tr.untranslated td.status { color: #b00; }
+tr.translated td.status, tr.edited td.status { color: #c60; }
+tr.confirmed td.status { color: #070; }
+tr.locked td { color: #777; }
+td.note { color: #729; }";
+
+impl TranslationWorkspace {
+    pub fn default_review_path_for_document(
+        OriginalDocument { path, .. }: &OriginalDocument,
+        format: ReportFormat,
+    ) -> PathBuf {
+        path.with_extension(format!("tlumok-review.{}", format.extension()))
+    }
+
+    /// (key, source, translation, status, note) of every segment, in document order
+    fn review_rows(&self) -> impl Iterator<Item = (&str, &str, &str, SegmentStatus, &str)> {
+        self.segments.segments.iter().map(|(key, segment)| {
+            (
+                key.as_str(),
+                segment.original_text.as_str(),
+                segment.translated_text.as_deref().unwrap_or_default(),
+                segment.status(),
+                segment.note.as_deref().unwrap_or_default(),
+            )
+        })
+    }
+
+    pub fn to_review_csv(&self) -> Result<String> {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer
+            .write_record(COLUMNS)
+            .wrap_err("writing review headers")?;
+        for (key, source, translation, status, note) in self.review_rows() {
+            writer
+                .write_record([key, source, translation, &status.to_string(), note])
+                .wrap_err_with(|| format!("writing review row of segment [{key}]"))?;
+        }
+        let content = writer.into_inner().wrap_err("finishing review csv")?;
+        String::from_utf8(content).wrap_err("review csv is not valid utf-8")
+    }
+
+    pub fn to_review_html(&self) -> String {
+        let Self {
+            original_document: OriginalDocument { path, .. },
+            translation_options:
+                TlumokTranslationOptions {
+                    source_language,
+                    target_language,
+                    ..
+                },
+            ..
+        } = self;
+        let title = escaped(&format!(
+            "{} ({source_language} -> {target_language})",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        ));
+        let counts = self
+            .segments
+            .segments
+            .values()
+            .map(|segment| segment.status())
+            .counts();
+        let summary = [
+            SegmentStatus::Confirmed,
+            SegmentStatus::EditedAfterConfirm,
+            SegmentStatus::MachineTranslated,
+            SegmentStatus::Untranslated,
+            SegmentStatus::Locked,
+        ]
+        .into_iter()
+        .filter_map(|status| counts.get(&status).map(|count| format!("{count} {status}")))
+        .join(", ");
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!(
+            "<title>{title}</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n"
+        ));
+        html.push_str(&format!(
+            "<h1>{title}</h1>\n<p>{} segments: {summary}, generated {}</p>\n",
+            self.segments.segments.len(),
+            now().format("%Y-%m-%d %H:%M"),
+        ));
+        html.push_str(&format!(
+            "<table>\n<thead><tr>{}</tr></thead>\n<tbody>\n",
+            COLUMNS
+                .iter()
+                .map(|column| format!("<th>{column}</th>"))
+                .join("")
+        ));
+        for (key, source, translation, status, note) in self.review_rows() {
+            html.push_str(&format!(
+                "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td class=\"status\">{status}</td><td class=\"note\">{}</td></tr>\n",
+                status_class(status),
+                escaped(key),
+                escaped(source),
+                escaped(translation),
+                escaped(note),
+            ));
+        }
+        html.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+        html
+    }
+
+    pub async fn export_review(&self, format: ReportFormat, output_path: &Path) -> Result<()> {
+        let content = match format {
+            ReportFormat::Csv => self.to_review_csv()?,
+            ReportFormat::Html => self.to_review_html(),
+        };
+        filesystem::write_atomically(output_path, content.as_bytes())
+            .await
+            .wrap_err_with(|| format!("writing review report to [{output_path:?}]"))
+    }
+}
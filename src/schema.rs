@@ -0,0 +1,210 @@
+//! versioned format of workspace files, [TranslationWorkspace] is always the latest version
+//! and older ones are converted to it when they're read
+use super::*;
+
+/// schema written by this build, bump it (and add a conversion from the previous version)
+/// whenever older builds wouldn't read the files correctly anymore - which is every time a field
+/// is added to [TranslationWorkspace] or [TranslationSegment], they reject unknown fields
+///
+/// - 1: the first versioned schema
+/// - 2: [TranslationWorkspace::translated_stem]
+/// - 3: [TranslationSegment::keep_out_of_memory]
+/// - 4: [TranslationSegment::note]
+/// - 5: [TranslationSegment::machine_translation_history]
+/// - 6: [TranslationWorkspace::min_suggestion_percent]
+/// - 7: [TranslationSegment::translation_before_lock]
+pub const WORKSPACE_SCHEMA_VERSION: u32 = 7;
+
+/// read before the rest of the file to know which version to read it as
+#[derive(Deserialize)]
+struct SchemaVersion {
+    /// files written before the schema was versioned don't have it
+    #[serde(default)]
+    schema_version: u32,
+}
+
+/// workspaces written before the schema was versioned,
+/// segments nobody translated yet hold [NOT_TRANSLATED_MARKER] instead of a missing translation
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkspaceV0 {
+    pub tlumok_version: String,
+    #[serde(default)]
+    pub source_checksum: Option<u32>,
+    pub original_document: OriginalDocument,
+    pub translation_options: TlumokTranslationOptions,
+    pub segments: TranslationSegments,
+}
+
+impl From<WorkspaceV0> for TranslationWorkspace {
+    fn from(
+        WorkspaceV0 {
+            tlumok_version,
+            source_checksum,
+            original_document,
+            translation_options,
+            mut segments,
+        }: WorkspaceV0,
+    ) -> Self {
+        for (key, segment) in segments.segments.iter_mut() {
+            if segment.translated_text.as_deref() == Some(NOT_TRANSLATED_MARKER) {
+                tracing::debug!("[{key}] replacing legacy not-translated marker");
+                segment.translated_text = None;
+            }
+        }
+        Self {
+            schema_version: WORKSPACE_SCHEMA_VERSION,
+            tlumok_version,
+            source_checksum,
+            translated_stem: None,
+            min_suggestion_percent: None,
+            original_document,
+            translation_options,
+            segments,
+        }
+    }
+}
+
+/// workspaces written before [TranslationWorkspace::translated_stem]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceV1 {
+    pub schema_version: u32,
+    pub tlumok_version: String,
+    #[serde(default)]
+    pub source_checksum: Option<u32>,
+    pub original_document: OriginalDocument,
+    pub translation_options: TlumokTranslationOptions,
+    pub segments: TranslationSegments,
+}
+
+impl From<WorkspaceV1> for TranslationWorkspace {
+    fn from(
+        WorkspaceV1 {
+            schema_version: _,
+            tlumok_version,
+            source_checksum,
+            original_document,
+            translation_options,
+            segments,
+        }: WorkspaceV1,
+    ) -> Self {
+        Self {
+            schema_version: WORKSPACE_SCHEMA_VERSION,
+            tlumok_version,
+            source_checksum,
+            translated_stem: None,
+            min_suggestion_percent: None,
+            original_document,
+            translation_options,
+            segments,
+        }
+    }
+}
+
+impl WorkspaceFormat {
+    fn parse<T: serde::de::DeserializeOwned>(self, content: &str) -> Result<T> {
+        match self {
+            Self::Toml => toml::from_str(content).map_err(eyre::Report::from),
+            Self::Json => serde_json::from_str(content).map_err(eyre::Report::from),
+            Self::Ron => ron::from_str(content).map_err(eyre::Report::from),
+        }
+    }
+    pub fn deserialize(self, content: &str) -> Result<TranslationWorkspace> {
+        let SchemaVersion { schema_version } = self
+            .parse(content)
+            .wrap_err_with(|| format!("reading schema version of a {self:?} workspace"))?;
+        match schema_version {
+            0 => self.parse::<WorkspaceV0>(content).map(TranslationWorkspace::from),
+            1 => self.parse::<WorkspaceV1>(content).map(TranslationWorkspace::from),
+            // versions since then only added fields that are left out when they're empty
+            2..=WORKSPACE_SCHEMA_VERSION => {
+                self.parse(content).map(|workspace| TranslationWorkspace {
+                    schema_version: WORKSPACE_SCHEMA_VERSION,
+                    ..workspace
+                })
+            }
+            newer => eyre::bail!(
+                "workspace schema version {newer} is newer than the supported one ({WORKSPACE_SCHEMA_VERSION}), update tlumok to open it"
+            ),
+        }
+        .wrap_err_with(|| {
+            format!("deserializing workspace as {self:?} (schema version {schema_version})")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a workspace of the given version, `extra` goes in before its tables
+    fn workspace(schema_version: Option<u32>, extra: &str) -> String {
+        let schema_version = schema_version
+            .map(|version| format!("schema_version = {version}\n"))
+            .unwrap_or_default();
+        format!(
+            r#"{schema_version}tlumok_version = '0.1.0'
+{extra}
+[original_document]
+path = 'example.txt'
+file_format = 'Txt'
+
+[translation_options]
+source_language = 'English'
+target_language = 'Polish'
+
+[segments.segments.segment_0]
+original_text = 'Hello.'
+translated_text = '{NOT_TRANSLATED_MARKER}'
+
+[segments.segments.segment_0.original_document_slice]
+start = 0
+len = 6
+"#
+        )
+    }
+
+    #[test]
+    fn unversioned_workspaces_drop_the_legacy_marker() -> Result<()> {
+        let workspace = WorkspaceFormat::Toml.deserialize(&workspace(None, ""))?;
+        assert_eq!(workspace.schema_version, WORKSPACE_SCHEMA_VERSION);
+        assert_eq!(
+            workspace.segments.segments["segment_0"].translated_text,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn version_1_is_converted() -> Result<()> {
+        let workspace = WorkspaceFormat::Toml.deserialize(&workspace(Some(1), ""))?;
+        assert_eq!(workspace.schema_version, WORKSPACE_SCHEMA_VERSION);
+        assert_eq!(workspace.translated_stem, None);
+        Ok(())
+    }
+
+    #[test]
+    fn version_1_rejects_fields_added_later() {
+        let error = WorkspaceFormat::Toml
+            .deserialize(&workspace(Some(1), "translated_stem = 'przyklad'"))
+            .unwrap_err();
+        assert!(format!("{error:?}").contains("unknown field"));
+    }
+
+    #[test]
+    fn older_versions_are_read_as_the_latest() -> Result<()> {
+        let workspace = WorkspaceFormat::Toml
+            .deserialize(&workspace(Some(2), "translated_stem = 'przyklad'"))?;
+        assert_eq!(workspace.schema_version, WORKSPACE_SCHEMA_VERSION);
+        assert_eq!(workspace.translated_stem.as_deref(), Some("przyklad"));
+        Ok(())
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let error = WorkspaceFormat::Toml
+            .deserialize(&workspace(Some(WORKSPACE_SCHEMA_VERSION + 1), ""))
+            .unwrap_err();
+        assert!(format!("{error:?}").contains("update tlumok to open it"));
+    }
+}
@@ -0,0 +1,429 @@
+//! `tlumok serve` - JSON over HTTP api for editor integrations and other front-ends
+//!
+//! workspaces are addressed by the path of their original document and live on disk
+//! (exactly like with the cli and the gui), every change is saved right away
+//!
+//! - `POST /workspace` `{path}` - loads the workspace of a document, creating it if needed
+//! - `GET /workspace/segments?path=` - all segments of the workspace, in document order
+//! - `POST /workspace/translate` `{path, key, overwrite?}` - machine translates a single segment,
+//!   a confirmed one only with `overwrite`
+//! - `POST /workspace/confirm` `{path, key, translated_text?, keep_out_of_memory?}` - (edits and) confirms a segment
+//! - `POST /workspace/apply` `{path, output?}` - writes the translated document
+//! - `GET /workspace/suggestions?path=&key=` - dictionary suggestions for a segment
+//!
+//! errors are `{error}` objects, with 400 for documents tlumok can't read, 404 for missing
+//! documents, workspaces and segments, 409 for segments that can't be changed and 500 for the rest
+use super::*;
+use axum::{
+    extract::{
+        Extension,
+        Query,
+    },
+    http::StatusCode,
+    response::{
+        IntoResponse,
+        Response,
+    },
+    routing::{
+        get,
+        post,
+    },
+    Json,
+    Router,
+};
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+};
+use translation_service::DictionarySuggestion;
+
+struct ServerState {
+    translation_service: TranslationService,
+    config: TlumokConfig,
+    /// workspaces are read (and modified and written back) by one request at a time,
+    /// so nobody reads a workspace another request is saving
+    lock: tokio::sync::Mutex<()>,
+}
+
+type State = Extension<Arc<ServerState>>;
+
+/// anything that isn't one of the client's mistakes is an internal error, the whole report only
+/// ends up in the log, the client gets its outermost message
+pub struct ApiError {
+    status: StatusCode,
+    report: eyre::Report,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: String) -> Self {
+        Self {
+            status,
+            report: eyre::eyre!(message),
+        }
+    }
+    fn bad_request(message: String) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+    fn not_found(message: String) -> Self {
+        Self::new(StatusCode::NOT_FOUND, message)
+    }
+    fn conflict(message: String) -> Self {
+        Self::new(StatusCode::CONFLICT, message)
+    }
+}
+
+impl<E: Into<eyre::Report>> From<E> for ApiError {
+    fn from(error: E) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            report: error.into(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let Self { status, report } = self;
+        match status.is_server_error() {
+            true => tracing::error!("{report:?}"),
+            false => tracing::info!("{status}: {report}"),
+        }
+        (
+            status,
+            Json(serde_json::json!({ "error": report.to_string() })),
+        )
+            .into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<Json<T>, ApiError>;
+
+/// document paths are resolved against the server's working directory, and made absolute so that
+/// the workspace (and the project dictionary) is the same no matter how the client spells them
+async fn canonical(path: &Path) -> std::result::Result<PathBuf, ApiError> {
+    tokio::fs::canonicalize(path)
+        .await
+        .map_err(|e| ApiError::not_found(format!("no document at {path:?} ({e})")))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DocumentRequest {
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SegmentRequest {
+    pub path: PathBuf,
+    pub key: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TranslateRequest {
+    pub path: PathBuf,
+    pub key: String,
+    /// replaces the translation of a confirmed segment too
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmRequest {
+    pub path: PathBuf,
+    pub key: String,
+    /// replaces the current translation before confirming it
+    pub translated_text: Option<String>,
+    /// sets [TranslationSegment::keep_out_of_memory] before confirming
+    pub keep_out_of_memory: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApplyRequest {
+    pub path: PathBuf,
+    /// defaults to the output template from the config
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkspaceSummary {
+    pub original_document: OriginalDocument,
+    pub translation_options: TlumokTranslationOptions,
+    pub segment_count: usize,
+    pub status_counts: IndexMap<SegmentStatus, usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SegmentView {
+    pub key: String,
+    pub original_text: String,
+    pub translated_text: Option<String>,
+    pub status: SegmentStatus,
+    pub keep_out_of_memory: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub needs_attention: Option<AttentionReason>,
+}
+
+impl SegmentView {
+    fn new(key: &str, segment: &TranslationSegment) -> Self {
+        Self {
+            key: key.to_owned(),
+            original_text: segment.original_text.clone(),
+            translated_text: segment.translated_text.clone(),
+            status: segment.status(),
+            keep_out_of_memory: segment.keep_out_of_memory,
+            note: segment.note.clone(),
+            needs_attention: segment.needs_attention.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Suggestions {
+    pub project: Vec<DictionarySuggestion>,
+    pub global: Vec<DictionarySuggestion>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Applied {
+    pub output: PathBuf,
+}
+
+/// workspace of an already canonical document path, see [canonical]
+async fn load_workspace(
+    path: &Path,
+) -> std::result::Result<(TranslationWorkspace, PathBuf), ApiError> {
+    let original_document = OriginalDocument::from_file(path)
+        .map_err(|e| ApiError::bad_request(format!("opening original document {path:?}: {e}")))?;
+    let workspace_path = TranslationWorkspace::default_path_for_document(&original_document);
+    if !workspace_path.exists() {
+        return Err(ApiError::not_found(format!(
+            "{path:?} has no workspace yet, open it with `POST /workspace` first"
+        )));
+    }
+    Ok((
+        TranslationWorkspace::load(&workspace_path).await?,
+        workspace_path,
+    ))
+}
+
+fn segment_mut<'a>(
+    workspace: &'a mut TranslationWorkspace,
+    key: &str,
+) -> std::result::Result<&'a mut TranslationSegment, ApiError> {
+    workspace
+        .segments
+        .segments
+        .get_mut(key)
+        .ok_or_else(|| ApiError::not_found(format!("there is no segment [{key}]")))
+}
+
+async fn open_workspace(
+    Extension(state): State,
+    Json(DocumentRequest { path }): Json<DocumentRequest>,
+) -> ApiResult<WorkspaceSummary> {
+    let path = canonical(&path).await?;
+    let _guard = state.lock.lock().await;
+    let config = state.config.clone().for_document(&path)?;
+    let workspace = TranslationWorkspace::get_or_create_for_path(
+        path,
+        config.default_translation_options,
+        config.max_segment_length,
+        config.workspace_storage(),
+    )
+    .await?;
+    let mut status_counts = IndexMap::new();
+    for segment in workspace.segments.segments.values() {
+        *status_counts.entry(segment.status()).or_default() += 1;
+    }
+    Ok(Json(WorkspaceSummary {
+        segment_count: workspace.segments.segments.len(),
+        status_counts,
+        original_document: workspace.original_document,
+        translation_options: workspace.translation_options,
+    }))
+}
+
+async fn list_segments(
+    Extension(state): State,
+    Query(DocumentRequest { path }): Query<DocumentRequest>,
+) -> ApiResult<Vec<SegmentView>> {
+    let path = canonical(&path).await?;
+    let _guard = state.lock.lock().await;
+    let (workspace, _) = load_workspace(&path).await?;
+    Ok(Json(
+        workspace
+            .segments
+            .segments
+            .iter()
+            .map(|(key, segment)| SegmentView::new(key, segment))
+            .collect(),
+    ))
+}
+
+async fn translate_segment(
+    Extension(state): State,
+    Json(TranslateRequest {
+        path,
+        key,
+        overwrite,
+    }): Json<TranslateRequest>,
+) -> ApiResult<SegmentView> {
+    let path = canonical(&path).await?;
+    let _guard = state.lock.lock().await;
+    let (mut workspace, workspace_path) = load_workspace(&path).await?;
+    let translation_options = workspace.translation_options;
+    let translation_service = state
+        .translation_service
+        .with_config(&state.config.clone().for_document(&path)?);
+    let max_history = translation_service.max_machine_translation_history;
+    let segment = segment_mut(&mut workspace, &key)?;
+    if segment.locked {
+        return Err(ApiError::conflict(format!("segment [{key}] is locked")));
+    }
+    if segment.confirmed.is_some() && !overwrite {
+        return Err(ApiError::conflict(format!(
+            "segment [{key}] is confirmed already, set `overwrite` to translate it anyway"
+        )));
+    }
+    let translated = translation_service
+        .translate_text(segment.original_text.clone(), translation_options)
+        .await?;
+    segment.set_machine_translation(Some(translated), translation_options, max_history);
+    let view = SegmentView::new(&key, segment);
+    workspace.save(&workspace_path).await?;
+    Ok(Json(view))
+}
+
+async fn confirm_segment(
+    Extension(state): State,
+    Json(ConfirmRequest {
+        path,
+        key,
+        translated_text,
+        keep_out_of_memory,
+    }): Json<ConfirmRequest>,
+) -> ApiResult<SegmentView> {
+    let path = canonical(&path).await?;
+    let _guard = state.lock.lock().await;
+    let (mut workspace, workspace_path) = load_workspace(&path).await?;
+    let TlumokTranslationOptions {
+        source_language,
+        target_language,
+        ..
+    } = workspace.translation_options;
+    let document_path = workspace.original_document.path.clone();
+    let segment = segment_mut(&mut workspace, &key)?;
+    if segment.locked {
+        return Err(ApiError::conflict(format!("segment [{key}] is locked")));
+    }
+    if let Some(translated_text) = translated_text {
+        segment.translated_text = Some(translated_text);
+    }
+    if let Some(keep_out_of_memory) = keep_out_of_memory {
+        segment.keep_out_of_memory = keep_out_of_memory;
+    }
+    let confirmed = segment
+        .confirm()
+        .ok_or_else(|| ApiError::conflict(format!("segment [{key}] is not translated yet")))?;
+    let original_text = segment.original_text.clone();
+    let keep_out_of_memory = segment.keep_out_of_memory;
+    let view = SegmentView::new(&key, segment);
+    workspace.save(&workspace_path).await?;
+    if !keep_out_of_memory {
+        state
+            .translation_service
+            .dictionary_service
+            .clone()
+            .save_translation(
+                document_path,
+                (source_language, target_language),
+                original_text,
+                confirmed,
+            )
+            .await?;
+    }
+    Ok(Json(view))
+}
+
+async fn apply(
+    Extension(state): State,
+    Json(ApplyRequest { path, output }): Json<ApplyRequest>,
+) -> ApiResult<Applied> {
+    let path = canonical(&path).await?;
+    let _guard = state.lock.lock().await;
+    let (mut workspace, workspace_path) = load_workspace(&path).await?;
+    let output_template = state.config.output_template();
+    if output.is_none()
+        && output_template.translates_stem()
+        && workspace
+            .ensure_translated_stem(&state.translation_service)
+            .await?
+    {
+        workspace.save(&workspace_path).await?;
+    }
+    let workspace = workspace.validated()?;
+    let output = output.unwrap_or_else(|| output_template.path_for(&workspace));
+    let output = workspace.save_translated_document(output).await?;
+    Ok(Json(Applied { output }))
+}
+
+async fn suggestions(
+    Extension(state): State,
+    Query(SegmentRequest { path, key }): Query<SegmentRequest>,
+) -> ApiResult<Suggestions> {
+    let path = canonical(&path).await?;
+    // dictionaries have locks of their own, only reading the workspace has to wait
+    let (mut workspace, _) = {
+        let _guard = state.lock.lock().await;
+        load_workspace(&path).await?
+    };
+    let TlumokTranslationOptions {
+        source_language,
+        target_language,
+        ..
+    } = workspace.translation_options;
+    let document_path = workspace.original_document.path.clone();
+    let original_text = segment_mut(&mut workspace, &key)?.original_text.clone();
+    let dictionary_service = state.translation_service.dictionary_service.clone();
+    let (project, global) = futures::try_join!(
+        dictionary_service.clone().get_project_suggestions(
+            document_path,
+            (source_language, target_language),
+            original_text.clone(),
+        ),
+        dictionary_service
+            .get_global_suggestions((source_language, target_language), original_text),
+    )?;
+    Ok(Json(Suggestions { project, global }))
+}
+
+pub async fn serve(
+    address: SocketAddr,
+    translation_service: TranslationService,
+    config: TlumokConfig,
+) -> Result<()> {
+    let state = Arc::new(ServerState {
+        translation_service,
+        config,
+        lock: Default::default(),
+    });
+    let app = Router::new()
+        .route("/workspace", post(open_workspace))
+        .route("/workspace/segments", get(list_segments))
+        .route("/workspace/translate", post(translate_segment))
+        .route("/workspace/confirm", post(confirm_segment))
+        .route("/workspace/apply", post(apply))
+        .route("/workspace/suggestions", get(suggestions))
+        .layer(Extension(state));
+    tracing::info!("listening on [{address}]");
+    // requests already being handled get to finish, so their workspaces are saved
+    let _handled = shutdown::handled_here();
+    axum::Server::bind(&address)
+        .serve(app.into_make_service())
+        .with_graceful_shutdown(shutdown::requested())
+        .await
+        .wrap_err("running http server")?;
+    tracing::info!("shutting down");
+    Ok(())
+}
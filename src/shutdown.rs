@@ -0,0 +1,69 @@
+//! ctrl+c handling, parts of tlumok that can stop cleanly (translating documents, the gui, the
+//! server) are asked to and get to save their work first, everything else quits right away -
+//! open dictionaries are flushed either way
+use super::*;
+use once_cell::sync::Lazy;
+use std::sync::atomic::{
+    AtomicUsize,
+    Ordering,
+};
+use tokio::sync::watch;
+
+/// how many running parts of tlumok stop on their own when asked to, see [handled_here]
+static HANDLERS: AtomicUsize = AtomicUsize::new(0);
+/// how many times ctrl+c was pressed so far
+static SIGNALS: Lazy<watch::Sender<usize>> = Lazy::new(|| watch::channel(0).0);
+
+/// held for as long as the caller checks [is_requested] (or waits for it) and stops by itself,
+/// the first ctrl+c then only asks it to, the second one quits anyway
+pub struct Handled;
+
+impl Drop for Handled {
+    fn drop(&mut self) {
+        HANDLERS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub fn handled_here() -> Handled {
+    HANDLERS.fetch_add(1, Ordering::SeqCst);
+    Handled
+}
+
+pub fn is_requested() -> bool {
+    *SIGNALS.borrow() > 0
+}
+
+/// every ctrl+c pressed from now on
+pub fn signals() -> watch::Receiver<usize> {
+    SIGNALS.subscribe()
+}
+
+/// resolves once shutting down was requested
+pub async fn requested() {
+    let mut signals = signals();
+    while *signals.borrow() == 0 {
+        if signals.changed().await.is_err() {
+            return futures::future::pending().await;
+        }
+    }
+}
+
+/// listens for ctrl+c in the background, for the rest of the program
+pub fn listen() {
+    tokio::spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            let signals = *SIGNALS.borrow() + 1;
+            SIGNALS.send_replace(signals);
+            if signals == 1 && HANDLERS.load(Ordering::SeqCst) > 0 {
+                tracing::warn!(
+                    "shutting down once the current step is done, press ctrl+c again to quit right away"
+                );
+                continue;
+            }
+            tracing::warn!("shutting down");
+            // destructors don't run on exit, so this is the last chance to write the dictionaries out
+            key_value_cache::cache_service::flush_open_dbs();
+            std::process::exit(130);
+        }
+    });
+}
@@ -0,0 +1,87 @@
+//! short dictionary entries used as terminology inside of longer segments
+use super::*;
+use itertools::Itertools;
+
+/// longer dictionary entries are whole sentences rather than terms
+pub const MAX_TERM_WORDS: usize = 4;
+
+/// a term found in a segment's original text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermMatch {
+    /// byte range in the searched text
+    pub range: std::ops::Range<usize>,
+    pub term: String,
+    pub translation: String,
+}
+
+/// dictionary entries of at most [MAX_TERM_WORDS] words, with their latest translation
+#[derive(Debug, Clone, Default)]
+pub struct Terms(Vec<(String, String)>);
+
+impl Terms {
+    pub fn from_entries(entries: impl IntoIterator<Item = (String, Vec<String>)>) -> Self {
+        let mut terms = entries
+            .into_iter()
+            .filter(|(term, _)| {
+                let words = term.split_whitespace().count();
+                (1..=MAX_TERM_WORDS).contains(&words) && lint::has_words(term)
+            })
+            .filter_map(|(term, mut translations)| {
+                translations
+                    .pop()
+                    .map(|translation| (term.trim().to_string(), translation))
+            })
+            .collect_vec();
+        // longer terms win over the ones they contain
+        terms.sort_by_key(|(term, _)| std::cmp::Reverse(term.len()));
+        Self(terms)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// non overlapping occurrences of whole terms (ascii case is ignored), in text order
+    pub fn find(&self, text: &str) -> Vec<TermMatch> {
+        let is_word_char = |c: Option<char>| c.map(char::is_alphanumeric).unwrap_or(false);
+        let mut matches: Vec<TermMatch> = vec![];
+        for (term, translation) in self.0.iter() {
+            for (start, _) in text.char_indices() {
+                let end = start + term.len();
+                let found = text
+                    .get(start..end)
+                    .map(|candidate| candidate.eq_ignore_ascii_case(term))
+                    .unwrap_or(false)
+                    && !is_word_char(text[..start].chars().next_back())
+                    && !is_word_char(text[end..].chars().next())
+                    && !matches
+                        .iter()
+                        .any(|m| m.range.start < end && start < m.range.end);
+                if found {
+                    matches.push(TermMatch {
+                        range: start..end,
+                        term: term.clone(),
+                        translation: translation.clone(),
+                    });
+                }
+            }
+        }
+        matches.sort_by_key(|m| m.range.start);
+        matches
+    }
+}
+
+/// puts the found terms in «» so they stand out in plain text
+pub fn marked(text: &str, matches: &[TermMatch]) -> String {
+    let mut marked = String::with_capacity(text.len() + matches.len() * 4);
+    let mut last = 0;
+    for TermMatch { range, .. } in matches {
+        marked.push_str(&text[last..range.start]);
+        marked.push('«');
+        marked.push_str(&text[range.clone()]);
+        marked.push('»');
+        last = range.end;
+    }
+    marked.push_str(&text[last..]);
+    marked
+}
@@ -0,0 +1,105 @@
+//! TMX 1.4 export of project dictionaries, so that confirmed translations can be reused
+//! as a translation memory in other CAT tools
+//!
+//! every translated variant of an original text becomes its own `<tu>`,
+//! dated with the time the dictionary entry was created
+use super::*;
+use crate::key_value_cache::cache_service::CacheEntry;
+use chrono::TimeZone;
+use translation_service::{
+    DatedEntry,
+    DictionaryService,
+    Language,
+    LanguagePair,
+};
+
+fn escaped(text: &str) -> String {
+    String::from_utf8_lossy(&quick_xml::escape::escape(text.as_bytes())).into_owned()
+}
+
+fn language_code(language: Language) -> String {
+    language.to_deepl_language_static().to_lowercase()
+}
+
+/// `YYYYMMDDThhmmssZ`, the only date format TMX allows - app times are local so they're converted to utc
+fn tmx_date(time: AppTime) -> String {
+    chrono::Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|local| local.naive_utc())
+        .unwrap_or(time)
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// `--since` value, either a date (`2022-06-01`, meaning its midnight) or a date with time (`2022-06-01T12:30:00`)
+pub fn parse_since(since: &str) -> std::result::Result<AppTime, String> {
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"]
+        .into_iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(since, format).ok())
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .ok_or_else(|| format!("expected YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS, got [{since}]"))
+}
+
+pub fn default_tmx_path_for_document(OriginalDocument { path, .. }: &OriginalDocument) -> PathBuf {
+    path.with_extension("tlumok.tmx")
+}
+
+pub fn to_tmx((source_language, target_language): LanguagePair, entries: &[DatedEntry]) -> String {
+    let (source_language, target_language) = (
+        language_code(source_language),
+        language_code(target_language),
+    );
+    let mut tmx = String::new();
+    tmx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    tmx.push_str("<tmx version=\"1.4\">\n");
+    tmx.push_str(&format!(
+        "  <header creationtool=\"tlumok\" creationtoolversion=\"{}\" segtype=\"paragraph\" o-tmf=\"tlumok\" adminlang=\"en\" srclang=\"{source_language}\" datatype=\"plaintext\"/>\n  <body>\n",
+        env!("CARGO_PKG_VERSION"),
+    ));
+    for (
+        original_text,
+        CacheEntry {
+            value,
+            created,
+            updated,
+        },
+    ) in entries
+    {
+        let changed = updated
+            .map(|updated| format!(" changedate=\"{}\"", tmx_date(updated)))
+            .unwrap_or_default();
+        for translated_text in value {
+            tmx.push_str(&format!(
+                "    <tu creationdate=\"{}\"{changed}>\n      <tuv xml:lang=\"{source_language}\"><seg>{}</seg></tuv>\n      <tuv xml:lang=\"{target_language}\"><seg>{}</seg></tuv>\n    </tu>\n",
+                tmx_date(*created),
+                escaped(original_text),
+                escaped(translated_text),
+            ));
+        }
+    }
+    tmx.push_str("  </body>\n</tmx>\n");
+    tmx
+}
+
+/// writes the project dictionary of a document to `output`, returns how many translation units were written
+pub async fn export_tmx(
+    dictionary_service: DictionaryService,
+    original_document_path: PathBuf,
+    language_pair: LanguagePair,
+    since: Option<AppTime>,
+    output: &Path,
+) -> Result<usize> {
+    let entries = dictionary_service
+        .project_entries(original_document_path, language_pair, since)
+        .await?;
+    let units = entries.iter().map(|(_, entry)| entry.value.len()).sum();
+    filesystem::write_atomically(output, to_tmx(language_pair, &entries).as_bytes())
+        .await
+        .wrap_err_with(|| format!("writing tmx to [{output:?}]"))?;
+    Ok(units)
+}
@@ -20,6 +20,8 @@ pub struct SuggestionPanel {
     pub translator_suggestion: Option<Vec<DictionarySuggestion>>,
     pub project_suggestions: Option<Vec<DictionarySuggestion>>,
     pub global_suggestions: Option<Vec<DictionarySuggestion>>,
+    /// kinds showing all of their suggestions, not just [TlumokConfig::max_suggestions_shown]
+    pub expanded: std::collections::HashSet<SuggestionKind>,
 }
 
 #[derive(Debug, Clone)]
@@ -27,60 +29,243 @@ pub struct PickingFile {
     current_dir: PathBuf,
 }
 
+/// last few components of the path, so that it fits in the header
+fn shortened_path(path: &Path) -> String {
+    const COMPONENTS: usize = 3;
+    let components = path.components().collect::<Vec<_>>();
+    match components.len() > COMPONENTS {
+        true => format!(
+            "…/{}",
+            components[components.len() - COMPONENTS..]
+                .iter()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .join("/")
+        ),
+        false => path.display().to_string(),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct InWorkspace {
     translation_workspace: TranslationWorkspace,
     focused_index: Option<String>,
     suggestions: SuggestionPanel,
+    /// suggestions loaded for segments that are not focused right now,
+    /// along with the original text they were loaded for
+    suggestion_cache: std::collections::HashMap<String, (String, SuggestionPanel)>,
+    /// keys of segments with their confirmation history expanded
+    expanded_history: std::collections::HashSet<String>,
+    /// keys of segments with their machine translation history expanded
+    expanded_machine_history: std::collections::HashSet<String>,
+    /// keys of segments whose original text is being corrected by hand
+    editing_source: std::collections::HashSet<String>,
+    /// user asked to confirm everything and save, waiting for them to make sure
+    finalize_requested: bool,
+    /// original texts with an exact match in the project dictionary, [None] until they're looked up,
+    /// texts confirmed since then are added as they're saved to it
+    exact_matches: Option<std::collections::HashSet<String>>,
+    /// segments changed since the workspace was last written, cleared when a save takes its snapshot
+    /// so edits made while it's being written aren't mistaken for saved ones
+    unsaved_changes: bool,
+    /// highlighted in the original text of the focused segment
+    terms: terms::Terms,
+    /// see [TlumokConfig::max_translation_lengths]
+    max_translation_length: Option<usize>,
+    /// see [TlumokConfig::max_suggestions_shown]
+    max_suggestions_shown: usize,
+    /// project and global suggestions below this are hidden, see [TlumokConfig::min_suggestion_percent]
+    min_suggestion_percent: u32,
+    /// keys of segments whose original text was edited after they got their translation
+    stale_translations: std::collections::HashSet<String>,
+    /// see [TlumokConfig::translate_on_open] and [Message::TranslateNewAndChanged]
+    batch_translation: BatchTranslation,
+    /// where the translated document was last saved to
+    last_output: Option<PathBuf>,
+    /// a save is being written right now, see [InWorkspace::save_workspace]
+    saving: bool,
+    /// the workspace was saved again while it was being written
+    save_again: bool,
+    /// only segments with a [TranslationSegment::note] are listed
+    only_noted: bool,
+}
+/// segments sent to deepl one batch after another
+#[derive(Debug, Clone, Default)]
+pub struct BatchTranslation {
+    /// keys of the segments that were not sent yet, in document order
+    remaining: std::collections::VecDeque<String>,
+    done: usize,
+    total: usize,
+}
+/// machine translations of one [BatchTranslation] batch, by segment key
+pub type BatchResults = Vec<(String, Option<translation_service::MachineTranslation>)>;
+impl BatchTranslation {
+    fn is_running(&self) -> bool {
+        self.done < self.total
+    }
+}
+#[derive(Debug, Clone)]
+pub struct DashboardEntry {
+    document: PathBuf,
+    /// see [TranslationSegments::completion]
+    completion: f32,
+    /// when the workspace was last saved
+    modified: AppTime,
 }
+
+/// documents with existing workspaces under `root`, the home screen
+#[derive(Debug, Clone)]
+pub struct Dashboard {
+    root: PathBuf,
+    /// [None] until the workspaces are found and loaded
+    entries: Option<Vec<DashboardEntry>>,
+}
+
+async fn dashboard_entries(root: PathBuf) -> Result<Vec<DashboardEntry>> {
+    let workspace_paths = tokio::task::block_in_place(|| TranslationWorkspace::find_all(&root))?;
+    let mut entries = vec![];
+    for workspace_path in workspace_paths {
+        let translation_workspace = match TranslationWorkspace::load(&workspace_path).await {
+            Ok(translation_workspace) => translation_workspace,
+            Err(e) => {
+                tracing::warn!("skipping [{workspace_path:?}] :: {e:?}");
+                continue;
+            }
+        };
+        entries.push(DashboardEntry {
+            document: translation_workspace.original_document.path.clone(),
+            completion: translation_workspace.segments.completion(),
+            modified: modified_at(&workspace_path)?,
+        });
+    }
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.modified));
+    Ok(entries)
+}
+
+impl Dashboard {
+    fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            entries: None,
+        }
+    }
+    fn load(&self) -> Command<Message> {
+        Command::perform(
+            dashboard_entries(self.root.clone()).map(Arc::new),
+            Message::DashboardLoaded,
+        )
+    }
+    fn view(&self) -> Element<'_, Message> {
+        let toolbar = row()
+            .spacing(20)
+            .push(text(format!("workspaces in {}", shortened_path(&self.root))).width(Length::Fill))
+            .push(button("refresh").on_press(Message::ShowDashboard(self.root.clone())))
+            .push(button("browse files").on_press(Message::BrowseFiles));
+        let entries = match &self.entries {
+            None => column().push(text("looking for workspaces...")),
+            Some(entries) if entries.is_empty() => column().push(text(
+                "no workspaces here yet, browse files to start translating",
+            )),
+            Some(entries) => entries.iter().fold(column().spacing(8), |acc, entry| {
+                acc.push(
+                    row()
+                        .spacing(20)
+                        .push(text(shortened_path(&entry.document)).width(Length::Fill))
+                        .push(text(format!("{:.0}%", entry.completion * 100.0)))
+                        .push(text(time_ago(entry.modified)))
+                        .push(
+                            button("open").on_press(Message::OpenWorkspace(entry.document.clone())),
+                        ),
+                )
+            }),
+        };
+        column()
+            .spacing(10)
+            .push(toolbar)
+            .push(scrollable(entries))
+            .into()
+    }
+}
+
 #[derive(Debug, Clone, derive_more::From)]
 pub enum AppMode {
+    Dashboard(Dashboard),
     PickingFile(PickingFile),
-    InWorkspace(InWorkspace),
+    InWorkspace(Box<InWorkspace>),
 }
 
 impl Default for AppMode {
     fn default() -> Self {
-        PickingFile {
-            current_dir: crate::filesystem::base_directory()
-                .expect("failed to find binary's parent dir"),
-        }
+        Dashboard::new(
+            crate::filesystem::base_directory().expect("failed to find binary's parent dir"),
+        )
         .into()
     }
 }
 #[derive(Clone, Debug)]
 pub struct TlumokState {
     error: Option<String>,
+    /// last thing the app did, shown in the status bar
+    status: Option<String>,
+    /// window close was requested while there were unsaved changes, waiting for the user to decide
+    exit_requested: bool,
+    /// see [iced::pure::Application::should_exit]
+    should_exit: bool,
+    /// suggestions panel is collapsed, kept for every workspace opened afterwards
+    suggestions_hidden: bool,
     translation_service: TranslationService,
+    config: TlumokConfig,
     app_mode: AppMode,
 }
 
 impl TlumokState {
-    pub fn new(translation_service: TranslationService) -> Self {
+    pub fn new(translation_service: TranslationService, config: TlumokConfig) -> Self {
         Self {
             error: Default::default(),
+            status: Default::default(),
+            exit_requested: false,
+            should_exit: false,
+            suggestions_hidden: false,
             translation_service,
+            config,
             app_mode: Default::default(),
         }
     }
+    fn unsaved_changes(&self) -> bool {
+        match &self.app_mode {
+            AppMode::InWorkspace(in_workspace) => {
+                in_workspace.unsaved_changes || in_workspace.saving
+            }
+            _ => false,
+        }
+    }
     pub fn e(&mut self, error: &eyre::Error) {
         tracing::error!("{error:#?}");
         self.error = Some(format!("{error:#?}"))
     }
-}
-
-macro_rules! e {
-    ($self:expr, $result:expr) => {{
-        match $result {
-            Ok(v) => v,
+    /// global config with the [project::ProjectOptions] of the document, if it has any
+    fn project_config(&mut self, document: &Path) -> TlumokConfig {
+        match self.config.clone().for_document(document) {
+            Ok(config) => config,
             Err(e) => {
-                $self.e(e);
-                return Command::none();
+                self.e(&e);
+                self.config.clone()
             }
         }
-    }};
+    }
+    fn open_workspace(&mut self, document: PathBuf) -> iced::Command<Message> {
+        let config = self.project_config(&document);
+        let task = TranslationWorkspace::get_or_create_for_path(
+            document,
+            config.default_translation_options,
+            config.max_segment_length,
+            config.workspace_storage(),
+        )
+        .map(Arc::new);
+        Command::perform(task, Message::NewWorkspaceLoaded)
+    }
 }
-#[derive(Debug, Clone, Copy)]
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SuggestionKind {
     Global,
     Machine,
@@ -88,22 +273,137 @@ pub enum SuggestionKind {
 }
 #[derive(Debug, Clone)]
 pub enum Message {
-    DocumentSaved(Arc<Result<()>>),
+    DocumentSaved(Arc<Result<PathBuf>>),
+    /// open a file (or a directory) with the default app of the system
+    OpenPath(PathBuf),
+    PathOpened(Arc<Result<()>>),
+    WorkspaceSaved(Arc<Result<PathBuf>>),
+    /// save the workspace and the translated document
     Save,
     // InitializeTranslationService,
     // TranslationServiceInitialized(Arc<Result<TranslationService>>),
     TranslationInput((String, String)),
+    /// hand correction of a segment's original text, see [TranslationSegment::source_edited]
+    OriginalInput((String, String)),
+    ToggleSourceEditing(String),
     FileSelected(PathBuf),
     NewWorkspaceLoaded(Arc<Result<TranslationWorkspace>>),
-    CtrlTab,
-    Tab,
+    /// alt + up
+    PreviousSegment,
+    /// alt + down
+    NextSegment,
+    /// alt + page up, previous segment the lint flagged, see [lint::lint_segment]
+    PreviousIssue,
+    /// alt + page down
+    NextIssue,
+    /// f9, collapses the suggestions panel so the translations get the whole width
+    ToggleSuggestions,
     /// user clicked on a translation
     ClickedOn(String),
     RequestedTranslations((SuggestionKind, String)),
     ReceivedTranslations(Arc<(String, SuggestionKind, Result<Vec<DictionarySuggestion>>)>),
+    /// show more (or fewer again) suggestions of the focused segment
+    ToggleSuggestionsExpanded(SuggestionKind),
+    /// hide project and global suggestions less similar than this, kept in the workspace
+    MinSuggestionPercentChanged(u32),
+    /// the slider was let go, the threshold it ended at is kept in the workspace
+    MinSuggestionPercentReleased,
     ApplyTranslation(DictionarySuggestion),
     ConfirmTranslation(String),
     SavedToProjectDictionary(Arc<Result<()>>),
+    /// save the confirmed translation of a segment into the global dictionary
+    PromoteToGlobal(String),
+    SavedToGlobalDictionary(Arc<Result<()>>),
+    ToggleHistory(String),
+    /// see [TranslationSegment::machine_translation_history]
+    ToggleMachineHistory(String),
+    ToggleLock(String),
+    /// see [TranslationSegment::keep_out_of_memory]
+    ToggleKeepOutOfMemory(String),
+    /// see [TranslationSegment::note], an empty note removes it
+    NoteInput((String, String)),
+    /// list only the segments with a note, or all of them again
+    ToggleOnlyNoted,
+    /// go back to the segment's last machine translation, see [TranslationSegment::revert_to_machine]
+    RevertToMachine(String),
+    /// confirm every segment and save the translated document (asks first)
+    FinalizeAll,
+    FinalizeAllConfirmed,
+    FinalizeAllCancelled,
+    ExactMatchesCounted(Arc<Result<std::collections::HashSet<String>>>),
+    TermsLoaded(Arc<Result<terms::Terms>>),
+    /// see [OutputTemplate::TRANSLATED_STEM]
+    StemTranslated(Arc<Result<String>>),
+    /// machine translate the untranslated segments and the ones with a stale translation
+    TranslateNewAndChanged,
+    /// a batch of [BatchTranslation] came back
+    BatchTranslated(Arc<Result<BatchResults>>),
+    /// look for workspaces under the given directory
+    ShowDashboard(PathBuf),
+    DashboardLoaded(Arc<Result<Vec<DashboardEntry>>>),
+    /// open (or create) the workspace of a document
+    OpenWorkspace(PathBuf),
+    /// go from the dashboard to the file picker
+    BrowseFiles,
+    /// user tried to close the window, it only closes right away if nothing is left unsaved
+    CloseRequested,
+    SaveAndExit,
+    ExitWithoutSaving,
+    ExitCancelled,
+}
+/// looks up every segment in the project dictionary at once, for the coverage summary in the header
+fn count_exact_matches(
+    dictionary_service: translation_service::DictionaryService,
+    translation_workspace: &TranslationWorkspace,
+) -> Command<Message> {
+    let TlumokTranslationOptions {
+        source_language,
+        target_language,
+        ..
+    } = translation_workspace.translation_options;
+    let texts = translation_workspace
+        .segments
+        .segments
+        .values()
+        .map(|segment| segment.original_text.clone())
+        .collect_vec();
+    let task = dictionary_service
+        .get_project_suggestions_batch(
+            translation_workspace.original_document.path.clone(),
+            (source_language, target_language),
+            texts,
+        )
+        .map(|found| found.map(|found| found.into_keys().collect()));
+    Command::perform(task.map(Arc::new), Message::ExactMatchesCounted)
+}
+/// short dictionary entries of the workspace's language pair, see [terms::Terms]
+fn load_terms(
+    dictionary_service: translation_service::DictionaryService,
+    translation_workspace: &TranslationWorkspace,
+) -> Command<Message> {
+    let TlumokTranslationOptions {
+        source_language,
+        target_language,
+        ..
+    } = translation_workspace.translation_options;
+    let task = dictionary_service
+        .all_entries((source_language, target_language))
+        .map(|entries| entries.map(terms::Terms::from_entries));
+    Command::perform(task.map(Arc::new), Message::TermsLoaded)
+}
+/// output file name for the workspace, when the template asks for a translated one that isn't there yet
+fn translate_stem(
+    translation_service: TranslationService,
+    translation_workspace: &TranslationWorkspace,
+    output_template: &OutputTemplate,
+) -> Command<Message> {
+    if !output_template.translates_stem() || translation_workspace.translated_stem.is_some() {
+        return Command::none();
+    }
+    let translation_workspace = translation_workspace.clone();
+    let task =
+        async move { filenames::translate_stem(&translation_workspace, translation_service).await };
+    Command::perform(task.map(Arc::new), Message::StemTranslated)
 }
 fn app_title() -> String {
     format!("Tłumok {}", clap::crate_version!())
@@ -111,7 +411,6 @@ fn app_title() -> String {
 
 fn dir_entries(path: &Path) -> Result<Vec<DirEntry>> {
     std::fs::read_dir(path)?
-        .into_iter()
         .map(|e| e.wrap_err("reading dir entry"))
         .collect()
 }
@@ -123,12 +422,10 @@ fn file_picker<'a>(current_dir: &'a Path) -> Result<Element<'a, Message>> {
             .push(button("select").on_press(Message::FileSelected(path)))
     };
     let entry = |path: PathBuf| {
-        let title = format!(
-            "{}",
-            path.file_name()
-                .map(|f| f.to_string_lossy().to_string())
-                .unwrap_or_default()
-        );
+        let title = path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
         named_entry(path, &title)
     };
     let empty_dir = match current_dir.parent() {
@@ -137,12 +434,28 @@ fn file_picker<'a>(current_dir: &'a Path) -> Result<Element<'a, Message>> {
     }
     .spacing(8);
     // let empty_dir = column().push(text("..")).push(entry(current_dir.parent));
-    let file_picker = dir_entries(&current_dir)?
+    let file_picker = dir_entries(current_dir)?
         .into_iter()
         .fold(empty_dir, |acc, next| acc.push(entry(next.path())));
     Ok(scrollable(file_picker).into())
 }
 
+/// human readable age of a dictionary entry, eg. "3 weeks ago"
+fn time_ago(created: AppTime) -> String {
+    let elapsed = now() - created;
+    let (amount, unit) = match elapsed {
+        e if e.num_weeks() >= 52 => (e.num_weeks() / 52, "year"),
+        e if e.num_days() >= 30 => (e.num_days() / 30, "month"),
+        e if e.num_weeks() >= 1 => (e.num_weeks(), "week"),
+        e if e.num_days() >= 1 => (e.num_days(), "day"),
+        e if e.num_hours() >= 1 => (e.num_hours(), "hour"),
+        e if e.num_minutes() >= 1 => (e.num_minutes(), "minute"),
+        _ => return "just now".to_string(),
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+    format!("{amount} {unit}{plural} ago")
+}
+
 fn or_error<'a, Message>(res: Result<Element<'a, Message>>) -> Element<'a, Message> {
     match res {
         Ok(view) => view,
@@ -151,48 +464,198 @@ fn or_error<'a, Message>(res: Result<Element<'a, Message>>) -> Element<'a, Messa
 }
 
 impl InWorkspace {
-    pub fn confirm_current_translation(
+    /// locks the ignored segments and starts sending the untranslated ones (and the ones
+    /// with a stale translation), see [BatchTranslation]
+    fn start_batch_translation(
         &mut self,
-        translation_service: TranslationService,
+        translation_service: &TranslationService,
+    ) -> Command<Message> {
+        let segments = &mut self.translation_workspace.segments;
+        if segments.lock_ignored(&translation_service.ignore_patterns, &SegmentSelection::All) > 0 {
+            self.unsaved_changes = true;
+        }
+        let remaining = segments
+            .pending(&SegmentSelection::All)
+            .filter(|(key, segment)| {
+                segment.translated_text.is_none() || self.stale_translations.contains(*key)
+            })
+            .map(|(key, _)| key.clone())
+            .collect::<std::collections::VecDeque<_>>();
+        self.batch_translation = BatchTranslation {
+            total: remaining.len(),
+            done: 0,
+            remaining,
+        };
+        self.translate_next_batch(translation_service)
+    }
+    /// whether a machine translation of a batch is still welcome when it comes back
+    fn awaits_batch_translation(&self, key: &str) -> bool {
+        self.translation_workspace
+            .segments
+            .segments
+            .get(key)
+            .map(|segment| {
+                segment.status() == SegmentStatus::Untranslated
+                    || (segment.needs_translation() && self.stale_translations.contains(key))
+            })
+            .unwrap_or_default()
+    }
+    fn translate_next_batch(
+        &mut self,
+        translation_service: &TranslationService,
+    ) -> Command<Message> {
+        let BatchTranslation { remaining, .. } = &mut self.batch_translation;
+        let keys = remaining
+            .drain(
+                ..remaining
+                    .len()
+                    .min(translation_service::TRANSLATION_BATCH_SIZE),
+            )
+            .collect_vec();
+        if keys.is_empty() {
+            return Command::none();
+        }
+        let texts = keys
+            .iter()
+            .map(|key| {
+                self.translation_workspace.segments.segments[key]
+                    .original_text
+                    .clone()
+            })
+            .collect();
+        let task = translation_service
+            .clone()
+            .translate_texts(texts, self.translation_workspace.translation_options)
+            .map(|results| results.map(|results| keys.into_iter().zip(results).collect()));
+        Command::perform(task.map(Arc::new), Message::BatchTranslated)
+    }
+    /// saves coming in while the workspace is being written are coalesced into a single
+    /// one, written once the current one is done (with whatever the workspace looks like by then)
+    fn save_workspace(&mut self) -> Command<Message> {
+        if self.saving {
+            self.save_again = true;
+            return Command::none();
+        }
+        self.saving = true;
+        self.unsaved_changes = false;
+        let translation_workspace = self.translation_workspace.clone();
+        let path = TranslationWorkspace::default_path_for_document(
+            &translation_workspace.original_document,
+        );
+        let task = async move { translation_workspace.save(&path).await.map(|_| path) };
+        Command::perform(task.map(Arc::new), Message::WorkspaceSaved)
+    }
+    fn confirm_segment(
+        &mut self,
+        key: &str,
+        translation_service: &TranslationService,
     ) -> iced::Command<Message> {
-        let Self {
-            translation_workspace:
-                TranslationWorkspace {
-                    original_document: OriginalDocument { path, .. },
-                    translation_options:
-                        TlumokTranslationOptions {
-                            source_language,
-                            target_language,
-                        },
-                    segments,
+        let TranslationWorkspace {
+            original_document: OriginalDocument { path, .. },
+            translation_options:
+                TlumokTranslationOptions {
+                    source_language,
+                    target_language,
                     ..
                 },
-            focused_index,
+            segments,
             ..
-        } = self;
-        if let Some(focused_index) = focused_index.clone() {
-            if let Some(TranslationSegment {
-                original_text,
-                translated_text,
-                confirmed: checked,
-                ..
-            }) = segments.segments.get_mut(&focused_index)
-            {
-                *checked = Some(translated_text.clone());
-                let task = translation_service
-                    .dictionary_service
-                    .clone()
-                    .save_translation(
-                        path.to_owned(),
-                        (source_language.clone(), target_language.clone()),
-                        original_text.clone(),
-                        translated_text.clone(),
-                    );
-                return Command::perform(task.map(Arc::new), Message::SavedToProjectDictionary);
+        } = &mut self.translation_workspace;
+        if let Some(segment) = segments.segments.get_mut(key) {
+            let translated_text = match segment.confirm() {
+                Some(translated_text) => translated_text,
+                None => return Command::none(),
+            };
+            if segment.keep_out_of_memory {
+                return Command::none();
+            }
+            // it's an exact match from now on
+            if let Some(exact_matches) = self.exact_matches.as_mut() {
+                exact_matches.insert(segment.original_text.clone());
             }
+            let task = translation_service
+                .dictionary_service
+                .clone()
+                .save_translation(
+                    path.to_owned(),
+                    (*source_language, *target_language),
+                    segment.original_text.clone(),
+                    translated_text,
+                );
+            return Command::perform(task.map(Arc::new), Message::SavedToProjectDictionary);
         }
         Command::none()
     }
+    pub fn confirm_current_translation(
+        &mut self,
+        translation_service: TranslationService,
+    ) -> iced::Command<Message> {
+        match self.focused_index.clone() {
+            Some(focused_index) => self.confirm_segment(&focused_index, &translation_service),
+            None => Command::none(),
+        }
+    }
+    /// confirms every translated segment which is not confirmed yet
+    pub fn confirm_all(
+        &mut self,
+        translation_service: TranslationService,
+    ) -> iced::Command<Message> {
+        let keys = self.unconfirmed_keys(true);
+        Command::batch(
+            keys.iter()
+                .map(|key| self.confirm_segment(key, &translation_service))
+                .collect_vec(),
+        )
+    }
+    /// keys of the segments that still need a confirmation, either the ones that already have a translation
+    /// (those can be confirmed) or the ones that don't
+    fn unconfirmed_keys(&self, translated: bool) -> Vec<String> {
+        self.translation_workspace
+            .segments
+            .segments
+            .iter()
+            .filter(|(_, segment)| {
+                !segment.status().is_final() && segment.translated_text.is_some() == translated
+            })
+            .map(|(key, _)| key.clone())
+            .collect_vec()
+    }
+    /// keys of the segments the lint flagged, in document order
+    fn issue_keys(&self) -> Vec<&String> {
+        self.translation_workspace
+            .segments
+            .segments
+            .iter()
+            .filter(|(_, segment)| {
+                !lint::lint_segment(segment, self.max_translation_length).is_empty()
+            })
+            .map(|(key, _)| key)
+            .collect()
+    }
+    /// closest flagged segment after (or before) the focused one, wrapping around the document
+    fn next_issue(&self, backwards: bool) -> Option<String> {
+        let segments = &self.translation_workspace.segments.segments;
+        let focused = self
+            .focused_index
+            .as_ref()
+            .and_then(|key| segments.get_index_of(key));
+        let issues = self.issue_keys();
+        let position = |key: &String| segments.get_index_of(key).unwrap_or_default();
+        let found = match (backwards, focused) {
+            (false, Some(focused)) => issues
+                .iter()
+                .find(|key| position(key) > focused)
+                .or_else(|| issues.first()),
+            (true, Some(focused)) => issues
+                .iter()
+                .rev()
+                .find(|key| position(key) < focused)
+                .or_else(|| issues.last()),
+            (false, None) => issues.first(),
+            (true, None) => issues.last(),
+        };
+        found.map(|key| key.to_string())
+    }
     pub fn select_index(&mut self, next_index: String) {
         if self
             .translation_workspace
@@ -207,76 +670,361 @@ impl InWorkspace {
                 .map(|current| current == &next_index)
                 .unwrap_or_default();
             if !is_same {
-                self.suggestions = SuggestionPanel::default();
+                let previous = std::mem::take(&mut self.suggestions);
+                if let Some(previous_index) = self.focused_index.take() {
+                    if let Some(panel) = self.suggestions_for(&previous_index) {
+                        *panel = previous;
+                    }
+                }
+                self.suggestions = self
+                    .suggestions_for(&next_index)
+                    .map(std::mem::take)
+                    .unwrap_or_default();
+                self.suggestion_cache.remove(&next_index);
             }
             self.focused_index = Some(next_index)
         }
     }
-    pub fn view<'a>(&'a self) -> Element<'a, Message> {
+    /// suggestions of a segment, cached ones are used for segments that are not focused
+    /// (and dropped if the segment's original text has changed since)
+    fn suggestions_for(&mut self, key: &str) -> Option<&mut SuggestionPanel> {
+        if self.focused_index.as_deref() == Some(key) {
+            return Some(&mut self.suggestions);
+        }
+        let original_text = &self
+            .translation_workspace
+            .segments
+            .segments
+            .get(key)?
+            .original_text;
+        let (cached_for, panel) = self
+            .suggestion_cache
+            .entry(key.to_owned())
+            .or_insert_with(|| (original_text.clone(), SuggestionPanel::default()));
+        if cached_for != original_text {
+            *cached_for = original_text.clone();
+            *panel = SuggestionPanel::default();
+        }
+        Some(panel)
+    }
+    pub fn view<'a>(
+        &'a self,
+        not_translated_marker: &'a str,
+        suggestions_hidden: bool,
+    ) -> Element<'a, Message> {
         let Self {
             translation_workspace,
             focused_index,
             suggestions: suggestion_panel,
+            suggestion_cache: _,
+            expanded_history,
+            expanded_machine_history,
+            editing_source,
+            finalize_requested,
+            exact_matches,
+            unsaved_changes: _,
+            terms,
+            max_translation_length,
+            max_suggestions_shown,
+            min_suggestion_percent,
+            stale_translations: _,
+            batch_translation,
+            last_output,
+            saving: _,
+            save_again: _,
+            only_noted,
         } = self;
+        // right to left translations get right aligned wherever they're only shown,
+        // iced can't align the text inside of an input
+        let translation_alignment = match translation_workspace
+            .translation_options
+            .target_language
+            .is_right_to_left()
+        {
+            true => Horizontal::Right,
+            false => Horizontal::Left,
+        };
         let segment_card = |segment: &'a TranslationSegment, key: &'a str| {
             let selected = focused_index.as_ref().map(|i| i == key).unwrap_or_default();
-            let confirmed = segment.confirmed.as_ref();
             let color = if selected {
                 [0.0, 0.8, 0.0]
             } else {
                 [0.0, 0.0, 0.0]
             };
-            let translated_part: Element<'a, _> = if selected {
-                text_input(
-                    "translation",
-                    &segment.translated_text.clone(),
+            let translated_part: Element<'a, _> = if selected && !segment.locked {
+                let input = text_input(
+                    not_translated_marker,
+                    segment.translated_text.as_deref().unwrap_or_default(),
                     |new_value| Message::TranslationInput((key.to_string(), new_value)),
+                );
+                match max_translation_length {
+                    Some(max_length) => {
+                        let length = segment
+                            .translated_text
+                            .as_deref()
+                            .unwrap_or_default()
+                            .chars()
+                            .count();
+                        column().push(input).push(
+                            text(format!("{length}/{max_length}")).size(14).color(
+                                match length > *max_length {
+                                    true => [0.7, 0.0, 0.0],
+                                    false => [0.4, 0.4, 0.4],
+                                },
+                            ),
+                        )
+                    }
+                    .into(),
+                    None => input.into(),
+                }
+            } else {
+                text(
+                    segment
+                        .translated_text
+                        .as_deref()
+                        .unwrap_or(not_translated_marker),
                 )
+                .width(Length::Fill)
+                .horizontal_alignment(translation_alignment)
                 .into()
-            } else {
-                text(&segment.translated_text).into()
             };
 
             let controls = match selected {
-                true => match confirmed {
-                    Some(confirmed_translation) => {
-                        match confirmed_translation == &segment.translated_text {
-                            true => button("confirmed"),
-                            false => button("confirm (edited)")
-                                .on_press(Message::ConfirmTranslation(key.to_string())),
-                        }
-                    }
-                    None => {
+                true => match segment.status() {
+                    SegmentStatus::Confirmed => button("confirmed"),
+                    SegmentStatus::EditedAfterConfirm => button("confirm (edited)")
+                        .on_press(Message::ConfirmTranslation(key.to_string())),
+                    SegmentStatus::MachineTranslated => {
                         button("confirm").on_press(Message::ConfirmTranslation(key.to_string()))
-                    } // true => button("confirmed"),
-                      // false => {
-                      //     button("confirm").on_press(Message::ConfirmTranslation(key.to_string()))
-                      // }
+                    }
+                    SegmentStatus::Untranslated => button("confirm"),
+                    SegmentStatus::Locked => button("locked"),
                 },
 
                 false => button("select").on_press(Message::ClickedOn(key.to_string())),
             };
-            row()
+            let lock = selected.then(|| {
+                button(match segment.locked {
+                    true => "unlock",
+                    false => "lock",
+                })
+                .on_press(Message::ToggleLock(key.to_string()))
+            });
+            let editing = selected && editing_source.contains(key);
+            let original_part = match editing {
+                true => column()
+                    .push(text_input(
+                        "original text",
+                        &segment.original_text,
+                        |new_value| Message::OriginalInput((key.to_string(), new_value)),
+                    ))
+                    .push(
+                        text("editing the source disables checking this segment against the original document")
+                            .size(14)
+                            .color([0.7, 0.0, 0.0]),
+                    ),
+                false => {
+                    let term_matches = match selected {
+                        true => terms.find(&segment.original_text),
+                        false => vec![],
+                    };
+                    match term_matches.is_empty() {
+                        true => column().push(text(&segment.original_text).color(color)),
+                        false => term_matches.iter().fold(
+                            column().push(
+                                text(terms::marked(&segment.original_text, &term_matches))
+                                    .color(color),
+                            ),
+                            |acc, terms::TermMatch {
+                                 term, translation, ..
+                             }| {
+                                acc.push(
+                                    text(format!("{term} -> {translation}"))
+                                        .size(14)
+                                        .color([0.0, 0.4, 0.8]),
+                                )
+                            },
+                        ),
+                    }
+                }
+            };
+            let original_part = match segment.source_edited && !editing {
+                true => original_part.push(text("source edited").size(14)),
+                false => original_part,
+            };
+            let issues = match selected {
+                true => lint::lint_segment(segment, *max_translation_length),
+                false => vec![],
+            };
+            let original_part = match issues.is_empty() {
+                true => original_part,
+                false => original_part.push(
+                    text(format!("issues: {}", issues.iter().join(", ")))
+                        .size(14)
+                        .color([0.7, 0.0, 0.0]),
+                ),
+            };
+            let original_part = match segment.needs_attention.as_ref() {
+                Some(reason) => original_part.push(
+                    text(format!("needs attention: {reason}"))
+                        .size(14)
+                        .color([0.8, 0.4, 0.0]),
+                ),
+                None => original_part,
+            };
+            let original_part = match (selected, segment.note.as_deref()) {
+                (true, note) => {
+                    original_part.push(text_input("note", note.unwrap_or_default(), |new_value| {
+                        Message::NoteInput((key.to_string(), new_value))
+                    }))
+                }
+                (false, Some(note)) => original_part.push(
+                    text(format!("note: {note}"))
+                        .size(14)
+                        .color([0.5, 0.2, 0.6]),
+                ),
+                (false, None) => original_part,
+            };
+            let original_part = match selected && !segment.locked {
+                true => original_part.push(
+                    button(match editing {
+                        true => "done editing source",
+                        false => "edit source",
+                    })
+                    .on_press(Message::ToggleSourceEditing(key.to_string())),
+                ),
+                false => original_part,
+            };
+            let card = row()
                 .spacing(10)
-                .push(
-                    column()
-                        .width(Length::FillPortion(1))
-                        .push(text(&segment.original_text).color(color)),
-                )
+                .push(original_part.width(Length::FillPortion(1)))
                 .push(column().width(Length::FillPortion(2)).push(translated_part))
-                .push(controls)
+                .push(controls);
+            let card = match lock {
+                Some(lock) => card.push(lock),
+                None => card,
+            };
+            let memory = (selected && !segment.locked).then(|| {
+                button(match segment.keep_out_of_memory {
+                    true => "save to memory",
+                    false => "keep out of memory",
+                })
+                .on_press(Message::ToggleKeepOutOfMemory(key.to_string()))
+            });
+            let card = match memory {
+                Some(memory) => card.push(memory),
+                None => card,
+            };
+            let revert = (selected
+                && !segment.locked
+                && segment.machine_translation.is_some()
+                && segment.machine_translation != segment.translated_text)
+                .then(|| {
+                    button("revert to machine translation")
+                        .on_press(Message::RevertToMachine(key.to_string()))
+                });
+            let card = match revert {
+                Some(revert) => card.push(revert),
+                None => card,
+            };
+            let card = match selected && segment.confirmed.is_some() {
+                true => card.push(
+                    button("promote to global").on_press(Message::PromoteToGlobal(key.to_string())),
+                ),
+                false => card,
+            };
+            let card = column().spacing(5).push(card);
+            if !selected {
+                return card;
+            }
+            let card = match segment.history.is_empty() {
+                true => card,
+                false => {
+                    let toggle = button(text(format!("history ({})", segment.history.len())))
+                        .on_press(Message::ToggleHistory(key.to_string()));
+                    let card = card.push(toggle);
+                    match expanded_history.contains(key) {
+                        true => segment.history.iter().rev().fold(
+                            card,
+                            |acc, SegmentEdit { at, previous, new }| {
+                                let change = match previous {
+                                    Some(previous) => format!("[{previous}] -> [{new}]"),
+                                    None => format!("[{new}]"),
+                                };
+                                acc.push(
+                                    text(format!("{} {change}", at.format("%Y-%m-%d %H:%M:%S")))
+                                        .size(16),
+                                )
+                            },
+                        ),
+                        false => card,
+                    }
+                }
+            };
+            if segment.machine_translation_history.is_empty() {
+                return card;
+            }
+            let toggle = button(text(format!(
+                "machine translations ({})",
+                segment.machine_translation_history.len()
+            )))
+            .on_press(Message::ToggleMachineHistory(key.to_string()));
+            let card = card.push(toggle);
+            match expanded_machine_history.contains(key) {
+                true => segment.machine_translation_history.iter().rev().fold(
+                    card,
+                    |acc, MachineTranslationVariant { at, text: variant }| {
+                        acc.push(
+                            text(format!("{} [{variant}]", at.format("%Y-%m-%d %H:%M:%S")))
+                                .size(16),
+                        )
+                    },
+                ),
+                false => card,
+            }
         };
         let translations = translation_workspace
             .segments
             .segments
             .iter()
+            .filter(|(_, segment)| !*only_noted || segment.note.is_some())
             .fold(column().spacing(15), |acc, (key, segment)| {
                 acc.push(segment_card(segment, key))
             });
 
         let suggestion_box = |suggestion: &DictionarySuggestion| {
+            let added = suggestion
+                .created
+                .map(|created| text(format!("added {}", time_ago(created))).size(14));
+            let suggestion_text = column().width(Length::Fill).push(
+                text(&suggestion.translated_text)
+                    .width(Length::Fill)
+                    .horizontal_alignment(translation_alignment),
+            );
+            let suggestion_text = match added {
+                Some(added) => suggestion_text.push(added),
+                None => suggestion_text,
+            };
+            let suggestion_text = match suggestion.match_type {
+                translation_service::MatchType::Exact => suggestion_text,
+                translation_service::MatchType::PartialPercent(percent) => suggestion_text.push(
+                    text(format!(
+                        "{percent}% match of {:?}",
+                        suggestion.original_text
+                    ))
+                    .size(14),
+                ),
+            };
+            let suggestion_text = match suggestion.needs_attention.as_ref() {
+                Some(reason) => suggestion_text.push(
+                    text(format!("needs attention: {reason}"))
+                        .size(14)
+                        .color([0.8, 0.4, 0.0]),
+                ),
+                None => suggestion_text,
+            };
             row()
-                .push(text(&suggestion.translated_text))
+                .push(suggestion_text)
                 .push(button("apply").on_press(Message::ApplyTranslation(suggestion.clone())))
         };
         let suggestions =
@@ -291,9 +1039,44 @@ impl InWorkspace {
                 .horizontal_alignment(Horizontal::Center);
                 let base = column().align_items(iced::Alignment::Center).push(title); // base_suggestions
                 match suggestions.as_ref() {
-                    Some(suggestions) => suggestions
-                        .iter()
-                        .fold(base, |acc, suggestion| acc.push(suggestion_box(suggestion))),
+                    Some(suggestions) => {
+                        // machine translations aren't matches of anything, so they always show
+                        let (mut sorted, below): (Vec<_>, Vec<_>) =
+                            suggestions.iter().partition(|suggestion| {
+                                kind == SuggestionKind::Machine
+                                    || suggestion.match_type.percent() >= *min_suggestion_percent
+                            });
+                        let base = match below.len() {
+                            0 => base,
+                            below => base.push(
+                                text(format!("{below} below {min_suggestion_percent}% hidden"))
+                                    .size(14),
+                            ),
+                        };
+                        sorted.sort_by_key(|suggestion| {
+                            std::cmp::Reverse(suggestion.match_type.percent())
+                        });
+                        let expanded = suggestion_panel.expanded.contains(&kind);
+                        let overflow = sorted.len().saturating_sub(*max_suggestions_shown);
+                        let shown = sorted
+                            .into_iter()
+                            .take(match expanded {
+                                true => usize::MAX,
+                                false => *max_suggestions_shown,
+                            })
+                            .fold(base, |acc, suggestion| acc.push(suggestion_box(suggestion)));
+                        match (overflow, expanded) {
+                            (0, _) => shown,
+                            (_, true) => shown.push(
+                                button("show less")
+                                    .on_press(Message::ToggleSuggestionsExpanded(kind)),
+                            ),
+                            (hidden, false) => shown.push(
+                                button(text(format!("show {hidden} more")))
+                                    .on_press(Message::ToggleSuggestionsExpanded(kind)),
+                            ),
+                        }
+                    }
                     None => {
                         if let Some(focused_index) = focused_index.as_ref() {
                             base.push(button("load").on_press(Message::RequestedTranslations((
@@ -306,8 +1089,21 @@ impl InWorkspace {
                     }
                 }
             };
+        let threshold = row()
+            .spacing(10)
+            .push(text(format!("min match {min_suggestion_percent}%")).size(16))
+            .push(
+                slider(
+                    0..=100,
+                    *min_suggestion_percent,
+                    Message::MinSuggestionPercentChanged,
+                )
+                .on_release(Message::MinSuggestionPercentReleased)
+                .width(Length::Fill),
+            );
         let suggestions_panel = column()
             .width(Length::Fill)
+            .push(threshold)
             .push(suggestions(
                 SuggestionKind::Machine,
                 &suggestion_panel.translator_suggestion,
@@ -320,53 +1116,188 @@ impl InWorkspace {
                 SuggestionKind::Global,
                 &suggestion_panel.global_suggestions,
             ));
-        row()
-            .push(container(scrollable(translations)).width(Length::FillPortion(3)))
-            .push(suggestions_panel.width(Length::FillPortion(1)))
+        let toolbar = match finalize_requested {
+            true => {
+                let unconfirmed = self.unconfirmed_keys(true).len();
+                let untranslated = self.unconfirmed_keys(false).len();
+                let confirm = button("yes");
+                row()
+                    .spacing(10)
+                    .push(text(match untranslated {
+                        0 => format!(
+                            "confirm all {unconfirmed} remaining segments and save the translated document?"
+                        ),
+                        untranslated => format!(
+                            "{untranslated} segments are not translated yet, translate them before confirming all"
+                        ),
+                    }))
+                    .push(match untranslated {
+                        0 => confirm.on_press(Message::FinalizeAllConfirmed),
+                        _ => confirm,
+                    })
+                    .push(button("cancel").on_press(Message::FinalizeAllCancelled))
+            }
+            false => {
+                let translate_new = button("translate new & changed");
+                let toolbar = row()
+                    .spacing(10)
+                    .push(match batch_translation.is_running() {
+                        true => translate_new,
+                        false => translate_new.on_press(Message::TranslateNewAndChanged),
+                    })
+                    .push(button("confirm all & save").on_press(Message::FinalizeAll))
+                    .push(text(format!("{} issues", self.issue_keys().len())))
+                    .push(button("previous issue").on_press(Message::PreviousIssue))
+                    .push(button("next issue").on_press(Message::NextIssue))
+                    .push(
+                        button(match suggestions_hidden {
+                            true => "show suggestions (f9)",
+                            false => "hide suggestions (f9)",
+                        })
+                        .on_press(Message::ToggleSuggestions),
+                    )
+                    .push(
+                        button(text(match only_noted {
+                            true => "show all segments".to_string(),
+                            false => format!(
+                                "show noted only ({})",
+                                translation_workspace
+                                    .segments
+                                    .segments
+                                    .values()
+                                    .filter(|segment| segment.note.is_some())
+                                    .count()
+                            ),
+                        }))
+                        .on_press(Message::ToggleOnlyNoted),
+                    );
+                match last_output {
+                    Some(output) => toolbar
+                        .push(text(format!("saved to {}", shortened_path(output))))
+                        .push(button("open output").on_press(Message::OpenPath(output.clone())))
+                        .push(button("open folder").on_press(Message::OpenPath(
+                            output.parent().map(Path::to_path_buf).unwrap_or_default(),
+                        ))),
+                    None => toolbar,
+                }
+            }
+        };
+        let OriginalDocument { path, file_format } = &translation_workspace.original_document;
+        let TlumokTranslationOptions {
+            source_language,
+            target_language,
+            ..
+        } = translation_workspace.translation_options;
+        let header = row()
+            .spacing(20)
+            .push(text(shortened_path(path)).width(Length::Fill))
+            .push(text(match exact_matches {
+                Some(exact_matches) => format!(
+                    "{}/{} segments have an exact match",
+                    translation_workspace
+                        .segments
+                        .segments
+                        .values()
+                        .filter(|segment| exact_matches.contains(&segment.original_text))
+                        .count(),
+                    translation_workspace.segments.segments.len()
+                ),
+                None => String::new(),
+            }))
+            .push(text(format!("[{file_format}]")))
+            .push(text(format!("{source_language} -> {target_language}")));
+        column()
+            .spacing(10)
+            .push(header)
+            .push(toolbar)
+            .push({
+                let translations =
+                    row().push(container(scrollable(translations)).width(Length::FillPortion(3)));
+                match suggestions_hidden {
+                    true => translations,
+                    false => translations.push(suggestions_panel.width(Length::FillPortion(1))),
+                }
+            })
             .into()
     }
 }
+/// ctrl+c pressed in the terminal the gui was started from, see [crate::shutdown]
+fn shutdown_requests() -> Subscription<Message> {
+    struct ShutdownRequests;
+    iced_native::subscription::unfold(
+        std::any::TypeId::of::<ShutdownRequests>(),
+        crate::shutdown::signals(),
+        |mut signals| async move {
+            match signals.changed().await {
+                Ok(()) => (Some(Message::CloseRequested), signals),
+                Err(_) => futures::future::pending().await,
+            }
+        },
+    )
+}
+
 impl Application for TlumokState {
     type Executor = iced::executor::Default;
 
     type Message = Message;
 
-    type Flags = (TranslationService,);
+    type Flags = (TranslationService, TlumokConfig);
 
-    fn new((translation_service,): Self::Flags) -> (Self, iced::Command<Self::Message>) {
-        (Self::new(translation_service), Command::none())
+    fn new((translation_service, config): Self::Flags) -> (Self, iced::Command<Self::Message>) {
+        let state = Self::new(translation_service, config);
+        let command = match &state.app_mode {
+            AppMode::Dashboard(dashboard) => dashboard.load(),
+            _ => Command::none(),
+        };
+        (state, command)
     }
 
     fn title(&self) -> String {
-        app_title()
+        match self.unsaved_changes() {
+            true => format!("{} *", app_title()),
+            false => app_title(),
+        }
+    }
+
+    fn should_exit(&self) -> bool {
+        self.should_exit
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        iced_native::subscription::events_with(|event, status| {
-            if let iced_native::event::Status::Captured = status {
-                return None;
-            }
-            match event {
-                iced_native::Event::Keyboard(keyboard::Event::KeyPressed {
-                    modifiers,
-                    key_code,
-                }) if key_code == keyboard::KeyCode::Tab => {
-                    if modifiers.control() || modifiers.shift() {
-                        Some(Message::CtrlTab)
-                    } else {
-                        Some(Message::Tab)
-                    }
-                }
+        // alt + arrows mean nothing to a text input, so these work even while one is focused
+        // and tab is left alone for moving between the fields
+        let events = iced_native::subscription::events_with(|event, _status| match event {
+            iced_native::Event::Keyboard(keyboard::Event::KeyPressed {
+                modifiers,
+                key_code,
+            }) if modifiers.alt() => match key_code {
+                keyboard::KeyCode::Up => Some(Message::PreviousSegment),
+                keyboard::KeyCode::Down => Some(Message::NextSegment),
+                keyboard::KeyCode::PageUp => Some(Message::PreviousIssue),
+                keyboard::KeyCode::PageDown => Some(Message::NextIssue),
                 _ => None,
+            },
+            iced_native::Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::F9,
+                ..
+            }) => Some(Message::ToggleSuggestions),
+            iced_native::Event::Window(iced_native::window::Event::CloseRequested) => {
+                Some(Message::CloseRequested)
             }
-        })
+            _ => None,
+        });
+        Subscription::batch([events, shutdown_requests()])
     }
     fn update(&mut self, message: Self::Message) -> iced::Command<Self::Message> {
         let translation_service = self.translation_service.clone();
         if let Message::NewWorkspaceLoaded(res) = &message {
             match res.as_ref() {
                 Ok(translation_workspace) => {
-                    self.app_mode = InWorkspace {
+                    let config = self.project_config(&translation_workspace.original_document.path);
+                    // later commands clone the service, so the project's patterns and rules stick
+                    self.translation_service = translation_service.with_config(&config);
+                    let translation_service = self.translation_service.clone();
+                    let mut in_workspace = InWorkspace {
                         translation_workspace: translation_workspace.clone(),
                         focused_index: translation_workspace
                             .segments
@@ -375,44 +1306,127 @@ impl Application for TlumokState {
                             .next()
                             .cloned(),
                         suggestions: Default::default(),
-                    }
-                    .into()
+                        suggestion_cache: Default::default(),
+                        expanded_history: Default::default(),
+                        expanded_machine_history: Default::default(),
+                        editing_source: Default::default(),
+                        finalize_requested: false,
+                        exact_matches: None,
+                        unsaved_changes: false,
+                        terms: Default::default(),
+                        max_translation_length: config.max_translation_length(
+                            translation_workspace.original_document.file_format,
+                        ),
+                        max_suggestions_shown: config.max_suggestions_shown,
+                        min_suggestion_percent: translation_workspace
+                            .min_suggestion_percent
+                            .unwrap_or(config.min_suggestion_percent),
+                        stale_translations: Default::default(),
+                        batch_translation: Default::default(),
+                        last_output: None,
+                        saving: false,
+                        save_again: false,
+                        only_noted: false,
+                    };
+                    let pre_translate = match config.translate_on_open {
+                        true => in_workspace.start_batch_translation(&translation_service),
+                        false => Command::none(),
+                    };
+                    self.status = Some(match in_workspace.batch_translation.total {
+                        0 => format!(
+                            "opened {}",
+                            shortened_path(&translation_workspace.original_document.path)
+                        ),
+                        total => format!("translating {total} segments..."),
+                    });
+                    self.app_mode = Box::new(in_workspace).into();
+                    return Command::batch([
+                        pre_translate,
+                        count_exact_matches(
+                            translation_service.dictionary_service.clone(),
+                            translation_workspace,
+                        ),
+                        load_terms(
+                            translation_service.dictionary_service.clone(),
+                            translation_workspace,
+                        ),
+                        translate_stem(
+                            translation_service.clone(),
+                            translation_workspace,
+                            &config.output_template(),
+                        ),
+                    ]);
                 }
-                Err(e) => self.e(&e),
+                Err(e) => self.e(e),
             }
             return Command::none();
         };
+        match &message {
+            Message::CloseRequested => match self.unsaved_changes() {
+                true => self.exit_requested = true,
+                false => self.should_exit = true,
+            },
+            Message::ExitWithoutSaving => self.should_exit = true,
+            Message::ExitCancelled => self.exit_requested = false,
+            Message::ToggleSuggestions => self.suggestions_hidden = !self.suggestions_hidden,
+            _ => {}
+        }
+        if let Message::ShowDashboard(root) = &message {
+            let dashboard = Dashboard::new(root.clone());
+            let command = dashboard.load();
+            self.app_mode = dashboard.into();
+            return command;
+        }
         match &mut self.app_mode {
-            AppMode::PickingFile(PickingFile { current_dir }) => match message {
-                Message::FileSelected(dir_entry) => match dir_entry.is_dir() {
-                    true => *current_dir = dir_entry,
-                    false => {
-                        let task =
-                            TranslationWorkspace::get_or_create_for_path(dir_entry).map(Arc::new);
-                        return Command::perform(task, Message::NewWorkspaceLoaded);
-                    }
+            AppMode::Dashboard(dashboard) => match message {
+                Message::DashboardLoaded(res) => match res.as_ref() {
+                    Ok(entries) => dashboard.entries = Some(entries.clone()),
+                    Err(e) => self.e(e),
                 },
-
+                Message::OpenWorkspace(path) => return self.open_workspace(path),
+                Message::BrowseFiles => {
+                    let current_dir = dashboard.root.clone();
+                    self.app_mode = PickingFile { current_dir }.into();
+                }
                 _ => {}
             },
+            AppMode::PickingFile(PickingFile { current_dir }) => {
+                if let Message::FileSelected(dir_entry) = message {
+                    match dir_entry.is_dir() {
+                        true => *current_dir = dir_entry,
+                        false => return self.open_workspace(dir_entry),
+                    }
+                }
+            }
             AppMode::InWorkspace(in_workspace) => match message {
+                Message::ShowDashboard(_)
+                | Message::DashboardLoaded(_)
+                | Message::OpenWorkspace(_)
+                | Message::BrowseFiles
+                | Message::ToggleSuggestions => {}
                 Message::TranslationInput((_, new_value)) => {
                     let InWorkspace {
                         translation_workspace,
                         focused_index,
-                        suggestions,
-                    } = in_workspace;
+                        unsaved_changes,
+                        stale_translations,
+                        ..
+                    } = &mut **in_workspace;
                     if let Some(focused_index) = focused_index.as_ref() {
                         if let Some(segment) = translation_workspace
                             .segments
                             .segments
                             .get_mut(focused_index)
+                            .filter(|segment| !segment.locked)
                         {
-                            segment.translated_text = new_value
+                            segment.translated_text = Some(new_value);
+                            segment.needs_attention = None;
+                            stale_translations.remove(focused_index);
+                            *unsaved_changes = true;
                         }
                     }
                 }
-                Message::CtrlTab => {
+                Message::PreviousSegment => {
                     // let InWorkspace {
                     //     translation_workspace,
                     //     focused_index,
@@ -436,7 +1450,7 @@ impl Application for TlumokState {
                         in_workspace.select_index(previous.to_string())
                     }
                 }
-                Message::Tab => {
+                Message::NextSegment => {
                     let keys = in_workspace
                         .translation_workspace
                         .segments
@@ -456,13 +1470,19 @@ impl Application for TlumokState {
                         // *focused_index = Some(previous.to_string())
                     }
                 }
+                Message::PreviousIssue | Message::NextIssue => {
+                    match in_workspace.next_issue(matches!(message, Message::PreviousIssue)) {
+                        Some(key) => in_workspace.select_index(key),
+                        None => self.status = Some("no issues found".to_string()),
+                    }
+                }
                 Message::ClickedOn(index) => in_workspace.select_index(index),
-                Message::RequestedTranslations((kind, index)) => {
+                Message::RequestedTranslations((kind, _index)) => {
                     let InWorkspace {
                         translation_workspace,
                         focused_index,
-                        suggestions,
-                    } = in_workspace;
+                        ..
+                    } = &mut **in_workspace;
                     if let Some(focused_index) = focused_index.as_ref() {
                         let language_pair = {
                             let t = translation_workspace.translation_options;
@@ -503,27 +1523,36 @@ impl Application for TlumokState {
                                     });
                                 }
                                 SuggestionKind::Machine => {
+                                    self.status = Some("translating...".to_string());
                                     let original_text = original_text.clone();
-                                    let task = translation_service.clone().translate_text(
+                                    let translation_options =
+                                        translation_workspace.translation_options;
+                                    let task = translation_service.clone().translate_alternatives(
                                         original_text.original_text.clone(),
-                                        TlumokTranslationOptions {
-                                            source_language: language_pair.0,
-                                            target_language: language_pair.1,
-                                        },
+                                        translation_options,
                                     );
                                     return Command::perform(task, move |res| {
                                         Message::ReceivedTranslations(Arc::new((
                                             focused_index.clone(),
                                             kind,
-                                            res.map(|translated_text| {
-                                                vec![DictionarySuggestion {
-                                                    original_text: original_text
-                                                        .original_text
-                                                        .clone(),
-                                                    translated_text,
-                                                    match_type:
-                                                        translation_service::MatchType::Exact,
-                                                }]
+                                            res.map(|translations| {
+                                                translations
+                                                    .into_iter()
+                                                    .map(|translated| DictionarySuggestion {
+                                                        needs_attention: translated
+                                                            .attention_reason(
+                                                                &original_text.original_text,
+                                                                translation_options,
+                                                            ),
+                                                        original_text: original_text
+                                                            .original_text
+                                                            .clone(),
+                                                        translated_text: translated.text,
+                                                        match_type:
+                                                            translation_service::MatchType::Exact,
+                                                        created: None,
+                                                    })
+                                                    .collect()
                                             }),
                                         )))
                                     });
@@ -533,16 +1562,12 @@ impl Application for TlumokState {
                     }
                 }
                 Message::ReceivedTranslations(event) => {
-                    let InWorkspace {
-                        translation_workspace,
-                        focused_index,
-                        suggestions,
-                    } = in_workspace;
                     let (key, kind, new_suggestions) = event.as_ref();
-                    if let Some(focused_index) = focused_index.as_ref() {
-                        if key == focused_index {
-                            match new_suggestions {
-                                Ok(new_suggestions) => match kind {
+                    match new_suggestions {
+                        Ok(new_suggestions) => {
+                            // results for segments the user already moved away from are kept for when they come back
+                            if let Some(suggestions) = in_workspace.suggestions_for(key) {
+                                match kind {
                                     SuggestionKind::Global => {
                                         suggestions.global_suggestions =
                                             Some(new_suggestions.clone())
@@ -555,42 +1580,123 @@ impl Application for TlumokState {
                                         suggestions.project_suggestions =
                                             Some(new_suggestions.clone())
                                     }
-                                },
-                                Err(e) => tracing::error!("{e:?}"),
+                                }
                             }
                         }
+                        Err(e) => {
+                            tracing::error!("{e:?}");
+                            self.status = Some(format!("loading {kind:?} suggestions failed"));
+                        }
+                    }
+                    if let (SuggestionKind::Machine, Ok(new_suggestions)) = (kind, new_suggestions)
+                    {
+                        // kept in the workspace as the segment's revert point
+                        let machine_translation = new_suggestions
+                            .first()
+                            .map(|suggestion| suggestion.translated_text.clone());
+                        if let Some(segment) = in_workspace
+                            .translation_workspace
+                            .segments
+                            .segments
+                            .get_mut(key)
+                            .filter(|_| machine_translation.is_some())
+                        {
+                            if let Some(machine_translation) = machine_translation {
+                                segment.record_machine_translation(
+                                    machine_translation,
+                                    self.translation_service.max_machine_translation_history,
+                                );
+                            }
+                            in_workspace.unsaved_changes = true;
+                        }
+                        self.status = Some(format!("translated {key}"));
                     }
                 } // _ => {}
                 Message::ApplyTranslation(dictionary_suggestion) => {
                     let InWorkspace {
                         translation_workspace,
                         focused_index,
-                        suggestions,
-                    } = in_workspace;
+                        unsaved_changes,
+                        stale_translations,
+                        ..
+                    } = &mut **in_workspace;
                     if let Some(focused_index) = focused_index.as_ref() {
                         if let Some(segment) = translation_workspace
                             .segments
                             .segments
                             .get_mut(focused_index)
+                            .filter(|segment| !segment.locked)
                         {
-                            segment.translated_text = dictionary_suggestion.translated_text.clone()
+                            segment.translated_text = Some(
+                                segment
+                                    .with_original_spacing(&dictionary_suggestion.translated_text),
+                            );
+                            segment.needs_attention = dictionary_suggestion.needs_attention.clone();
+                            stale_translations.remove(focused_index);
+                            *unsaved_changes = true;
                         }
                     }
                 }
                 Message::Save => {
-                    return Command::perform(
+                    let output_path = self
+                        .config
+                        .output_template()
+                        .path_for(&in_workspace.translation_workspace);
+                    self.status = Some("saving...".to_string());
+                    let save_document = Command::perform(
                         in_workspace
                             .translation_workspace
                             .clone()
-                            .save_translated_document()
+                            .save_translated_document(output_path)
                             .map(Arc::new),
                         Message::DocumentSaved,
-                    )
+                    );
+                    return Command::batch([in_workspace.save_workspace(), save_document]);
                 }
                 Message::DocumentSaved(res) => match res.as_ref() {
-                    Ok(_) => {}
-                    Err(e) => self.e(&e),
+                    Ok(path) => {
+                        self.status =
+                            Some(format!("saved translated document to {}", path.display()));
+                        in_workspace.last_output = Some(path.clone());
+                    }
+                    Err(e) => self.e(e),
                 },
+                Message::OpenPath(path) => {
+                    let task = tokio::task::spawn_blocking(move || {
+                        open::that(&path).wrap_err_with(|| format!("opening [{path:?}]"))
+                    })
+                    .map(|opened| opened.wrap_err("opening a file").and_then(|opened| opened));
+                    return Command::perform(task.map(Arc::new), Message::PathOpened);
+                }
+                Message::PathOpened(res) => {
+                    if let Err(e) = res.as_ref() {
+                        self.e(e);
+                    }
+                }
+                Message::WorkspaceSaved(res) => {
+                    in_workspace.saving = false;
+                    // the snapshot never made it to disk, so its changes are still unsaved
+                    if res.is_err() {
+                        in_workspace.unsaved_changes = true;
+                    }
+                    // edits made while it was being written aren't in the saved snapshot
+                    let save_again = std::mem::take(&mut in_workspace.save_again)
+                        || (res.is_ok() && in_workspace.unsaved_changes);
+                    let save = match save_again {
+                        true => in_workspace.save_workspace(),
+                        false => Command::none(),
+                    };
+                    match res.as_ref() {
+                        Ok(_) if save_again => {}
+                        Ok(_) => {
+                            self.status = Some("saved workspace".to_string());
+                            self.should_exit = self.exit_requested;
+                        }
+                        // the next save could fail the same way, so this one isn't silently dropped
+                        Err(e) => self.e(e),
+                    }
+                    return save;
+                }
 
                 Message::FileSelected(_) => todo!(),
                 Message::NewWorkspaceLoaded(_) => todo!(),
@@ -601,14 +1707,245 @@ impl Application for TlumokState {
                         .map(|i| i == &index)
                         .unwrap_or_default()
                     {
+                        in_workspace.unsaved_changes = true;
+                        self.status = Some(format!("confirmed {index}"));
                         return in_workspace
                             .confirm_current_translation(self.translation_service.clone());
                     }
                 }
                 Message::SavedToProjectDictionary(res) => match res.as_ref() {
-                    Ok(_) => {}
+                    Ok(_) => self.status = Some("saved to the project dictionary".to_string()),
+                    Err(e) => self.e(e),
+                },
+                Message::PromoteToGlobal(key) => {
+                    let TranslationWorkspace {
+                        translation_options:
+                            TlumokTranslationOptions {
+                                source_language,
+                                target_language,
+                                ..
+                            },
+                        segments,
+                        ..
+                    } = &in_workspace.translation_workspace;
+                    if let Some(TranslationSegment {
+                        original_text,
+                        confirmed: Some(confirmed),
+                        ..
+                    }) = segments.segments.get(&key)
+                    {
+                        let task = translation_service
+                            .dictionary_service
+                            .clone()
+                            .save_global_translation(
+                                (*source_language, *target_language),
+                                original_text.clone(),
+                                confirmed.clone(),
+                            );
+                        return Command::perform(
+                            task.map(Arc::new),
+                            Message::SavedToGlobalDictionary,
+                        );
+                    }
+                }
+                Message::SavedToGlobalDictionary(res) => match res.as_ref() {
+                    Ok(_) => self.status = Some("promoted to the global dictionary".to_string()),
                     Err(e) => self.e(e),
                 },
+                Message::ExactMatchesCounted(res) => match res.as_ref() {
+                    Ok(exact_matches) => in_workspace.exact_matches = Some(exact_matches.clone()),
+                    Err(e) => self.e(e),
+                },
+                Message::StemTranslated(res) => match res.as_ref() {
+                    Ok(translated_stem) => {
+                        in_workspace.translation_workspace.translated_stem =
+                            Some(translated_stem.clone());
+                        in_workspace.unsaved_changes = true;
+                    }
+                    Err(e) => self.e(e),
+                },
+                Message::TermsLoaded(res) => match res.as_ref() {
+                    Ok(terms) => in_workspace.terms = terms.clone(),
+                    Err(e) => self.e(e),
+                },
+                Message::BatchTranslated(res) => match res.as_ref() {
+                    Ok(results) => {
+                        let translation_options =
+                            in_workspace.translation_workspace.translation_options;
+                        in_workspace.batch_translation.done += results.len();
+                        for (key, result) in results {
+                            // the user could have started on the segment in the meantime
+                            if !in_workspace.awaits_batch_translation(key) {
+                                continue;
+                            }
+                            if let Some(segment) = in_workspace
+                                .translation_workspace
+                                .segments
+                                .segments
+                                .get_mut(key)
+                            {
+                                segment.set_machine_translation(
+                                    result.clone(),
+                                    translation_options,
+                                    self.translation_service.max_machine_translation_history,
+                                );
+                                in_workspace.stale_translations.remove(key);
+                                in_workspace.unsaved_changes = true;
+                            }
+                        }
+                        let BatchTranslation { done, total, .. } = &in_workspace.batch_translation;
+                        self.status = Some(format!("translated {done} of {total} segments"));
+                        return in_workspace.translate_next_batch(&translation_service);
+                    }
+                    Err(e) => {
+                        in_workspace.batch_translation = Default::default();
+                        self.e(e)
+                    }
+                },
+                Message::TranslateNewAndChanged => {
+                    if in_workspace.batch_translation.is_running() {
+                        return Command::none();
+                    }
+                    let command = in_workspace.start_batch_translation(&translation_service);
+                    self.status = Some(match in_workspace.batch_translation.total {
+                        0 => "nothing new to translate".to_string(),
+                        total => format!("translating {total} segments..."),
+                    });
+                    return command;
+                }
+                Message::FinalizeAll => in_workspace.finalize_requested = true,
+                Message::FinalizeAllCancelled => in_workspace.finalize_requested = false,
+                Message::FinalizeAllConfirmed => {
+                    in_workspace.finalize_requested = false;
+                    let untranslated = in_workspace.unconfirmed_keys(false).len();
+                    if untranslated > 0 {
+                        self.status = Some(format!(
+                            "{untranslated} segments are not translated yet, nothing was confirmed"
+                        ));
+                        return Command::none();
+                    }
+                    let confirm = in_workspace.confirm_all(translation_service.clone());
+                    self.status = Some("confirmed all segments, saving...".to_string());
+                    let output_path = self
+                        .config
+                        .output_template()
+                        .path_for(&in_workspace.translation_workspace);
+                    let save = Command::perform(
+                        in_workspace
+                            .translation_workspace
+                            .clone()
+                            .save_translated_document(output_path)
+                            .map(Arc::new),
+                        Message::DocumentSaved,
+                    );
+                    return Command::batch([confirm, in_workspace.save_workspace(), save]);
+                }
+                Message::SaveAndExit => {
+                    self.status = Some("saving before exit...".to_string());
+                    return in_workspace.save_workspace();
+                }
+                Message::CloseRequested | Message::ExitWithoutSaving | Message::ExitCancelled => {}
+                Message::ToggleSourceEditing(key) => {
+                    if !in_workspace.editing_source.remove(&key) {
+                        in_workspace.editing_source.insert(key);
+                    }
+                }
+                Message::OriginalInput((key, new_value)) => {
+                    if !in_workspace.editing_source.contains(&key) {
+                        return Command::none();
+                    }
+                    if let Some(segment) = in_workspace
+                        .translation_workspace
+                        .segments
+                        .segments
+                        .get_mut(&key)
+                        .filter(|segment| !segment.locked)
+                    {
+                        if segment.translated_text.is_some() {
+                            in_workspace.stale_translations.insert(key);
+                        }
+                        segment.edit_original_text(new_value);
+                        in_workspace.unsaved_changes = true;
+                        // suggestions were looked up for the old text
+                        in_workspace.suggestions = SuggestionPanel::default();
+                    }
+                }
+                Message::ToggleSuggestionsExpanded(kind) => {
+                    let expanded = &mut in_workspace.suggestions.expanded;
+                    if !expanded.remove(&kind) {
+                        expanded.insert(kind);
+                    }
+                }
+                Message::MinSuggestionPercentChanged(percent) => {
+                    in_workspace.min_suggestion_percent = percent
+                }
+                Message::MinSuggestionPercentReleased => {
+                    let percent = Some(in_workspace.min_suggestion_percent);
+                    let stored = &mut in_workspace.translation_workspace.min_suggestion_percent;
+                    if *stored != percent {
+                        *stored = percent;
+                        in_workspace.unsaved_changes = true;
+                    }
+                }
+                Message::ToggleHistory(key) => {
+                    if !in_workspace.expanded_history.remove(&key) {
+                        in_workspace.expanded_history.insert(key);
+                    }
+                }
+                Message::ToggleMachineHistory(key) => {
+                    if !in_workspace.expanded_machine_history.remove(&key) {
+                        in_workspace.expanded_machine_history.insert(key);
+                    }
+                }
+                Message::ToggleLock(key) => {
+                    if let Some(segment) = in_workspace
+                        .translation_workspace
+                        .segments
+                        .segments
+                        .get_mut(&key)
+                    {
+                        segment.set_locked(!segment.locked);
+                        in_workspace.unsaved_changes = true;
+                    }
+                }
+                Message::ToggleKeepOutOfMemory(key) => {
+                    if let Some(segment) = in_workspace
+                        .translation_workspace
+                        .segments
+                        .segments
+                        .get_mut(&key)
+                    {
+                        segment.keep_out_of_memory = !segment.keep_out_of_memory;
+                        in_workspace.unsaved_changes = true;
+                    }
+                }
+                Message::NoteInput((key, new_value)) => {
+                    if let Some(segment) = in_workspace
+                        .translation_workspace
+                        .segments
+                        .segments
+                        .get_mut(&key)
+                    {
+                        segment.note = Some(new_value).filter(|note| !note.trim().is_empty());
+                        in_workspace.unsaved_changes = true;
+                    }
+                }
+                Message::ToggleOnlyNoted => in_workspace.only_noted = !in_workspace.only_noted,
+                Message::RevertToMachine(key) => {
+                    if let Some(segment) = in_workspace
+                        .translation_workspace
+                        .segments
+                        .segments
+                        .get_mut(&key)
+                        .filter(|segment| !segment.locked)
+                    {
+                        if segment.revert_to_machine() {
+                            in_workspace.unsaved_changes = true;
+                            self.status =
+                                Some(format!("reverted {key} to the machine translation"));
+                        }
+                    }
+                }
             },
         }
         Command::none()
@@ -619,10 +1956,18 @@ impl Application for TlumokState {
             .align_items(iced::Alignment::Center)
             .width(Length::Fill)
             .push(match &self.app_mode {
-                AppMode::PickingFile(PickingFile { current_dir }) => {
-                    or_error(file_picker(&current_dir))
+                AppMode::Dashboard(dashboard) => dashboard.view(),
+                AppMode::PickingFile(PickingFile { current_dir }) => column()
+                    .spacing(10)
+                    .push(
+                        button("workspaces in this directory")
+                            .on_press(Message::ShowDashboard(current_dir.clone())),
+                    )
+                    .push(or_error(file_picker(current_dir)))
+                    .into(),
+                AppMode::InWorkspace(in_workspace) => {
+                    in_workspace.view(&self.config.not_translated_marker, self.suggestions_hidden)
                 }
-                AppMode::InWorkspace(in_workspace) => in_workspace.view(),
             });
         let errors = match &self.error {
             Some(e) => column().push(text(format!("{e:#?}")).color([0.7, 0., 0.])),
@@ -632,12 +1977,40 @@ impl Application for TlumokState {
             .width(Length::Fill)
             .size(30)
             .horizontal_alignment(Horizontal::Center);
+        let status_bar = row()
+            .spacing(20)
+            .push(
+                text(self.status.as_deref().unwrap_or_default())
+                    .size(16)
+                    .width(Length::Fill),
+            )
+            .push(
+                text(match self.unsaved_changes() {
+                    true => "unsaved changes",
+                    false => "",
+                })
+                .size(16),
+            );
+        let exit_prompt = match self.exit_requested {
+            true => row()
+                .spacing(20)
+                .push(
+                    text("there are unsaved changes, save them before quitting?")
+                        .width(Length::Fill),
+                )
+                .push(button("save and quit").on_press(Message::SaveAndExit))
+                .push(button("quit without saving").on_press(Message::ExitWithoutSaving))
+                .push(button("cancel").on_press(Message::ExitCancelled)),
+            false => row(),
+        };
         let content = column()
             .width(Length::Fill)
             .spacing(10)
             .push(navbar)
             .push(main_view)
-            .push(errors.width(Length::Fill).height(Length::Shrink));
+            .push(errors.width(Length::Fill).height(Length::Shrink))
+            .push(exit_prompt)
+            .push(status_bar);
         let app = container(content)
             .width(Length::Fill)
             .center_x()
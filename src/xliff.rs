@@ -0,0 +1,297 @@
+//! XLIFF 1.2 interchange, so that workspaces can be worked on in other CAT tools
+//!
+//! every segment becomes a `<trans-unit>` with the segment key as its id,
+//! segment status is reflected in the `state` attribute of `<target>` (and `approved` of the unit)
+use super::*;
+use quick_xml::events::Event;
+
+fn escaped(text: &str) -> String {
+    String::from_utf8_lossy(&quick_xml::escape::escape(text.as_bytes())).into_owned()
+}
+
+fn xliff_state(status: SegmentStatus) -> &'static str {
+    match status {
+        SegmentStatus::Untranslated => "needs-translation",
+        SegmentStatus::MachineTranslated => "needs-review-translation",
+        SegmentStatus::Confirmed | SegmentStatus::Locked => "final",
+        SegmentStatus::EditedAfterConfirm => "translated",
+    }
+}
+
+/// states which mean that the translator signed off on the target
+const CONFIRMED_STATES: &[&str] = &["final", "signed-off"];
+
+/// translated unit read from an xliff file
+#[derive(Debug, Clone, Default)]
+pub struct XliffUnit {
+    pub id: String,
+    pub source: String,
+    pub target: Option<String>,
+    pub confirmed: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImportSummary {
+    pub updated: usize,
+    pub confirmed: usize,
+    pub skipped: usize,
+}
+
+impl TranslationWorkspace {
+    pub fn default_xliff_path_for_document(
+        OriginalDocument { path, .. }: &OriginalDocument,
+    ) -> PathBuf {
+        path.with_extension("tlumok.xlf")
+    }
+
+    pub fn to_xliff(&self) -> String {
+        let Self {
+            original_document: OriginalDocument { path, file_format },
+            translation_options:
+                TlumokTranslationOptions {
+                    source_language,
+                    target_language,
+                    ..
+                },
+            segments: TranslationSegments { segments },
+            ..
+        } = self;
+        let mut xliff = String::new();
+        xliff.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xliff.push_str("<xliff version=\"1.2\" xmlns=\"urn:oasis:names:tc:xliff:document:1.2\">\n");
+        xliff.push_str(&format!(
+            "  <file original=\"{}\" source-language=\"{}\" target-language=\"{}\" datatype=\"{}\">\n    <body>\n",
+            escaped(&path.display().to_string()),
+            source_language.to_deepl_language_static().to_lowercase(),
+            target_language.to_deepl_language_static().to_lowercase(),
+            match file_format {
+                FileFormat::Txt => "plaintext",
+                FileFormat::Adoc | FileFormat::Rst => "x-text",
+                FileFormat::Epub => "xhtml",
+                FileFormat::Docx => "x-docx",
+                FileFormat::Pdf => "pdf",
+            },
+        ));
+        for (key, segment) in segments.iter() {
+            let status = segment.status();
+            xliff.push_str(&format!(
+                "      <trans-unit id=\"{}\" approved=\"{}\" translate=\"{}\" xml:space=\"preserve\">\n        <source>{}</source>\n",
+                escaped(key),
+                match status.is_final() {
+                    true => "yes",
+                    false => "no",
+                },
+                match segment.locked {
+                    true => "no",
+                    false => "yes",
+                },
+                escaped(&segment.original_text),
+            ));
+            if let Some(translated_text) = segment.translated_text.as_ref() {
+                xliff.push_str(&format!(
+                    "        <target state=\"{}\">{}</target>\n",
+                    xliff_state(status),
+                    escaped(translated_text),
+                ));
+            }
+            xliff.push_str("      </trans-unit>\n");
+        }
+        xliff.push_str("    </body>\n  </file>\n</xliff>\n");
+        xliff
+    }
+
+    pub async fn export_xliff(&self, output_path: &Path) -> Result<()> {
+        filesystem::write_atomically(output_path, self.to_xliff().as_bytes())
+            .await
+            .wrap_err_with(|| format!("writing xliff to [{output_path:?}]"))
+    }
+
+    /// applies translated targets to segments with matching keys, units that are marked
+    /// as final / approved get confirmed
+    pub fn import_xliff_units(mut self, units: Vec<XliffUnit>) -> (Self, ImportSummary) {
+        let mut summary = ImportSummary::default();
+        for XliffUnit {
+            id,
+            source,
+            target,
+            confirmed,
+        } in units
+        {
+            let segment = match self.segments.segments.get_mut(&id) {
+                Some(segment) => segment,
+                None => {
+                    tracing::warn!("unit [{id}] does not match any segment, skipping");
+                    summary.skipped += 1;
+                    continue;
+                }
+            };
+            if segment.locked {
+                tracing::warn!("segment [{id}] is locked, skipping");
+                summary.skipped += 1;
+                continue;
+            }
+            if segment.original_text != source {
+                tracing::warn!("source of unit [{id}] differs from the original text of the segment, skipping\nunit source: [{source}]\nsegment original text: [{}]", segment.original_text);
+                summary.skipped += 1;
+                continue;
+            }
+            let target = match target {
+                Some(target) => target,
+                None => continue,
+            };
+            if segment.translated_text.as_ref() != Some(&target) {
+                segment.translated_text = Some(target);
+                summary.updated += 1;
+            }
+            if confirmed && !segment.status().is_final() {
+                segment.confirm();
+                summary.confirmed += 1;
+            }
+        }
+        (self, summary)
+    }
+
+    pub async fn import_xliff(self, xliff_path: &Path) -> Result<(Self, ImportSummary)> {
+        let content = tokio::fs::read_to_string(xliff_path)
+            .await
+            .wrap_err_with(|| format!("reading xliff from [{xliff_path:?}]"))?;
+        let units = parse_units(&content)
+            .wrap_err_with(|| format!("parsing xliff file [{xliff_path:?}]"))?;
+        Ok(self.import_xliff_units(units))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Element {
+    Source,
+    Target,
+}
+
+/// units of the file, only the `<source>` and `<target>` right inside of a `<trans-unit>`
+/// are read, the ones of `<alt-trans>` candidates (and the like) are skipped
+pub fn parse_units(content: &str) -> Result<Vec<XliffUnit>> {
+    let mut reader = quick_xml::Reader::from_str(content);
+    let mut buf = vec![];
+    let mut units = vec![];
+    let mut unit: Option<XliffUnit> = None;
+    let mut element: Option<Element> = None;
+    let mut target_state: Option<String> = None;
+    // elements open inside of the current unit
+    let mut depth = 0usize;
+    loop {
+        match reader
+            .read_event(&mut buf)
+            .wrap_err_with(|| format!("at position {}", reader.buffer_position()))?
+        {
+            Event::Start(start) => match start.local_name() {
+                b"trans-unit" => {
+                    let id = start
+                        .try_get_attribute("id")?
+                        .ok_or_else(|| eyre::eyre!("trans-unit without an id"))?
+                        .unescape_and_decode_value(&reader)?;
+                    let approved = start
+                        .try_get_attribute("approved")?
+                        .map(|approved| approved.unescape_and_decode_value(&reader))
+                        .transpose()?;
+                    unit = Some(XliffUnit {
+                        id,
+                        confirmed: approved.as_deref() == Some("yes"),
+                        ..Default::default()
+                    });
+                    depth = 0;
+                }
+                name => {
+                    match (unit.as_mut(), depth, name) {
+                        (Some(_), 0, b"source") => element = Some(Element::Source),
+                        (Some(unit), 0, b"target") => {
+                            element = Some(Element::Target);
+                            target_state = start
+                                .try_get_attribute("state")?
+                                .map(|state| state.unescape_and_decode_value(&reader))
+                                .transpose()?;
+                            unit.target = Some(String::new());
+                        }
+                        _ => {}
+                    }
+                    depth += 1;
+                }
+            },
+            Event::Text(text) => {
+                let text = text.unescape_and_decode(&reader)?;
+                match (unit.as_mut(), element) {
+                    (Some(unit), Some(Element::Source)) => unit.source.push_str(&text),
+                    (Some(unit), Some(Element::Target)) => {
+                        unit.target.get_or_insert_with(String::new).push_str(&text)
+                    }
+                    _ => {}
+                }
+            }
+            Event::CData(text) => {
+                let text = reader.decode(&text.into_inner())?.to_owned();
+                match (unit.as_mut(), element) {
+                    (Some(unit), Some(Element::Source)) => unit.source.push_str(&text),
+                    (Some(unit), Some(Element::Target)) => {
+                        unit.target.get_or_insert_with(String::new).push_str(&text)
+                    }
+                    _ => {}
+                }
+            }
+            Event::End(end) => match end.local_name() {
+                b"trans-unit" => {
+                    if let Some(mut unit) = unit.take() {
+                        unit.confirmed = unit.confirmed
+                            || target_state
+                                .take()
+                                .map(|state| CONFIRMED_STATES.contains(&state.as_str()))
+                                .unwrap_or_default();
+                        units.push(unit);
+                    }
+                }
+                _ => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        element = None;
+                    }
+                }
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alternative_translations_are_skipped() -> Result<()> {
+        let units = parse_units(include_str!("../test-data/alt-trans.xlf"))?;
+        let units: Vec<_> = units
+            .into_iter()
+            .map(
+                |XliffUnit {
+                     id,
+                     source,
+                     target,
+                     confirmed,
+                 }| (id, source, target, confirmed),
+            )
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (
+                    "segment_0".to_owned(),
+                    "Hello & welcome.".to_owned(),
+                    Some("Witaj & <b>.".to_owned()),
+                    true
+                ),
+                ("segment_1".to_owned(), "Bold text".to_owned(), None, false),
+            ]
+        );
+        Ok(())
+    }
+}